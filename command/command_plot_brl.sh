@@ -1,2 +1,2 @@
 #!/bin/bash
-cargo run --release -- -y BTCBRL -m 15 -s "2020-12-24 00:00:00" -e "2020-12-25 00:00:00" plot
+cargo run --release -- -y BTCBRL -k spot -m 15 -s "2020-12-24 00:00:00" -e "2020-12-25 00:00:00" plot
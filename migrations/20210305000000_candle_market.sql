@@ -0,0 +1,8 @@
+-- Market kind (spot, futures) of candle, candles already stored came from futures market
+ALTER TABLE candle ADD COLUMN market character varying(8) NOT NULL DEFAULT 'futures'
+;
+ALTER TABLE candle ALTER COLUMN market DROP DEFAULT
+;
+ALTER TABLE candle DROP CONSTRAINT start_time
+;
+ALTER TABLE candle ADD CONSTRAINT start_time UNIQUE (symbol, minutes, market, open_time)
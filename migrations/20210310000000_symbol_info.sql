@@ -0,0 +1,14 @@
+-- Exchange trading rules of symbols, refreshed from exchange info
+CREATE TABLE symbol_info
+(
+    symbol character varying(20) NOT NULL,
+    market character varying(8) NOT NULL,
+    base_asset character varying(12) NOT NULL,
+    quote_asset character varying(12) NOT NULL,
+    tick_size numeric(20,8) NOT NULL,
+    step_size numeric(20,8) NOT NULL,
+    min_qty numeric(20,8) NOT NULL,
+    min_notional numeric(20,8) NOT NULL,
+    updated_at timestamp with time zone NOT NULL,
+    CONSTRAINT symbol_info_pkey PRIMARY KEY (symbol, market)
+)
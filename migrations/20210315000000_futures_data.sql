@@ -0,0 +1,20 @@
+-- Funding rate history of perpetual futures symbols
+CREATE TABLE funding_rate
+(
+    symbol character varying(20) NOT NULL,
+    funding_time timestamp with time zone NOT NULL,
+    funding_rate numeric(20,8) NOT NULL,
+    mark_price numeric(20,8),
+    CONSTRAINT funding_rate_pkey PRIMARY KEY (symbol, funding_time)
+)
+;
+-- Open interest statistics of futures symbols, a snapshot each interval
+CREATE TABLE open_interest
+(
+    symbol character varying(20) NOT NULL,
+    minutes numeric(5,0) NOT NULL,
+    time timestamp with time zone NOT NULL,
+    sum_open_interest numeric(28,8) NOT NULL,
+    sum_open_interest_value numeric(28,8) NOT NULL,
+    CONSTRAINT open_interest_pkey PRIMARY KEY (symbol, minutes, time)
+)
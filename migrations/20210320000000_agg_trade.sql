@@ -0,0 +1,14 @@
+-- Aggregate trades, imported to build candles of any interval
+CREATE TABLE agg_trade
+(
+    symbol character varying(20) NOT NULL,
+    market character varying(8) NOT NULL,
+    id bigint NOT NULL,
+    price numeric(20,8) NOT NULL,
+    quantity numeric(28,8) NOT NULL,
+    time timestamp with time zone NOT NULL,
+    is_buyer_maker boolean NOT NULL,
+    CONSTRAINT agg_trade_pkey PRIMARY KEY (symbol, market, id)
+)
+;
+CREATE INDEX agg_trade_time_idx ON agg_trade (symbol, market, time);
@@ -0,0 +1,8 @@
+-- Candle ids come from a sequence, so syncs running at the same time don't collide on MAX(id) + 1
+CREATE SEQUENCE candle_id_seq AS bigint OWNED BY candle.id
+;
+SELECT setval('candle_id_seq', COALESCE((SELECT MAX(id) FROM candle), 0)::bigint + 1, false)
+;
+ALTER TABLE candle ALTER COLUMN id TYPE numeric(20,0)
+;
+ALTER TABLE candle ALTER COLUMN id SET DEFAULT nextval('candle_id_seq')
@@ -0,0 +1,4 @@
+-- Candles revised by exchange after import are updated in place, counting revisions and keeping the last revision time
+ALTER TABLE candle ADD COLUMN revision integer NOT NULL DEFAULT 0
+;
+ALTER TABLE candle ADD COLUMN revised_at timestamp with time zone NULL
@@ -0,0 +1,90 @@
+-- Positions, orders, profits and balance flows of back test and live runs, simulated ones flagged apart
+CREATE TABLE position
+(
+    id bigint GENERATED BY DEFAULT AS IDENTITY,
+    symbol character varying(20) NOT NULL,
+    state character varying(8) NOT NULL,
+    balance numeric(5,2) NOT NULL,
+    balance_usd numeric(20,8) NOT NULL,
+    balance_amount numeric(20,8) NOT NULL,
+    balance_minimum numeric(20,8) NOT NULL,
+    simulation boolean NOT NULL,
+    active boolean NOT NULL,
+    current_profit bigint NULL,
+    CONSTRAINT position_pkey PRIMARY KEY (id),
+    -- Orders and profits reference position with its simulation flag, so they can't mix
+    CONSTRAINT position_simulation UNIQUE (id, simulation)
+)
+;
+CREATE INDEX position_symbol_idx ON position (symbol, simulation)
+;
+-- "order" is a reserved word
+CREATE TABLE trade_order
+(
+    id bigint GENERATED BY DEFAULT AS IDENTITY,
+    symbol character varying(20) NOT NULL,
+    exchange character varying(20) NOT NULL,
+    date timestamp with time zone NOT NULL,
+    price numeric(20,8) NOT NULL,
+    avg_execution_price numeric(20,8) NOT NULL,
+    side character varying(4) NOT NULL,
+    order_type character varying(12) NOT NULL,
+    is_live boolean NOT NULL,
+    is_cancelled boolean NOT NULL,
+    is_hidden boolean NOT NULL,
+    was_forced boolean NOT NULL,
+    original_amount numeric(20,8) NOT NULL,
+    remaining_amount numeric(20,8) NOT NULL,
+    executed_amount numeric(20,8) NOT NULL,
+    source character varying(20) NOT NULL,
+    order_id bigint NULL,
+    position bigint NOT NULL,
+    profit bigint NULL,
+    simulation boolean NOT NULL,
+    CONSTRAINT trade_order_pkey PRIMARY KEY (id),
+    CONSTRAINT trade_order_position FOREIGN KEY (position, simulation) REFERENCES position (id, simulation) ON DELETE CASCADE
+)
+;
+CREATE INDEX trade_order_position_idx ON trade_order (position, date)
+;
+CREATE TABLE profit
+(
+    id bigint GENERATED BY DEFAULT AS IDENTITY,
+    symbol character varying(20) NOT NULL,
+    position bigint NOT NULL,
+    sold_date timestamp with time zone NOT NULL,
+    sold_price numeric(20,8) NOT NULL,
+    sold_amount numeric(20,8) NOT NULL,
+    bought_date timestamp with time zone NOT NULL,
+    bought_price numeric(20,8) NOT NULL,
+    bought_amount numeric(20,8) NOT NULL,
+    bought_usd numeric(20,8) NOT NULL,
+    sold_usd numeric(20,8) NOT NULL,
+    bought_order bigint NOT NULL REFERENCES trade_order (id) ON DELETE CASCADE,
+    sold_order bigint NOT NULL REFERENCES trade_order (id) ON DELETE CASCADE,
+    profit numeric(20,8) NOT NULL,
+    simulation boolean NOT NULL,
+    percent numeric(7,2) NOT NULL,
+    CONSTRAINT profit_pkey PRIMARY KEY (id),
+    CONSTRAINT profit_position FOREIGN KEY (position, simulation) REFERENCES position (id, simulation) ON DELETE CASCADE
+)
+;
+CREATE INDEX profit_position_idx ON profit (position, sold_date)
+;
+CREATE TABLE flow
+(
+    id bigint GENERATED BY DEFAULT AS IDENTITY,
+    profit bigint NULL REFERENCES profit (id) ON DELETE SET NULL,
+    position bigint NOT NULL REFERENCES position (id) ON DELETE CASCADE,
+    date timestamp with time zone NOT NULL,
+    price numeric(20,8) NOT NULL,
+    amount numeric(20,8) NOT NULL,
+    usd numeric(20,8) NOT NULL,
+    original_usd numeric(20,8) NOT NULL,
+    balance_amount numeric(20,8) NOT NULL,
+    balance_usd numeric(20,8) NOT NULL,
+    state character varying(8) NOT NULL,
+    CONSTRAINT flow_pkey PRIMARY KEY (id)
+)
+;
+CREATE INDEX flow_position_idx ON flow (position, date)
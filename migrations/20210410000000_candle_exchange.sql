@@ -0,0 +1,11 @@
+-- Exchange of candle, part of its key next to market; candles already stored came from Binance.
+-- Symbols as long as exchanges list (e.g. 1000SHIBUSDT) fit, like on other tables
+ALTER TABLE candle ALTER COLUMN symbol TYPE character varying(20)
+;
+ALTER TABLE candle ADD COLUMN exchange character varying(20) NOT NULL DEFAULT 'binance'
+;
+ALTER TABLE candle ALTER COLUMN exchange DROP DEFAULT
+;
+ALTER TABLE candle DROP CONSTRAINT start_time
+;
+ALTER TABLE candle ADD CONSTRAINT start_time UNIQUE (exchange, symbol, minutes, market, open_time)
@@ -0,0 +1,20 @@
+-- Candles of higher intervals aggregated from stored candles of a lower interval (source), kept apart from exchange candles.
+-- Buckets with gaps on source candles have fewer sources than expected, they are incomplete
+CREATE TABLE resampled_candle
+(
+    exchange character varying(20) NOT NULL,
+    symbol character varying(20) NOT NULL,
+    minutes numeric(5,0) NOT NULL,
+    market character varying(8) NOT NULL,
+    open_time timestamp with time zone NOT NULL,
+    close_time timestamp with time zone NOT NULL,
+    open numeric(20,8) NOT NULL,
+    high numeric(20,8) NOT NULL,
+    low numeric(20,8) NOT NULL,
+    close numeric(20,8) NOT NULL,
+    volume numeric(28,8) NOT NULL,
+    source_minutes numeric(5,0) NOT NULL,
+    sources integer NOT NULL,
+    expected integer NOT NULL,
+    CONSTRAINT resampled_candle_pkey PRIMARY KEY (exchange, symbol, minutes, market, open_time)
+)
@@ -0,0 +1,19 @@
+-- Candles breaking integrity rules moved out of candle by validate --quarantine, kept with their id and reason for review
+CREATE TABLE candle_quarantine
+(
+    id numeric(20,0) NOT NULL,
+    exchange character varying(20) NOT NULL,
+    symbol character varying(20) NOT NULL,
+    minutes numeric(5,0) NOT NULL,
+    market character varying(8) NOT NULL,
+    open_time timestamp with time zone NOT NULL,
+    close_time timestamp with time zone NOT NULL,
+    open numeric(20,8) NOT NULL,
+    high numeric(20,8) NOT NULL,
+    low numeric(20,8) NOT NULL,
+    close numeric(20,8) NOT NULL,
+    volume numeric(20,8) NOT NULL,
+    reason text NOT NULL,
+    quarantined_at timestamp with time zone NOT NULL DEFAULT now(),
+    CONSTRAINT candle_quarantine_pkey PRIMARY KEY (id)
+)
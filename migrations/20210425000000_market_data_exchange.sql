@@ -0,0 +1,37 @@
+-- Exchange of symbol info, funding rates, open interests and aggregate trades, part of their keys like on candle;
+-- rows already stored came from Binance.
+ALTER TABLE symbol_info ADD COLUMN exchange character varying(20) NOT NULL DEFAULT 'binance'
+;
+ALTER TABLE symbol_info ALTER COLUMN exchange DROP DEFAULT
+;
+ALTER TABLE symbol_info DROP CONSTRAINT symbol_info_pkey
+;
+ALTER TABLE symbol_info ADD CONSTRAINT symbol_info_pkey PRIMARY KEY (exchange, symbol, market)
+;
+ALTER TABLE funding_rate ADD COLUMN exchange character varying(20) NOT NULL DEFAULT 'binance'
+;
+ALTER TABLE funding_rate ALTER COLUMN exchange DROP DEFAULT
+;
+ALTER TABLE funding_rate DROP CONSTRAINT funding_rate_pkey
+;
+ALTER TABLE funding_rate ADD CONSTRAINT funding_rate_pkey PRIMARY KEY (exchange, symbol, funding_time)
+;
+ALTER TABLE open_interest ADD COLUMN exchange character varying(20) NOT NULL DEFAULT 'binance'
+;
+ALTER TABLE open_interest ALTER COLUMN exchange DROP DEFAULT
+;
+ALTER TABLE open_interest DROP CONSTRAINT open_interest_pkey
+;
+ALTER TABLE open_interest ADD CONSTRAINT open_interest_pkey PRIMARY KEY (exchange, symbol, minutes, time)
+;
+ALTER TABLE agg_trade ADD COLUMN exchange character varying(20) NOT NULL DEFAULT 'binance'
+;
+ALTER TABLE agg_trade ALTER COLUMN exchange DROP DEFAULT
+;
+ALTER TABLE agg_trade DROP CONSTRAINT agg_trade_pkey
+;
+ALTER TABLE agg_trade ADD CONSTRAINT agg_trade_pkey PRIMARY KEY (exchange, symbol, market, id)
+;
+DROP INDEX agg_trade_time_idx
+;
+CREATE INDEX agg_trade_time_idx ON agg_trade (exchange, symbol, market, time)
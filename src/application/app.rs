@@ -1,35 +1,49 @@
 use super::{
     candles_provider::{CandlesProvider, CandlesProviderBuffer, CandlesProviderBufferSingleton, CandlesProviderSelection},
+    export_selection::{export_candles, ExportFormat, TacExport},
+    live_feed::{LiveFeed, SOURCE_LIVE},
     plot_selection::plot_selection,
     streamer::Streamer,
 };
-use crate::strategy::back_test_runner::run_trader_back_test;
+use crate::strategy::{
+    back_test_runner::{run_trader_back_test, TraderFactory},
+    trade_ledger::TradeLedger,
+    trader_register::{Position, TraderRegister},
+};
 use crate::{
     checker::Checker,
     config::{definition::ConfigDefinition, selection::Selection},
     exchange::Exchange,
-    repository::Repository,
+    model::{market::Market, symbol_info::SymbolInfo},
+    repository::{candles_cursor::CandlesCursor, Repository},
     strategy::topbottom_triangle::topbottom_triangle,
+    tac_plotters::{futures_plotter::FuturesPlotter, indicator_plotter::IndicatorPlotter},
     technicals::topbottom::TopBottomTac,
     candles_utils::datetime_to_filename,
 };
-use chrono::Duration;
+use anyhow::Context;
+use ifmt::iformat;
 use log::info;
-use std::sync::{Arc, RwLock};
+use rust_decimal_macros::dec;
+use std::{fs::File, io::BufWriter, path::Path, sync::Arc};
 
 pub struct Application<'a> {
     pub definition: ConfigDefinition,
     pub selection: Selection,
     pub candles_provider: CandlesProviderBuffer,
+    pub repository: Arc<dyn Repository + Send + Sync>,
     pub synchronizer: &'a Checker<'a>,
 }
 
 impl<'a> Application<'a> {
-    pub fn new(repository: Repository, exchange: Exchange, synchronizer: &'a Checker<'a>, selection: Selection) -> Self {
-        let candles_provider_singleton = CandlesProviderBufferSingleton::new(repository, exchange);
+    pub fn new(
+        repository: Arc<dyn Repository + Send + Sync>, exchange: Arc<dyn Exchange + Send + Sync>, synchronizer: &'a Checker<'a>, selection: Selection,
+    ) -> Self {
+        let candles_provider_singleton = CandlesProviderBufferSingleton::new(repository.clone(), exchange);
         Application {
             synchronizer,
-            candles_provider: CandlesProviderBuffer::new(Arc::new(RwLock::new(candles_provider_singleton))),
+            repository,
+            candles_provider: CandlesProviderBuffer::new(Arc::new(candles_provider_singleton)),
             selection,
             definition: ConfigDefinition::new(),
         }
@@ -65,11 +79,61 @@ impl<'a> Application<'a> {
         streamer.run()
     }
 
+    pub fn run_live(&self, url: &str) -> anyhow::Result<()> {
+        let symbol_minutes = &self.selection.candles_selection.symbol_minutes;
+        // Orders aren't sent to exchange, position is simulated
+        let trader = TraderFactory::new(self.selection.candles_selection.clone(), self.candles_provider.clone()).create_trader();
+        let register = TraderRegister::new(Position::new_from_usd(dec!(1000))).with_symbol_info(self.symbol_info()?);
+        let ledger = TradeLedger::open(self.repository.clone(), register, symbol_minutes, true, SOURCE_LIVE)?;
+        info!("{}", iformat!("Live position {ledger.position().id}"));
+        let mut live_feed = LiveFeed::new(url, symbol_minutes, self.candles_provider.candles_provider_singleton()).with_trading(trader, ledger);
+        live_feed.run()
+    }
+
+    pub fn symbol_info(&self) -> anyhow::Result<SymbolInfo> {
+        let symbol_minutes = &self.selection.candles_selection.symbol_minutes;
+        let singleton = self.candles_provider.candles_provider_singleton();
+        async_std::task::block_on(singleton.symbol_info(&symbol_minutes.exchange, &symbol_minutes.symbol, &symbol_minutes.market))
+    }
+
     pub fn plot_selection(&mut self) -> anyhow::Result<()> {
         let selection = self.selection.clone();
         let candles_provider_selection = CandlesProviderSelection::new(self.candles_provider.clone(), selection.candles_selection.clone());
+        let lower_plotters = self.futures_plotters()?;
         let candles_provider = Box::new(candles_provider_selection);
-        plot_selection(selection, candles_provider, Vec::new())
+        plot_selection(selection, candles_provider, Vec::new(), lower_plotters)
+    }
+
+    /// Writes stored candles of selection with indicators of tacs to file, read page by page
+    pub fn export(&mut self, tacs: &[TacExport], format: &ExportFormat, path: &Path) -> anyhow::Result<()> {
+        let candles_selection = &self.selection.candles_selection;
+        let pages = CandlesCursor::new(
+            self.repository.clone(),
+            &candles_selection.symbol_minutes,
+            &candles_selection.start_time,
+            &candles_selection.end_time,
+        );
+        let writer = BufWriter::new(File::create(path).with_context(|| format!("Export file {:?} not writable", path))?);
+        export_candles(&self.selection, pages, tacs, *format, writer)?;
+        Ok(())
+    }
+
+    /// Funding rate and open interest plotter, when selection is on futures market and repository has them
+    fn futures_plotters(&self) -> anyhow::Result<Vec<Box<dyn IndicatorPlotter>>> {
+        let symbol_minutes = &self.selection.candles_selection.symbol_minutes;
+        if symbol_minutes.market != Market::Futures {
+            return Ok(Vec::new());
+        }
+        let mut candles_provider = CandlesProviderSelection::new(self.candles_provider.clone(), self.selection.candles_selection.clone());
+        let candles = candles_provider.candles()?;
+        let candles = candles.iter().collect::<Vec<_>>();
+        let singleton = self.candles_provider.candles_provider_singleton();
+        let futures_tac = async_std::task::block_on(singleton.futures_tac(symbol_minutes, &candles))?;
+        if futures_tac.funding_rate().series.is_empty() && futures_tac.open_interest().series.is_empty() {
+            info!("No funding rates or open interests on repository, see sync-funding and sync-open-interest commands");
+            return Ok(Vec::new());
+        }
+        Ok(vec![Box::new(FuturesPlotter::new(futures_tac))])
     }
 }
 
@@ -80,19 +144,19 @@ pub fn plot_triangles(selection: Selection, candles_provider: Box<dyn CandlesPro
     let topbottoms = topbottoms.iter().collect::<Vec<_>>();
     let topbottoms_ref = topbottoms.as_slice();
 
-    let minutes = selection.candles_selection.symbol_minutes.minutes;
+    let interval = selection.candles_selection.symbol_minutes.interval;
 
-    let triangles = topbottom_triangle(topbottoms_ref, &minutes);
+    let triangles = topbottom_triangle(topbottoms_ref, &interval);
     triangles.iter().for_each(|triangle| {
         let mut selection_par = selection.clone();
-        let open_time = triangle.open(&minutes);
-        let margin = Duration::minutes(minutes as i64 * 100);
+        let open_time = triangle.open(&interval);
+        let margin = interval.duration() * 100;
         selection_par.candles_selection.start_time = open_time - margin;
         selection_par.candles_selection.end_time = open_time + margin;
         selection_par.image_name = format!("out/triangle_{}.png", datetime_to_filename(&open_time));
         info!("Plotting triangle {}", selection_par.image_name);
 
-        plot_selection(selection_par, candles_provider.clone_provider(), Vec::new()).unwrap();
+        plot_selection(selection_par, candles_provider.clone_provider(), Vec::new(), Vec::new()).unwrap();
     });
     Ok(())
 }
@@ -2,16 +2,28 @@ use crate::{
     candles_range::candles_to_ranges_missing,
     config::{candles_selection::CandlesSelection, symbol_minutes::SymbolMinutes},
     exchange::Exchange,
-    model::{candle::Candle, open_close::OpenClose},
-    repository::Repository,
-    technicals::heikin_ashi,
+    model::{
+        candle::Candle,
+        exchange_name::ExchangeName,
+        funding_rate::{funding_period, FundingRate},
+        interval::Interval,
+        market::Market,
+        open_close::OpenClose,
+        open_interest::OpenInterest,
+        symbol_info::SymbolInfo,
+    },
+    repository::{repository_error::RepositoryResult, Repository},
+    technicals::{futures_tac::FuturesTac, heikin_ashi},
 };
 use anyhow::anyhow;
+use chrono::{DateTime, Duration, Utc};
 use ifmt::iformat;
-use log::debug;
+use log::{debug, warn};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use std::{
     collections::HashMap,
+    future::Future,
+    hash::Hash,
     sync::{Arc, RwLock},
     time::Instant,
 };
@@ -21,61 +33,264 @@ pub trait CandlesProvider {
     fn clone_provider(&self) -> Box<dyn CandlesProvider>;
 }
 
+/// Symbol info older than this is retrieved again from exchange
+const SYMBOL_INFO_MAX_AGE_HOURS: i64 = 24;
+/// Futures data buffer is loaded this further than asked, so a back test moving forward doesn't reload it on each candle
+const FUTURES_DATA_LOOKAHEAD_DAYS: i64 = 7;
+
+/// Items read from repository for a time range
+struct RangeBuffer<T> {
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    items: Vec<T>,
+}
+
+/// Items with time in range, read from repository when buffer of key doesn't cover range.
+///
+/// Buffers are locked only to look them up or replace them, never while reading repository.
+async fn range_buffered<K: Eq + Hash, T: Clone, F: Future<Output = RepositoryResult<Vec<T>>>>(
+    buffers: &RwLock<HashMap<K, RangeBuffer<T>>>, key: K, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>, time: impl Fn(&T) -> DateTime<Utc>,
+    read: impl FnOnce(DateTime<Utc>, DateTime<Utc>) -> F,
+) -> anyhow::Result<Vec<T>> {
+    let in_range = |items: &[T]| {
+        items
+            .iter()
+            .filter(|i| time(i) >= *start_time && time(i) <= *end_time)
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let (read_start, read_end) = match buffers.read().unwrap().get(&key) {
+        Some(b) if b.start_time <= *start_time && b.end_time >= *end_time => return Ok(in_range(&b.items)),
+        Some(b) => (b.start_time.min(*start_time), b.end_time.max(*end_time)),
+        None => (*start_time, *end_time),
+    };
+    let read_end = read_end + Duration::days(FUTURES_DATA_LOOKAHEAD_DAYS);
+    let items = read(read_start, read_end).await?;
+    let result = in_range(&items);
+    // Data after now may still arrive
+    let buffer = RangeBuffer {
+        start_time: read_start,
+        end_time: read_end.min(Utc::now()),
+        items,
+    };
+    buffers.write().unwrap().insert(key, buffer);
+    Ok(result)
+}
+
+/// Candles of every selection shared by providers.
+///
+/// Each cache has its own lock, held only to read or update it, so repository and exchange calls don't serialize callers.
 pub struct CandlesProviderBufferSingleton {
-    exchange: Exchange,
-    repository: Repository,
-    buffer: HashMap<SymbolMinutes, Vec<Candle>>,
+    exchange: Arc<dyn Exchange + Send + Sync>,
+    repository: Arc<dyn Repository + Send + Sync>,
+    buffer: RwLock<HashMap<SymbolMinutes, Vec<Candle>>>,
+    in_progress: RwLock<HashMap<SymbolMinutes, Candle>>,
+    symbols_info: RwLock<HashMap<(ExchangeName, String, Market), SymbolInfo>>,
+    funding_rates: RwLock<HashMap<(ExchangeName, String), RangeBuffer<FundingRate>>>,
+    open_interests: RwLock<HashMap<(ExchangeName, String, Interval), RangeBuffer<OpenInterest>>>,
 }
 
 impl CandlesProviderBufferSingleton {
-    pub fn new(repository: Repository, exchange: Exchange) -> Self {
+    pub fn new(repository: Arc<dyn Repository + Send + Sync>, exchange: Arc<dyn Exchange + Send + Sync>) -> Self {
         Self {
             exchange,
             repository,
-            buffer: HashMap::new(),
+            buffer: RwLock::new(HashMap::new()),
+            in_progress: RwLock::new(HashMap::new()),
+            symbols_info: RwLock::new(HashMap::new()),
+            funding_rates: RwLock::new(HashMap::new()),
+            open_interests: RwLock::new(HashMap::new()),
         }
     }
 
-    fn candles(&mut self, candles_selection: CandlesSelection) -> anyhow::Result<Vec<Candle>> {
-        let start = Instant::now();
-        debug!("Initializing import...");
+    /// Funding rates on repository with funding time between start and end time
+    pub async fn funding_rates(
+        &self, exchange: &ExchangeName, symbol: &str, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> anyhow::Result<Vec<FundingRate>> {
+        let repository = &self.repository;
+        range_buffered(
+            &self.funding_rates,
+            (*exchange, symbol.to_string()),
+            start_time,
+            end_time,
+            |f| f.funding_time,
+            |st, et| async move { repository.funding_rates(exchange, symbol, &st, &et).await },
+        )
+        .await
+    }
+
+    /// Open interest snapshots on repository with time between start and end time
+    pub async fn open_interests(
+        &self, exchange: &ExchangeName, symbol: &str, interval: &Interval, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> anyhow::Result<Vec<OpenInterest>> {
+        let repository = &self.repository;
+        range_buffered(
+            &self.open_interests,
+            (*exchange, symbol.to_string(), *interval),
+            start_time,
+            end_time,
+            |o| o.time,
+            |st, et| async move { repository.open_interests(exchange, symbol, interval, &st, &et).await },
+        )
+        .await
+    }
+
+    /// Funding rate and open interest of symbol minutes aligned to candles
+    pub async fn futures_tac(&self, symbol_minutes: &SymbolMinutes, candles: &[&Candle]) -> anyhow::Result<FuturesTac> {
+        let (start_time, end_time) = match (candles.first(), candles.last()) {
+            (Some(first), Some(last)) => (first.open_time, last.close_time),
+            _ => return Ok(FuturesTac::new(candles, &[], &[])),
+        };
+        let exchange = &symbol_minutes.exchange;
+        let symbol = &symbol_minutes.symbol;
+        let interval = &symbol_minutes.interval;
+        // Values in effect at first candle came before it
+        let funding_rates = self.funding_rates(exchange, symbol, &(start_time - funding_period()), &end_time).await?;
+        let open_interests = self
+            .open_interests(exchange, symbol, interval, &(start_time - interval.duration()), &end_time)
+            .await?;
+        Ok(FuturesTac::new(candles, &funding_rates, &open_interests))
+    }
+
+    /// Symbol trading rules, from memory, repository or exchange (then saved on repository)
+    pub async fn symbol_info(&self, exchange: &ExchangeName, symbol: &str, market: &Market) -> anyhow::Result<SymbolInfo> {
+        let key = (*exchange, symbol.to_string(), *market);
+        let is_fresh = |s: &SymbolInfo| Utc::now() - s.updated_at < Duration::hours(SYMBOL_INFO_MAX_AGE_HOURS);
 
-        fn candles_to_buf(heikin_ashi: bool, candles: &mut Vec<Candle>, buff: &mut Vec<Candle>) {
-            if heikin_ashi {
-                let candles = candles.iter().collect::<Vec<_>>();
-                let candles_ref = candles.as_slice();
-                let mut candles = heikin_ashi::heikin_ashi(candles_ref);
-                buff.append(&mut candles);
-            } else {
-                buff.append(candles);
+        if let Some(symbol_info) = self.symbols_info.read().unwrap().get(&key).filter(|s| is_fresh(s)) {
+            return Ok(symbol_info.clone());
+        }
+
+        let symbol_info = match self.repository.symbol_info(exchange, symbol, market).await?.filter(|s| is_fresh(s)) {
+            Some(symbol_info) => symbol_info,
+            None => {
+                debug!("{}", iformat!("Retrieving exchange info {market}..."));
+                let symbols_info = self.exchange.symbols_info(market)?;
+                self.repository.save_symbols_info(&symbols_info).await?;
+                symbols_info
+                    .into_iter()
+                    .find(|s| &s.exchange == exchange && s.symbol == symbol)
+                    .ok_or_else(|| anyhow!("Symbol {} not found on {} {} exchange info", symbol, exchange, market))?
             }
-            buff.sort();
+        };
+        self.symbols_info.write().unwrap().insert(key, symbol_info.clone());
+        Ok(symbol_info)
+    }
+
+    /// Candle not closed yet, it is not on buffer neither on repository
+    pub fn in_progress(&self, symbol_minutes: &SymbolMinutes) -> Option<Candle> {
+        self.in_progress.read().unwrap().get(symbol_minutes).cloned()
+    }
+
+    pub fn set_in_progress(&self, symbol_minutes: &SymbolMinutes, candle: Candle) {
+        self.in_progress.write().unwrap().insert(symbol_minutes.clone(), candle);
+    }
+
+    /// Adds candles to buffer of symbol minutes, sorted and without repeated open time
+    fn buffer_append(&self, symbol_minutes: &SymbolMinutes, heikin_ashi: bool, candles: &mut Vec<Candle>) {
+        let mut candles = if heikin_ashi {
+            heikin_ashi::heikin_ashi(candles.iter().collect::<Vec<_>>().as_slice())
+        } else {
+            std::mem::take(candles)
+        };
+        let mut buffer = self.buffer.write().unwrap();
+        let buffer = buffer.entry(symbol_minutes.clone()).or_default();
+        buffer.append(&mut candles);
+        buffer.sort();
+        // Another caller may have loaded the same range meanwhile
+        buffer.dedup_by(|a, b| a.open_time == b.open_time);
+    }
+
+    /// Appends closed candles after last close time to repository and buffer, returns count appended
+    pub async fn append_closed(&self, symbol_minutes: &SymbolMinutes, candles: Vec<Candle>) -> anyhow::Result<usize> {
+        let last_close_time = self.repository.last_close_time(symbol_minutes).await?;
+        let mut candles = candles
+            .into_iter()
+            .filter(|c| last_close_time.map(|l| c.open_time > l).unwrap_or(true))
+            .collect::<Vec<_>>();
+        if candles.is_empty() {
+            return Ok(0);
         }
 
+        self.repository.insert_candles(&mut candles).await?;
+
+        {
+            let mut in_progress = self.in_progress.write().unwrap();
+            if let Some(candle) = in_progress.get(symbol_minutes) {
+                if candles.iter().any(|c| c.open_time >= candle.open_time) {
+                    in_progress.remove(symbol_minutes);
+                }
+            }
+        }
+
+        let count = candles.len();
+        self.buffer_append(symbol_minutes, false, &mut candles);
+        Ok(count)
+    }
+
+    /// Retrieves from exchange closed candles after last close time on repository
+    pub async fn backfill(&self, symbol_minutes: &SymbolMinutes) -> anyhow::Result<usize> {
+        let last_close_time = match self.repository.last_close_time(symbol_minutes).await? {
+            Some(last_close_time) => last_close_time,
+            None => return Ok(0),
+        };
+        let now = Utc::now();
+        let candles = self
+            .exchange
+            .candles(symbol_minutes, &Some(last_close_time), &None)?
+            .into_iter()
+            .filter(|c| c.close_time < now)
+            .collect::<Vec<_>>();
+        self.append_closed(symbol_minutes, candles).await
+    }
+
+    /// Candles of selection from buffer, missing ones are read from repository, then from exchange (and saved on repository)
+    pub async fn candles(&self, candles_selection: &CandlesSelection) -> anyhow::Result<Vec<Candle>> {
+        let start = Instant::now();
+        debug!("Initializing import...");
+
         // Normalize default start/end date time
         let start_time = &candles_selection.start_time;
         let end_time = &candles_selection.end_time;
-        let minutes = &candles_selection.symbol_minutes.minutes;
+        let interval = &candles_selection.symbol_minutes.interval;
         let symbol_minutes = &candles_selection.symbol_minutes;
 
-        let candles_buf = loop {
+        // Exchange is asked once for each missing range, holes it doesn't fill are left in the result
+        let mut exchange_queried = false;
+        loop {
             // Get candles from buffer
             debug!("Retrieving candles buffer {:?} {:?}...", start_time, end_time);
-            let mut candles_buf = self.buffer.entry(symbol_minutes.clone()).or_default();
-            debug!("Candles buffer count: {}", candles_buf.len());
-
-            debug!("Retrieving ranges missing from buffer...");
-            let ranges_missing_from_buffer = candles_to_ranges_missing(
-                &OpenClose::from_date(start_time, minutes),
-                &OpenClose::from_date(end_time, minutes),
-                &candles_selection.symbol_minutes.minutes,
-                candles_buf.iter().collect::<Vec<_>>().as_slice(),
-            )?;
-            debug!("Buffer ranges missing count: {}", ranges_missing_from_buffer.len());
-
-            if ranges_missing_from_buffer.is_empty() {
-                break candles_buf;
-            }
+            let ranges_missing_from_buffer = {
+                let buffer = self.buffer.read().unwrap();
+                let candles_buf = buffer.get(symbol_minutes).map(|b| b.iter().collect::<Vec<_>>()).unwrap_or_default();
+                debug!("Candles buffer count: {}", candles_buf.len());
+
+                debug!("Retrieving ranges missing from buffer...");
+                let ranges_missing_from_buffer = candles_to_ranges_missing(
+                    &OpenClose::from_date(start_time, interval),
+                    &OpenClose::from_date(end_time, interval),
+                    &candles_selection.symbol_minutes.interval,
+                    candles_buf.as_slice(),
+                )?;
+                debug!("Buffer ranges missing count: {}", ranges_missing_from_buffer.len());
+
+                if ranges_missing_from_buffer.is_empty() || exchange_queried {
+                    for (hole_start, hole_end) in ranges_missing_from_buffer.iter() {
+                        let hole = (hole_start.open(interval), hole_end.open(interval));
+                        warn!("{}", iformat!("Candles missing {symbol_minutes:?} {hole.0} - {hole.1}: not on exchange"));
+                    }
+                    let candles = candles_buf
+                        .par_iter()
+                        .filter(|c| &c.open_time >= start_time && &c.open_time <= end_time)
+                        .map(|c| (*c).clone())
+                        .collect::<Vec<_>>();
+
+                    debug!("{}", iformat!("Finished candles retrieve count: {candles.len()} elapsed: {start.elapsed():?}"));
+
+                    return Ok(candles);
+                }
+                ranges_missing_from_buffer
+            };
 
             for range_missing_from_buffer in ranges_missing_from_buffer.iter() {
                 let (start_time, end_time) = range_missing_from_buffer;
@@ -84,65 +299,54 @@ impl CandlesProviderBufferSingleton {
                 debug!("Retrieving candles repository {:?} {:?}...", start_time, end_time);
                 let mut candles_repo = self
                     .repository
-                    .candles_by_time(&candles_selection.symbol_minutes, &start_time.open(minutes), &end_time.open(minutes))
-                    .unwrap_or_default();
+                    .candles_by_time(&candles_selection.symbol_minutes, &start_time.open(interval), &end_time.open(interval))
+                    .await?;
                 debug!("Candles repository count: {}", candles_repo.len());
 
-                candles_to_buf(candles_selection.heikin_ashi, &mut candles_repo, &mut candles_buf);
-
                 // Get ranges missing
                 debug!("Retrieving ranges missing from repository {:?} {:?}...", start_time, end_time);
                 let ranges_missing_from_exchange = candles_to_ranges_missing(
-                    &start_time,
-                    &end_time,
-                    &candles_selection.symbol_minutes.minutes,
+                    start_time,
+                    end_time,
+                    &candles_selection.symbol_minutes.interval,
                     candles_repo.iter().collect::<Vec<_>>().as_slice(),
                 )?;
                 debug!("Repository ranges missing count: {}", ranges_missing_from_exchange.len());
-                if ranges_missing_from_exchange.is_empty() {
-                    break;
-                }
+
+                self.buffer_append(symbol_minutes, candles_selection.heikin_ashi, &mut candles_repo);
 
                 for range_missing_from_exchange in ranges_missing_from_exchange.iter() {
                     let (start_time, end_time) = range_missing_from_exchange;
 
                     debug!("Retrieving candles from exchange {:?} {:?}...", start_time, end_time);
-                    let mut candles_exch = self.exchange.candles(
+                    let mut candles_exch = self.exchange.candles_with_progress(
                         &candles_selection.symbol_minutes,
-                        &Some(start_time.open(minutes)),
-                        &Some(end_time.open(minutes)),
+                        &Some(start_time.open(interval)),
+                        &Some(end_time.open(interval)),
+                        &mut |p| debug!("Retrieved exchange page {}/{} candles: {}", p.page, p.pages, p.candles),
                     )?;
                     debug!("Candles exchange count: {}", candles_exch.len());
 
-                    // Save news candles on repository
-                    self.repository.insert_candles(&mut candles_exch)?;
+                    // Save news candles on repository, range bounds may be stored already
+                    self.repository.upsert_candles(&mut candles_exch).await?;
 
                     // Insert candles on buffer
-                    candles_to_buf(candles_selection.heikin_ashi, &mut candles_exch, &mut candles_buf);
+                    self.buffer_append(symbol_minutes, candles_selection.heikin_ashi, &mut candles_exch);
                 }
             }
-        };
-
-        let candles = candles_buf
-            .par_iter()
-            .filter(|c| &c.open_time >= start_time && &c.open_time <= end_time)
-            .cloned()
-            .collect::<Vec<_>>();
-
-        debug!("{}", iformat!("Finished candles retrieve count: {candles.len()} elapsed: {start.elapsed():?}"));
-
-        Ok(candles)
+            exchange_queried = true;
+        }
     }
 }
 
 #[derive(Clone)]
 pub struct CandlesProviderBuffer {
-    candles_provider_singleton: Arc<RwLock<CandlesProviderBufferSingleton>>,
+    candles_provider_singleton: Arc<CandlesProviderBufferSingleton>,
     candles_selection_opt: Option<CandlesSelection>,
 }
 
 impl CandlesProviderBuffer {
-    pub fn new(candles_provider_singleton: Arc<RwLock<CandlesProviderBufferSingleton>>) -> Self {
+    pub fn new(candles_provider_singleton: Arc<CandlesProviderBufferSingleton>) -> Self {
         Self {
             candles_provider_singleton,
             candles_selection_opt: None,
@@ -151,6 +355,10 @@ impl CandlesProviderBuffer {
     pub fn set_candles_selection(&mut self, candles_selection: CandlesSelection) {
         self.candles_selection_opt = Some(candles_selection);
     }
+
+    pub fn candles_provider_singleton(&self) -> Arc<CandlesProviderBufferSingleton> {
+        self.candles_provider_singleton.clone()
+    }
 }
 
 impl CandlesProvider for CandlesProviderBuffer {
@@ -158,14 +366,9 @@ impl CandlesProvider for CandlesProviderBuffer {
         let candles_selection = self
             .candles_selection_opt
             .as_ref()
-            .cloned()
             .ok_or_else(|| -> anyhow::Error { anyhow!("candles_selection not definied!") })?;
 
-        let m = &*self.candles_provider_singleton;
-
-        let mut c = m.write().unwrap();
-
-        c.candles(candles_selection)
+        async_std::task::block_on(self.candles_provider_singleton.candles(candles_selection))
     }
 
     fn clone_provider(&self) -> Box<dyn CandlesProvider> {
@@ -213,7 +416,7 @@ pub struct CandlesProviderVec {
 
 impl<'a> CandlesProviderVec {
     pub fn new(candles: &'a [Candle], last_n: usize) -> Self {
-        let start = (candles.len() - last_n).max(0);
+        let start = candles.len().saturating_sub(last_n);
         Self {
             candles: candles[start..candles.len()].to_vec(),
         }
@@ -266,29 +469,29 @@ where
 pub mod tests {
     use super::*;
     use crate::candles_utils::str_to_datetime;
+    use crate::exchange::replay_exchange::tests::replay_exchange;
+    use crate::model::{interval::Interval, market::Market};
+    use crate::repository::memory_repository::MemoryRepository;
     use crate::utils;
     use anyhow::Result;
     use log::LevelFilter;
 
-    #[test]
-    fn candles_provider_buffer_singleton_test() -> Result<()> {
+    #[async_std::test]
+    async fn candles_provider_buffer_singleton_test() -> Result<()> {
         utils::log_utils::setup_log(LevelFilter::Debug, module_path!());
 
-        dotenv::dotenv()?;
-        let exchange: Exchange = Exchange::new()?;
-        let repository: Repository = Repository::new()?;
-
-        repository.delete_all_candles()?;
-        let mut candles_provider_buffer_singleton = CandlesProviderBufferSingleton::new(repository, exchange);
+        let exchange = Arc::new(replay_exchange());
+        let candles_provider_buffer_singleton = CandlesProviderBufferSingleton::new(Arc::new(MemoryRepository::new()), exchange);
 
         {
             let candles_selection = CandlesSelection::new(
                 "BTCUSDT",
-                &15u32,
+                &Interval::M15,
+                &Market::Futures,
                 str_to_datetime("2020-11-11 10:00:00"),
                 str_to_datetime("2020-11-11 10:30:00"),
             );
-            let candles = candles_provider_buffer_singleton.candles(candles_selection);
+            let candles = candles_provider_buffer_singleton.candles(&candles_selection).await;
             assert!(candles.is_ok());
             assert_eq!(candles.unwrap().len(), 3);
         }
@@ -296,11 +499,12 @@ pub mod tests {
         {
             let candles_selection = CandlesSelection::new(
                 "BTCUSDT",
-                &15u32,
+                &Interval::M15,
+                &Market::Futures,
                 str_to_datetime("2020-11-11 11:00:00"),
                 str_to_datetime("2020-11-11 11:30:00"),
             );
-            let candles = candles_provider_buffer_singleton.candles(candles_selection);
+            let candles = candles_provider_buffer_singleton.candles(&candles_selection).await;
             assert!(candles.is_ok());
             assert_eq!(candles.unwrap().len(), 3);
         }
@@ -308,15 +512,122 @@ pub mod tests {
         {
             let candles_selection = CandlesSelection::new(
                 "BTCUSDT",
-                &15u32,
+                &Interval::M15,
+                &Market::Futures,
                 str_to_datetime("2020-11-11 10:00:00"),
                 str_to_datetime("2020-11-11 11:30:00"),
             );
-            let candles = candles_provider_buffer_singleton.candles(candles_selection);
+            let candles = candles_provider_buffer_singleton.candles(&candles_selection).await;
             assert!(candles.is_ok());
             assert_eq!(candles.unwrap().len(), 7);
         }
 
         Ok(())
     }
+    #[async_std::test]
+    async fn candles_provider_buffer_singleton_beyond_exchange_test() -> Result<()> {
+        let singleton = CandlesProviderBufferSingleton::new(Arc::new(MemoryRepository::new()), Arc::new(replay_exchange()));
+
+        // Exchange data ends at 2020-11-12 23:45
+        let candles_selection = CandlesSelection::new(
+            "BTCUSDT",
+            &Interval::M15,
+            &Market::Futures,
+            str_to_datetime("2020-11-12 23:00:00"),
+            str_to_datetime("2020-11-13 01:00:00"),
+        );
+        let candles = singleton.candles(&candles_selection).await?;
+        assert_eq!(candles.len(), 4);
+        assert_eq!(candles.last().unwrap().open_time, str_to_datetime("2020-11-12 23:45:00"));
+
+        Ok(())
+    }
+    #[test]
+    fn candles_provider_buffer_concurrent_test() -> Result<()> {
+        let singleton = Arc::new(CandlesProviderBufferSingleton::new(Arc::new(MemoryRepository::new()), Arc::new(replay_exchange())));
+        let candles_selection = CandlesSelection::new(
+            "BTCUSDT",
+            &Interval::M15,
+            &Market::Futures,
+            str_to_datetime("2020-11-11 10:00:00"),
+            str_to_datetime("2020-11-11 11:30:00"),
+        );
+
+        // Providers sharing singleton load the same range at once
+        let handles = (0..4)
+            .map(|_| {
+                let mut candles_provider = CandlesProviderBuffer::new(singleton.clone());
+                candles_provider.set_candles_selection(candles_selection.clone());
+                std::thread::spawn(move || candles_provider.candles().map(|c| c.len()))
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap()?, 7);
+        }
+
+        // Buffer keeps each candle once
+        assert_eq!(async_std::task::block_on(singleton.candles(&candles_selection))?.len(), 7);
+        assert_eq!(singleton.buffer.read().unwrap()[&candles_selection.symbol_minutes].len(), 7);
+        Ok(())
+    }
+    #[async_std::test]
+    async fn symbol_info_cache_test() -> Result<()> {
+        let repository = Arc::new(MemoryRepository::new());
+        let candles_provider_buffer_singleton = CandlesProviderBufferSingleton::new(repository.clone(), Arc::new(replay_exchange()));
+
+        // Not on repository, so it comes from exchange info and all market symbols are saved
+        let binance = &ExchangeName::Binance;
+        let symbol_info = candles_provider_buffer_singleton.symbol_info(binance, "ETHUSDT", &Market::Spot).await?;
+        assert_eq!(symbol_info.base_asset, "ETH");
+        assert_eq!(
+            repository.symbol_info(binance, "ETHUSDT", &Market::Spot).await?.unwrap().step_size,
+            symbol_info.step_size
+        );
+        assert!(repository.symbol_info(binance, "BTCUSDT", &Market::Spot).await?.is_some());
+
+        assert!(candles_provider_buffer_singleton.symbol_info(binance, "XXXUSDT", &Market::Spot).await.is_err());
+        Ok(())
+    }
+    #[async_std::test]
+    async fn futures_tac_test() -> Result<()> {
+        let repository = Arc::new(MemoryRepository::new());
+        let exchange = replay_exchange();
+        let start = str_to_datetime("2020-11-10 00:00:00");
+        let end = str_to_datetime("2020-11-12 23:59:59");
+        repository.insert_funding_rates(&exchange.funding_rates("BTCUSDT", &start, &end)?).await?;
+        repository
+            .insert_open_interests(&exchange.open_interests("BTCUSDT", &Interval::M15, &start, &end)?)
+            .await?;
+
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Futures);
+        let candles = exchange.candles(
+            &symbol_minutes,
+            &Some(str_to_datetime("2020-11-11 07:00:00")),
+            &Some(str_to_datetime("2020-11-11 09:00:00")),
+        )?;
+        let candles = candles.iter().collect::<Vec<_>>();
+        let singleton = CandlesProviderBufferSingleton::new(repository.clone(), Arc::new(exchange));
+        let futures_tac = singleton.futures_tac(&symbol_minutes, &candles).await?;
+
+        // Buffer serves a range it covers without reading repository
+        repository.delete_funding_rates(&ExchangeName::Binance, "BTCUSDT").await?;
+        repository.delete_open_interests(&ExchangeName::Binance, "BTCUSDT", &Interval::M15).await?;
+        let funding_rates = singleton
+            .funding_rates(
+                &ExchangeName::Binance,
+                "BTCUSDT",
+                &str_to_datetime("2020-11-11 00:00:00"),
+                &str_to_datetime("2020-11-11 12:00:00"),
+            )
+            .await?;
+
+        let funding_rate = &futures_tac.funding_rate().series;
+        assert_eq!(funding_rate.len(), candles.len());
+        // Funding at 00:00 in effect until 08:00
+        assert_eq!(funding_rate.first().unwrap().value, 0.00018262);
+        assert_eq!(funding_rate.last().unwrap().value, 0.0001);
+        assert_eq!(futures_tac.open_interest().series.len(), candles.len());
+        assert_eq!(funding_rates.len(), 2);
+        Ok(())
+    }
 }
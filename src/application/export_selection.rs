@@ -0,0 +1,516 @@
+use crate::{
+    config::selection::Selection,
+    model::candle::Candle,
+    repository::repository_error::RepositoryResult,
+    technicals::{
+        ema_tac::EMA_IND,
+        heikin_ashi::HeikinAshi,
+        macd::macd_tac::{MACD_DIV_IND, MACD_IND, MACD_SIG_IND},
+        sma_tac::SMA_IND,
+    },
+};
+use anyhow::{anyhow, bail, Context};
+use chrono::{DateTime, TimeZone, Utc};
+use ifmt::iformat;
+use log::info;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use std::{
+    convert::TryInto,
+    fmt,
+    io::{Read, Write},
+    str::FromStr,
+    time::Instant,
+};
+use ta::{
+    indicators::{ExponentialMovingAverage as Ema, MovingAverageConvergenceDivergence as Macd, SimpleMovingAverage as Sma},
+    Next,
+};
+
+/// Periods of indicators not given, same as plot
+const MACD_PERIODS: [usize; 3] = [34, 72, 17];
+const EMA_PERIOD: usize = 17;
+const SMA_PERIOD: usize = 17;
+
+/// Magic and version of columnar files
+pub const COLUMNAR_MAGIC: &[u8; 8] = b"RTCOL\0\0\x02";
+const COLUMN_TIME: u8 = 1;
+const COLUMN_F64: u8 = 2;
+
+/// File format of export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Header line then one line per candle, prices as stored (exact decimals)
+    Csv,
+    /// One json object per candle
+    Jsonl,
+    /// Binary columns, see `ExportWriter::write_columnar`
+    Columnar,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(ExportFormat::Csv),
+            "jsonl" => Ok(ExportFormat::Jsonl),
+            "columnar" => Ok(ExportFormat::Columnar),
+            _ => bail!("Content {} is not valid export format (csv, jsonl, columnar)!", s),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Columnar => "columnar",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Tac exported with all indicators selected of it, periods optional (e.g. `macd`, `macd:12:26:9`, `ema:72`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TacExport {
+    pub name: String,
+    pub periods: Vec<usize>,
+}
+
+impl FromStr for TacExport {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let name = parts.next().unwrap_or_default().trim().to_lowercase();
+        let periods = parts
+            .map(|p| p.trim().parse::<usize>().with_context(|| format!("Tac {} period {} is not a number", s, p)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self { name, periods })
+    }
+}
+
+impl TacExport {
+    /// Indicator names of tac, in order of values of its state, and state with periods given or defaults of plot
+    fn state(&self) -> anyhow::Result<(&'static [&'static str], TacState)> {
+        let period = |default: usize| self.periods.first().copied().unwrap_or(default);
+        let invalid = |e| anyhow!("Tac {} periods {:?} are not valid: {}", self.name, self.periods, e);
+        Ok(match self.name.as_str() {
+            MACD_IND => {
+                let [fast, slow, signal] = match self.periods.as_slice() {
+                    [] => MACD_PERIODS,
+                    [fast, slow, signal] => [*fast, *slow, *signal],
+                    _ => bail!("Tac macd needs 3 periods (fast, slow and signal), not {:?}", self.periods),
+                };
+                (
+                    &[MACD_IND, MACD_SIG_IND, MACD_DIV_IND],
+                    TacState::Macd(Macd::new(fast, slow, signal).map_err(invalid)?),
+                )
+            }
+            EMA_IND => (&[EMA_IND], TacState::Ema(Ema::new(period(EMA_PERIOD)).map_err(invalid)?)),
+            SMA_IND => (&[SMA_IND], TacState::Sma(Sma::new(period(SMA_PERIOD)).map_err(invalid)?)),
+            other => bail!("Tac {} can't be exported (macd, ema, sma)", other),
+        })
+    }
+
+    /// Column of indicator, named after it and periods when given (e.g. `ema_72`)
+    fn column(&self, indicator: &str) -> String {
+        let suffix = self.periods.iter().map(|p| iformat!("_{p}")).collect::<String>();
+        iformat!("{indicator}{suffix}")
+    }
+}
+
+/// Indicators of a tac computed one candle at a time, same as its plotted tac
+enum TacState {
+    Macd(Macd),
+    Ema(Ema),
+    Sma(Sma),
+}
+
+impl TacState {
+    fn next(&mut self, close: f64) -> Vec<f64> {
+        match self {
+            TacState::Macd(macd) => {
+                let (macd, signal, divergence): (f64, f64, f64) = macd.next(close).into();
+                vec![macd, signal, divergence]
+            }
+            TacState::Ema(ema) => vec![ema.next(close)],
+            TacState::Sma(sma) => vec![sma.next(close)],
+        }
+    }
+}
+
+/// Tac state and positions of its values selected
+struct TacColumns {
+    state: TacState,
+    selected: Vec<usize>,
+}
+
+/// Writes candles page by page with indicators of tacs, keeping only state of indicators between pages.
+///
+/// Candles are expected sorted by open time, heikin ashi is applied when selection has it as plotted.
+pub struct ExportWriter<W: Write> {
+    writer: W,
+    format: ExportFormat,
+    columns: Vec<String>,
+    tacs: Vec<TacColumns>,
+    heikin_ashi: Option<HeikinAshi>,
+    rows: usize,
+}
+
+impl<W: Write> ExportWriter<W> {
+    /// Writes header of format, only tacs and indicators of selection are allowed
+    pub fn new(selection: &Selection, tacs: &[TacExport], format: ExportFormat, writer: W) -> anyhow::Result<Self> {
+        let mut columns = ["open_time", "close_time", "open", "high", "low", "close", "volume"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        let mut tac_columns = Vec::new();
+        for tac in tacs.iter() {
+            let definition = selection.tacs.get(&tac.name).ok_or_else(|| anyhow!("Tac {} not selected!", tac.name))?;
+            let (indicators, state) = tac.state()?;
+            let mut selected = Vec::new();
+            for (i, indicator) in indicators.iter().enumerate() {
+                if definition.indicators.iter().any(|name| name == indicator) {
+                    columns.push(tac.column(indicator));
+                    selected.push(i);
+                }
+            }
+            tac_columns.push(TacColumns { state, selected });
+        }
+        let heikin_ashi = if selection.candles_selection.heikin_ashi {
+            Some(HeikinAshi::default())
+        } else {
+            None
+        };
+        let mut export = Self {
+            writer,
+            format,
+            columns,
+            tacs: tac_columns,
+            heikin_ashi,
+            rows: 0,
+        };
+        export.write_header()?;
+        Ok(export)
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    fn write_header(&mut self) -> anyhow::Result<()> {
+        match self.format {
+            ExportFormat::Csv => writeln!(self.writer, "{}", self.columns.join(","))?,
+            ExportFormat::Jsonl => {}
+            ExportFormat::Columnar => {
+                self.writer.write_all(COLUMNAR_MAGIC)?;
+                self.writer.write_all(&(self.columns.len() as u32).to_le_bytes())?;
+                for (i, name) in self.columns.iter().enumerate() {
+                    self.writer.write_all(&(name.len() as u16).to_le_bytes())?;
+                    self.writer.write_all(name.as_bytes())?;
+                    self.writer.write_all(&[if i < 2 { COLUMN_TIME } else { COLUMN_F64 }])?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes candles following the ones already written
+    pub fn write_page(&mut self, candles: &[Candle]) -> anyhow::Result<()> {
+        let mut page = Vec::with_capacity(candles.len());
+        let mut values = Vec::with_capacity(candles.len());
+        for candle in candles.iter() {
+            let candle = match self.heikin_ashi.as_mut() {
+                Some(heikin_ashi) => heikin_ashi.next(candle),
+                None => candle.clone(),
+            };
+            let close = decimal_f64(&candle.close);
+            let mut row = Vec::with_capacity(self.columns.len());
+            for tac in self.tacs.iter_mut() {
+                let tac_values = tac.state.next(close);
+                row.extend(tac.selected.iter().map(|i| tac_values[*i]));
+            }
+            page.push(candle);
+            values.push(row);
+        }
+        match self.format {
+            ExportFormat::Csv => self.write_csv(&page, &values)?,
+            ExportFormat::Jsonl => self.write_jsonl(&page, &values)?,
+            ExportFormat::Columnar => self.write_columnar(&page, &values)?,
+        }
+        self.rows += page.len();
+        Ok(())
+    }
+
+    /// Flushes writer, returns candles written
+    pub fn finish(mut self) -> anyhow::Result<usize> {
+        self.writer.flush()?;
+        Ok(self.rows)
+    }
+
+    fn write_csv(&mut self, candles: &[Candle], values: &[Vec<f64>]) -> anyhow::Result<()> {
+        for (c, row) in candles.iter().zip(values.iter()) {
+            let mut fields = vec![c.open_time.format(TIME_FORMAT).to_string(), c.close_time.format(TIME_FORMAT).to_string()];
+            fields.extend([c.open, c.high, c.low, c.close, c.volume].iter().map(|d| d.to_string()));
+            fields.extend(row.iter().map(|v| if v.is_nan() { String::new() } else { v.to_string() }));
+            writeln!(self.writer, "{}", fields.join(","))?;
+        }
+        Ok(())
+    }
+
+    fn write_jsonl(&mut self, candles: &[Candle], values: &[Vec<f64>]) -> anyhow::Result<()> {
+        for (c, row) in candles.iter().zip(values.iter()) {
+            let mut object = serde_json::Map::new();
+            object.insert("open_time".into(), c.open_time.format(TIME_FORMAT).to_string().into());
+            object.insert("close_time".into(), c.close_time.format(TIME_FORMAT).to_string().into());
+            for (name, value) in [("open", c.open), ("high", c.high), ("low", c.low), ("close", c.close), ("volume", c.volume)].iter() {
+                object.insert(name.to_string(), json_number(decimal_f64(value)));
+            }
+            for (name, value) in self.columns[7..].iter().zip(row.iter()) {
+                object.insert(name.clone(), json_number(*value));
+            }
+            writeln!(self.writer, "{}", serde_json::Value::Object(object))?;
+        }
+        Ok(())
+    }
+
+    /// Little endian columns, for readers like numpy:
+    ///
+    /// header is `RTCOL\0\0\x02`, columns count (u32), then for each column its name length (u16), name (utf-8)
+    /// and type (u8, 1 is time as epoch milliseconds i64, 2 is f64). Each page follows as a block of its rows count (u64)
+    /// then values of each column in same order (8 bytes each), until end of file.
+    fn write_columnar(&mut self, candles: &[Candle], values: &[Vec<f64>]) -> anyhow::Result<()> {
+        if candles.is_empty() {
+            return Ok(());
+        }
+        self.writer.write_all(&(candles.len() as u64).to_le_bytes())?;
+        let times: [fn(&Candle) -> DateTime<Utc>; 2] = [|c| c.open_time, |c| c.close_time];
+        let prices: [fn(&Candle) -> Decimal; 5] = [|c| c.open, |c| c.high, |c| c.low, |c| c.close, |c| c.volume];
+        for time in times.iter() {
+            for candle in candles.iter() {
+                self.writer.write_all(&time(candle).timestamp_millis().to_le_bytes())?;
+            }
+        }
+        for price in prices.iter() {
+            for candle in candles.iter() {
+                self.writer.write_all(&decimal_f64(&price(candle)).to_le_bytes())?;
+            }
+        }
+        for i in 0..self.columns.len() - 7 {
+            for row in values.iter() {
+                self.writer.write_all(&row[i].to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+fn decimal_f64(value: &Decimal) -> f64 {
+    value.to_f64().unwrap_or(f64::NAN)
+}
+
+/// Json number, null when not finite
+fn json_number(value: f64) -> serde_json::Value {
+    serde_json::Number::from_f64(value)
+        .map(serde_json::Value::Number)
+        .unwrap_or(serde_json::Value::Null)
+}
+
+/// Column of a columnar file
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnValues {
+    Time(Vec<DateTime<Utc>>),
+    F64(Vec<f64>),
+}
+
+fn read_bytes<R: Read>(reader: &mut R, len: usize) -> anyhow::Result<Vec<u8>> {
+    let mut buffer = vec![0; len];
+    reader.read_exact(&mut buffer).context("Columnar file truncated")?;
+    Ok(buffer)
+}
+
+/// Reads columns written by `ExportWriter` in columnar format, blocks of pages concatenated
+pub fn read_columnar<R: Read>(reader: &mut R) -> anyhow::Result<Vec<(String, ColumnValues)>> {
+    if read_bytes(reader, 8)? != COLUMNAR_MAGIC {
+        bail!("Not a columnar file of version 2");
+    }
+    let columns = u32::from_le_bytes(read_bytes(reader, 4)?.as_slice().try_into()?) as usize;
+    let mut result = Vec::with_capacity(columns);
+    for _ in 0..columns {
+        let len = u16::from_le_bytes(read_bytes(reader, 2)?.as_slice().try_into()?) as usize;
+        let name = String::from_utf8(read_bytes(reader, len)?)?;
+        let values = match read_bytes(reader, 1)?[0] {
+            COLUMN_TIME => ColumnValues::Time(Vec::new()),
+            COLUMN_F64 => ColumnValues::F64(Vec::new()),
+            other => bail!("Column {} has unknown type {}", name, other),
+        };
+        result.push((name, values));
+    }
+    loop {
+        let mut rows = [0; 8];
+        if reader.read(&mut rows[..1])? == 0 {
+            break;
+        }
+        reader.read_exact(&mut rows[1..]).context("Columnar file truncated")?;
+        let rows = u64::from_le_bytes(rows) as usize;
+        for (_, values) in result.iter_mut() {
+            let bytes = read_bytes(reader, rows * 8)?;
+            let words = bytes.chunks_exact(8).map(|b| b.try_into().unwrap());
+            match values {
+                ColumnValues::Time(times) => times.extend(words.map(|w| Utc.timestamp_millis_opt(i64::from_le_bytes(w)).unwrap())),
+                ColumnValues::F64(floats) => floats.extend(words.map(f64::from_le_bytes)),
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Writes candles of pages (e.g. a `CandlesCursor` on selection range) with indicators of tacs, returns candles written
+pub fn export_candles<W: Write, I: Iterator<Item = RepositoryResult<Vec<Candle>>>>(
+    selection: &Selection, pages: I, tacs: &[TacExport], format: ExportFormat, writer: W,
+) -> anyhow::Result<usize> {
+    let start = Instant::now();
+    let mut export = ExportWriter::new(selection, tacs, format, writer)?;
+    for page in pages {
+        export.write_page(&page?)?;
+    }
+    let columns = export.columns().join(",");
+    let rows = export.finish()?;
+    info!("{}", iformat!("Exported {rows} candles {format} columns {columns}: {start.elapsed():?}"));
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::candles_provider::CandlesProviderVec,
+        candles_utils::{fdec, str_to_datetime},
+        config::{candles_selection::CandlesSelection, definition::TacDefinition},
+        model::{interval::Interval, market::Market},
+        technicals::{heikin_ashi, macd::macd_tac::MacdTac},
+    };
+    use std::collections::HashMap;
+
+    fn selection() -> Selection {
+        let mut tacs = HashMap::new();
+        tacs.insert(MACD_IND.to_string(), TacDefinition::new(MACD_IND, &[MACD_IND, MACD_SIG_IND]));
+        tacs.insert(EMA_IND.to_string(), TacDefinition::new(EMA_IND, &[EMA_IND]));
+        let start_time = str_to_datetime("2020-11-11 10:00:00");
+        let candles_selection = CandlesSelection::new("BTCUSDT", &Interval::M15, &Market::Spot, start_time, str_to_datetime("2020-11-11 11:00:00"));
+        Selection {
+            tacs,
+            candles_selection: CandlesSelection {
+                heikin_ashi: false,
+                ..candles_selection
+            },
+            image_name: String::new(),
+        }
+    }
+
+    fn candles() -> Vec<Candle> {
+        vec![
+            Candle::new(
+                0,
+                "2020-11-11 10:00:00",
+                "2020-11-11 10:14:59",
+                "BTCUSDT",
+                15,
+                fdec(10.0),
+                fdec(12.0),
+                fdec(9.0),
+                fdec(11.5),
+                fdec(1.25),
+            ),
+            Candle::new(
+                0,
+                "2020-11-11 10:15:00",
+                "2020-11-11 10:29:59",
+                "BTCUSDT",
+                15,
+                fdec(11.5),
+                fdec(13.0),
+                fdec(11.0),
+                fdec(12.0),
+                fdec(2.0),
+            ),
+        ]
+    }
+
+    /// Candles written in pages of page size, as bytes of format
+    fn write(selection: &Selection, tacs: &[TacExport], format: ExportFormat, page_size: usize) -> Vec<u8> {
+        let candles = candles();
+        let pages = candles.chunks(page_size).map(|page| Ok(page.to_vec()));
+        let mut buffer = Vec::new();
+        assert_eq!(export_candles(selection, pages, tacs, format, &mut buffer).unwrap(), 2);
+        buffer
+    }
+
+    #[test]
+    fn export_writer_test() {
+        let tacs = ["macd".parse().unwrap(), "ema:2".parse().unwrap()];
+        let export = ExportWriter::new(&selection(), &tacs, ExportFormat::Csv, Vec::new()).unwrap();
+
+        assert_eq!(
+            export.columns(),
+            ["open_time", "close_time", "open", "high", "low", "close", "volume", "macd", "signal", "ema_2"]
+        );
+        assert!(ExportWriter::new(&selection(), &["sma".parse().unwrap()], ExportFormat::Csv, Vec::new()).is_err());
+        assert!(ExportWriter::new(&selection(), &["macd:1:2".parse().unwrap()], ExportFormat::Csv, Vec::new()).is_err());
+        assert!(ExportWriter::new(&selection(), &["ema:0".parse().unwrap()], ExportFormat::Csv, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn write_test() {
+        let tacs = ["ema:2".parse().unwrap()];
+        let selection = selection();
+        for format in [ExportFormat::Csv, ExportFormat::Jsonl].iter() {
+            assert_eq!(write(&selection, &tacs, *format, 1), write(&selection, &tacs, *format, 2));
+        }
+        let columnar = |page_size| read_columnar(&mut write(&selection, &tacs, ExportFormat::Columnar, page_size).as_slice()).unwrap();
+        assert_eq!(columnar(1), columnar(2));
+
+        let csv = String::from_utf8(write(&selection, &tacs, ExportFormat::Csv, 1)).unwrap();
+        let jsonl = String::from_utf8(write(&selection, &tacs, ExportFormat::Jsonl, 1)).unwrap();
+        let columns = read_columnar(&mut write(&selection, &tacs, ExportFormat::Columnar, 1).as_slice()).unwrap();
+
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "open_time,close_time,open,high,low,close,volume,ema_2");
+        assert_eq!(lines[1], "2020-11-11 10:00:00,2020-11-11 10:14:59,10,12,9,11.5,1.25,11.5");
+        assert_eq!(lines.len(), 3);
+        let row: serde_json::Value = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(row["close_time"], "2020-11-11 10:14:59");
+        assert_eq!(row["volume"], 1.25);
+        assert_eq!(row["ema_2"], 11.5);
+        assert_eq!(columns.len(), 8);
+        assert_eq!(
+            columns[0],
+            (
+                "open_time".to_string(),
+                ColumnValues::Time(vec![str_to_datetime("2020-11-11 10:00:00"), str_to_datetime("2020-11-11 10:15:00")])
+            )
+        );
+        assert_eq!(columns[5], ("close".to_string(), ColumnValues::F64(vec![11.5, 12.0])));
+        assert!(read_columnar(&mut &b"RTCOL\0\0\x01"[..]).is_err());
+    }
+
+    #[test]
+    fn heikin_ashi_test() {
+        let mut selection = selection();
+        selection.candles_selection.heikin_ashi = true;
+        let candles = candles();
+
+        let columns = read_columnar(&mut write(&selection, &["macd".parse().unwrap()], ExportFormat::Columnar, 1).as_slice()).unwrap();
+
+        let heikin_ashi = heikin_ashi::heikin_ashi(&candles.iter().collect::<Vec<_>>());
+        let closes = heikin_ashi.iter().map(|c| decimal_f64(&c.close)).collect::<Vec<_>>();
+        let macd = MacdTac::new(Box::new(CandlesProviderVec::new(&heikin_ashi, heikin_ashi.len())), 34, 72, 17);
+        let macd = macd.indicators[MACD_IND].series.iter().map(|s| s.value).collect::<Vec<_>>();
+        assert_eq!(columns[5], ("close".to_string(), ColumnValues::F64(closes)));
+        assert_eq!(columns[7], ("macd".to_string(), ColumnValues::F64(macd)));
+    }
+}
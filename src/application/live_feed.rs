@@ -0,0 +1,186 @@
+use super::candles_provider::CandlesProviderBufferSingleton;
+use crate::{
+    config::symbol_minutes::SymbolMinutes,
+    exchange::kline_stream::{KlineStream, KlineUpdate},
+    model::candle::Candle,
+    strategy::{trade_ledger::TradeLedger, trader::Trader},
+};
+use async_std::task::block_on;
+use ifmt::iformat;
+use log::{debug, info, warn};
+use std::{sync::Arc, thread, time::Duration};
+
+/// Feeds candle buffer from exchange kline stream.
+///
+/// Closed candles go to repository and buffer, the candle in progress is kept apart on buffer singleton.
+/// After each (re)connection the gap since last closed candle is retrieved from exchange.
+/// With a trader, each closed candle is checked and its trades are written by ledger.
+pub struct LiveFeed {
+    url: String,
+    symbol_minutes: SymbolMinutes,
+    candles_provider_singleton: Arc<CandlesProviderBufferSingleton>,
+    reconnect_delay: Duration,
+    max_connections: Option<usize>,
+    trading: Option<(Trader, TradeLedger)>,
+}
+
+/// Source of orders written by live runs
+pub static SOURCE_LIVE: &str = "live";
+
+impl LiveFeed {
+    pub fn new(url: &str, symbol_minutes: &SymbolMinutes, candles_provider_singleton: Arc<CandlesProviderBufferSingleton>) -> Self {
+        Self {
+            url: url.to_string(),
+            symbol_minutes: symbol_minutes.clone(),
+            candles_provider_singleton,
+            reconnect_delay: Duration::from_secs(5),
+            max_connections: None,
+            trading: None,
+        }
+    }
+
+    pub fn with_trading(mut self, trader: Trader, ledger: TradeLedger) -> Self {
+        self.trading = Some((trader, ledger));
+        self
+    }
+
+    /// Stops after this number of connections, instead of reconnecting forever
+    #[cfg(test)]
+    pub fn with_max_connections(mut self, max_connections: usize, reconnect_delay: Duration) -> Self {
+        self.max_connections = Some(max_connections);
+        self.reconnect_delay = reconnect_delay;
+        self
+    }
+
+    pub fn run(&mut self) -> anyhow::Result<()> {
+        let mut connections = 0;
+        loop {
+            connections += 1;
+            if let Err(e) = self.session() {
+                warn!("{}", iformat!("Kline stream {self.url} failed: {e}"));
+            }
+            if self.max_connections.map(|m| connections >= m).unwrap_or(false) {
+                break;
+            }
+            info!("{}", iformat!("Reconnecting in {self.reconnect_delay:?}..."));
+            thread::sleep(self.reconnect_delay);
+        }
+        Ok(())
+    }
+
+    /// Runs one connection until it fails
+    fn session(&mut self) -> anyhow::Result<()> {
+        info!("{}", iformat!("Connecting kline stream {self.url}..."));
+        let mut stream = KlineStream::connect(&self.url, &self.symbol_minutes)?;
+
+        // Updates are buffered by the socket while the gap is retrieved
+        let backfilled = block_on(self.candles_provider_singleton.backfill(&self.symbol_minutes))?;
+        info!("{}", iformat!("Backfilled candles: {backfilled}"));
+
+        loop {
+            match stream.next_update()? {
+                KlineUpdate::Closed(candle) => {
+                    info!("{}", iformat!("Closed: {candle}"));
+                    block_on(self.candles_provider_singleton.append_closed(&self.symbol_minutes, vec![candle.clone()]))?;
+                    self.trade(&candle)?;
+                }
+                KlineUpdate::InProgress(candle) => {
+                    debug!("{}", iformat!("In progress: {candle}"));
+                    self.candles_provider_singleton.set_in_progress(&self.symbol_minutes, candle);
+                }
+            }
+        }
+    }
+
+    /// Checks trader on closed candle, its new trades are written by ledger
+    fn trade(&mut self, candle: &Candle) -> anyhow::Result<()> {
+        if let Some((trader, ledger)) = self.trading.as_mut() {
+            let known = trader.trades().len();
+            trader.check(candle.close_time, candle.close)?;
+            for trade in trader.trades().iter().skip(known) {
+                if ledger.record(trade)? {
+                    info!(
+                        "{}",
+                        iformat!(
+                            "Traded {trade.operation:?} price {trade.price}, position {ledger.position().state} balance USD {ledger.position().balance_usd}"
+                        )
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        candles_utils::str_to_datetime,
+        exchange::{kline_stream::tests::kline_event, replay_exchange::tests::replay_exchange, Exchange},
+        model::{candle::Candle, interval::Interval, market::Market},
+        repository::{memory_repository::MemoryRepository, Repository},
+    };
+    use std::net::TcpListener;
+    use tungstenite::Message;
+
+    #[test]
+    fn live_feed_test() -> anyhow::Result<()> {
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Spot);
+        let exchange = replay_exchange();
+        let klines = exchange.candles(&symbol_minutes, &None, &None)?;
+        let at = |time: &str| klines.iter().find(|c| c.open_time == str_to_datetime(time)).unwrap().clone();
+
+        // Repository knows candles until 10:00
+        let repository = Arc::new(MemoryRepository::new());
+        let mut history = klines
+            .iter()
+            .filter(|c| c.open_time <= str_to_datetime("2020-11-11 10:00:00"))
+            .cloned()
+            .collect::<Vec<_>>();
+        block_on(repository.insert_candles(&mut history))?;
+
+        // Next candle after the exchange klines, first in progress then closed
+        let last = klines.last().unwrap();
+        let next = Candle {
+            open_time: last.open_time + chrono::Duration::minutes(15),
+            close_time: last.close_time + chrono::Duration::minutes(15),
+            ..last.clone()
+        };
+
+        // First connection sends a closed candle already backfilled and the next in progress, then drops.
+        // Second connection closes next candle.
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("ws://{}/ws/btcusdt@kline_15m", listener.local_addr()?);
+        let sessions = vec![
+            vec![kline_event(&at("2020-11-11 10:15:00"), true), kline_event(&next, false)],
+            vec![kline_event(&next, true)],
+        ];
+        let server = thread::spawn(move || {
+            for messages in sessions {
+                let (tcp, _) = listener.accept().unwrap();
+                let mut socket = tungstenite::accept(tcp).unwrap();
+                for message in messages {
+                    socket.write_message(Message::Text(message)).unwrap();
+                }
+                socket.close(None).unwrap();
+                while socket.read_message().is_ok() {}
+            }
+        });
+
+        let singleton = Arc::new(CandlesProviderBufferSingleton::new(repository.clone(), Arc::new(replay_exchange())));
+        let mut live_feed = LiveFeed::new(&url, &symbol_minutes, singleton.clone()).with_max_connections(2, Duration::from_millis(10));
+        live_feed.run()?;
+        server.join().unwrap();
+
+        let candles = block_on(repository.candles_by_time(&symbol_minutes, &klines.first().unwrap().open_time, &next.open_time)).unwrap();
+        block_on(repository.delete_candles(&symbol_minutes))?;
+
+        // Gap since 10:00 was backfilled, nothing duplicated
+        assert_eq!(candles.len(), klines.len() + 1);
+        assert!(candles.iter().zip(klines.iter()).all(|(c, k)| c.open_time == k.open_time && c.close == k.close));
+        assert_eq!(candles.last().unwrap().open_time, next.open_time);
+        assert!(singleton.in_progress(&symbol_minutes).is_none());
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
 pub mod app;
 pub mod candles_provider;
+pub mod export_selection;
+pub mod live_feed;
 pub mod plot_selection;
 pub mod streamer;
@@ -2,8 +2,12 @@ use super::candles_provider::CandlesProvider;
 use crate::{
     config::selection::Selection,
     tac_plotters::{
-        candles_plotter::CandlePlotter, indicator_plotter::PlotterIndicatorContext, line_ind_plotter::LineIndicatorPlotter, macd_plotter::MacdPlotter,
-        plotter::Plotter, topbottom_plotter::TopBottomPlotter,
+        candles_plotter::CandlePlotter,
+        indicator_plotter::{IndicatorPlotter, PlotterIndicatorContext},
+        line_ind_plotter::LineIndicatorPlotter,
+        macd_plotter::MacdPlotter,
+        plotter::Plotter,
+        topbottom_plotter::TopBottomPlotter,
     },
     technicals::technical::TechnicalIndicators,
     technicals::{ema_tac::EmaTac, macd::macd_tac::MacdTac, topbottom::TopBottomTac},
@@ -19,6 +23,7 @@ pub fn plot_selection<'a>(
     selection: Selection,
     mut candles_provider: Box<dyn CandlesProvider>,
     plotters: Vec<Box<dyn PlotterIndicatorContext + 'a>>,
+    lower_plotters: Vec<Box<dyn IndicatorPlotter + 'a>>,
 ) -> anyhow::Result<()> {
     let total_start = Instant::now();
 
@@ -69,6 +74,7 @@ pub fn plot_selection<'a>(
     // Lower indicators
     let macd_plotter = MacdPlotter::new(&macd_tac);
     plotter.add_plotter_ind(&macd_plotter);
+    lower_plotters.iter().for_each(|p| plotter.add_plotter_ind(&**p));
 
     let start = Instant::now();
     plotter.plot(&selection.image_name)?;
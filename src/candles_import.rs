@@ -0,0 +1,404 @@
+use crate::{
+    candles_validation::candle_violations,
+    config::symbol_minutes::SymbolMinutes,
+    model::{candle::Candle, interval::Interval},
+};
+use anyhow::{anyhow, bail, Context};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use ifmt::{iformat, iwrite};
+use rust_decimal::Decimal;
+use std::{
+    fmt, fs,
+    io::Read,
+    ops::AddAssign,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Column of csv file, by position (from 0) or by header name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+impl Column {
+    fn index(&self, header: Option<&[&str]>) -> anyhow::Result<usize> {
+        match (self, header) {
+            (Column::Index(index), _) => Ok(*index),
+            (Column::Name(name), Some(header)) => header
+                .iter()
+                .position(|h| h.eq_ignore_ascii_case(name))
+                .ok_or_else(|| anyhow!("Column {} not found in header {}", name, header.join(","))),
+            (Column::Name(name), None) => bail!("Column {} named but file has no header", name),
+        }
+    }
+}
+
+impl FromStr for Column {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => bail!("Column is empty"),
+            s => Ok(s.parse::<usize>().map(Column::Index).unwrap_or_else(|_| Column::Name(s.to_string()))),
+        }
+    }
+}
+
+/// Columns of open time, OHLCV and optionally close time (otherwise close of interval) in csv files.
+///
+/// Times are epoch seconds, milliseconds or microseconds, or date times like `2020-11-11 10:00:00`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMapping {
+    pub open_time: Column,
+    pub close_time: Option<Column>,
+    pub open: Column,
+    pub high: Column,
+    pub low: Column,
+    pub close: Column,
+    pub volume: Column,
+}
+
+impl ColumnMapping {
+    /// Layout of Binance kline csv dumps (data.binance.vision)
+    pub fn binance() -> Self {
+        Self {
+            open_time: Column::Index(0),
+            open: Column::Index(1),
+            high: Column::Index(2),
+            low: Column::Index(3),
+            close: Column::Index(4),
+            volume: Column::Index(5),
+            close_time: Some(Column::Index(6)),
+        }
+    }
+}
+
+/// Mapping like `open_time=Date,open=Open,high=High,low=Low,close=Close,volume=Volume`, columns by name or position, `close_time` optional
+impl FromStr for ColumnMapping {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut columns = std::collections::HashMap::new();
+        for pair in s.split(',') {
+            let mut parts = pair.splitn(2, '=');
+            let (key, column) = match (parts.next(), parts.next()) {
+                (Some(key), Some(column)) => (key, column),
+                _ => bail!("Column mapping {} is not key=column", pair),
+            };
+            columns.insert(key.trim().to_lowercase(), column.parse::<Column>()?);
+        }
+        let mut take = |key: &str| columns.remove(key).ok_or_else(|| anyhow!("Column mapping {} has no {}", s, key));
+        let mapping = Self {
+            open_time: take("open_time")?,
+            open: take("open")?,
+            high: take("high")?,
+            low: take("low")?,
+            close: take("close")?,
+            volume: take("volume")?,
+            close_time: take("close_time").ok(),
+        };
+        if let Some(key) = columns.keys().next() {
+            bail!("Column mapping {} has unknown key {}", s, key);
+        }
+        Ok(mapping)
+    }
+}
+
+/// Positions of mapped columns in a file
+struct ColumnIndexes {
+    open_time: usize,
+    close_time: Option<usize>,
+    open: usize,
+    high: usize,
+    low: usize,
+    close: usize,
+    volume: usize,
+}
+
+/// Candles of csv rows and rows refused with their line and reason
+#[derive(Debug, Default)]
+pub struct ParsedCandles {
+    pub candles: Vec<Candle>,
+    pub rejected: Vec<String>,
+}
+
+/// Counts of an import, rows are data lines read from files
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ImportStats {
+    pub files: usize,
+    pub rows: usize,
+    pub rejected: usize,
+    /// Rows repeating an open time of same import
+    pub duplicated: usize,
+    /// Rows with an open time already stored
+    pub existing: usize,
+    pub inserted: u64,
+}
+
+impl AddAssign for ImportStats {
+    fn add_assign(&mut self, other: Self) {
+        self.files += other.files;
+        self.rows += other.rows;
+        self.rejected += other.rejected;
+        self.duplicated += other.duplicated;
+        self.existing += other.existing;
+        self.inserted += other.inserted;
+    }
+}
+
+impl fmt::Display for ImportStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        iwrite!(
+            f,
+            "{self.files} files, {self.rows} rows: {self.inserted} inserted, {self.existing} already stored, {self.duplicated} duplicated, {self.rejected} rejected"
+        )
+    }
+}
+
+/// Reads candles of symbol minutes from csv files, plain or zipped (e.g. Binance monthly kline dumps)
+pub struct CandlesImport {
+    symbol_minutes: SymbolMinutes,
+    mapping: ColumnMapping,
+    delimiter: char,
+}
+
+impl CandlesImport {
+    pub fn new(symbol_minutes: &SymbolMinutes, mapping: ColumnMapping, delimiter: char) -> Self {
+        Self {
+            symbol_minutes: symbol_minutes.clone(),
+            mapping,
+            delimiter,
+        }
+    }
+
+    /// Binance kline csv dumps of symbol minutes
+    pub fn binance(symbol_minutes: &SymbolMinutes) -> Self {
+        Self::new(symbol_minutes, ColumnMapping::binance(), ',')
+    }
+
+    /// Parses rows of csv content, first line is a header when it doesn't start with a digit
+    pub fn parse(&self, content: &str) -> anyhow::Result<ParsedCandles> {
+        let mut lines = content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()).peekable();
+        let header = match lines.peek() {
+            Some((_, line)) if !line.trim_start_matches('"').starts_with(|c: char| c.is_ascii_digit()) => {
+                lines.next().map(|(_, line)| split_fields(line, self.delimiter))
+            }
+            _ => None,
+        };
+        let indexes = self.indexes(header.as_deref())?;
+
+        let mut parsed = ParsedCandles::default();
+        for (i, line) in lines {
+            match self.parse_row(&split_fields(line, self.delimiter), &indexes) {
+                Ok(candle) => parsed.candles.push(candle),
+                Err(e) => parsed.rejected.push(iformat!("line {i + 1}: {e}")),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn indexes(&self, header: Option<&[&str]>) -> anyhow::Result<ColumnIndexes> {
+        let mapping = &self.mapping;
+        Ok(ColumnIndexes {
+            open_time: mapping.open_time.index(header)?,
+            close_time: mapping.close_time.as_ref().map(|c| c.index(header)).transpose()?,
+            open: mapping.open.index(header)?,
+            high: mapping.high.index(header)?,
+            low: mapping.low.index(header)?,
+            close: mapping.close.index(header)?,
+            volume: mapping.volume.index(header)?,
+        })
+    }
+
+    fn parse_row(&self, fields: &[&str], indexes: &ColumnIndexes) -> anyhow::Result<Candle> {
+        let field = |index: usize| {
+            fields
+                .get(index)
+                .copied()
+                .ok_or_else(|| anyhow!("{} fields, column {} missing", fields.len(), index))
+        };
+        let decimal = |index: usize| -> anyhow::Result<Decimal> {
+            let value = field(index)?;
+            Decimal::from_str(value)
+                .or_else(|_| Decimal::from_scientific(value))
+                .with_context(|| format!("{} is not a number", value))
+        };
+        let interval = &self.symbol_minutes.interval;
+        let open_time = parse_time(field(indexes.open_time)?)?;
+        let close_time = match indexes.close_time {
+            Some(index) => parse_time(field(index)?)?,
+            None => interval.close(&open_time),
+        };
+        let candle = Candle {
+            id: Decimal::ZERO,
+            symbol: self.symbol_minutes.symbol.clone(),
+            minutes: interval.minutes().into(),
+            open_time,
+            close_time,
+            open: decimal(indexes.open)?,
+            high: decimal(indexes.high)?,
+            low: decimal(indexes.low)?,
+            close: decimal(indexes.close)?,
+            volume: decimal(indexes.volume)?,
+            market: self.symbol_minutes.market.to_string(),
+            exchange: self.symbol_minutes.exchange.to_string(),
+        };
+        check_candle(&candle, interval).map_err(|e| anyhow!(e))?;
+        Ok(candle)
+    }
+}
+
+fn split_fields(line: &str, delimiter: char) -> Vec<&str> {
+    line.split(delimiter).map(|f| f.trim().trim_matches('"')).collect()
+}
+
+/// Candle breaks no rule of `candle_violations`, otherwise the violations joined
+pub fn check_candle(candle: &Candle, interval: &Interval) -> Result<(), String> {
+    let violations = candle_violations(candle, interval);
+    if violations.is_empty() {
+        return Ok(());
+    }
+    Err(violations.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "))
+}
+
+/// Epoch seconds, milliseconds or microseconds (told apart by magnitude), truncated to seconds, or a date time
+fn parse_time(value: &str) -> anyhow::Result<DateTime<Utc>> {
+    if let Ok(timestamp) = value.parse::<i64>() {
+        let seconds = match timestamp.abs() {
+            t if t >= 100_000_000_000_000 => timestamp / 1_000_000,
+            t if t >= 100_000_000_000 => timestamp / 1000,
+            _ => timestamp,
+        };
+        return Utc
+            .timestamp_opt(seconds, 0)
+            .single()
+            .ok_or_else(|| anyhow!("{} is not a valid timestamp", value));
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|d| d.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").map(|d| Utc.from_utc_datetime(&d)))
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|d| Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap())))
+        .map_err(|_| anyhow!("{} is not a date time", value))
+}
+
+/// Csv and zip files of path, the file itself or the ones of a directory sorted by name
+pub fn import_paths(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut paths = fs::read_dir(path)
+        .with_context(|| format!("Import directory {:?} not readable", path))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| matches!(path.extension().and_then(|e| e.to_str()), Some("csv") | Some("zip")))
+        .collect::<Vec<_>>();
+    if paths.is_empty() {
+        bail!("Import: no csv or zip file in {:?}", path);
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Name and content of csv file, or of each csv file inside zip file
+pub fn read_csv_files(path: &Path) -> anyhow::Result<Vec<(String, String)>> {
+    if path.extension().and_then(|e| e.to_str()) != Some("zip") {
+        let content = fs::read_to_string(path).with_context(|| format!("Import: {:?} not readable", path))?;
+        return Ok(vec![(path.display().to_string(), content)]);
+    }
+    let file = fs::File::open(path).with_context(|| format!("Import: {:?} not readable", path))?;
+    let mut archive = zip::ZipArchive::new(file).with_context(|| format!("Import: {:?} is not a zip file", path))?;
+    let mut files = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if !entry.name().ends_with(".csv") {
+            continue;
+        }
+        let mut content = String::new();
+        entry
+            .read_to_string(&mut content)
+            .with_context(|| format!("Import: {:?} {} not readable", path, entry.name()))?;
+        files.push((iformat!("{path.display()}:{entry.name()}"), content));
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        candles_utils::{fdec, str_to_datetime},
+        model::market::Market,
+    };
+
+    #[test]
+    fn column_mapping_test() {
+        let mapping = "open_time=Date, open=1,high=High,low=Low,close=Close,volume=Volume"
+            .parse::<ColumnMapping>()
+            .unwrap();
+        assert_eq!(mapping.open_time, Column::Name("Date".to_string()));
+        assert_eq!(mapping.open, Column::Index(1));
+        assert_eq!(mapping.close_time, None);
+        assert!("open_time=0,open=1,high=2,low=3,close=4".parse::<ColumnMapping>().is_err());
+        assert!("open_time=0,open=1,high=2,low=3,close=4,volume=5,trades=8".parse::<ColumnMapping>().is_err());
+    }
+
+    #[test]
+    fn parse_time_test() {
+        let time = str_to_datetime("2020-11-10 00:00:00");
+        assert_eq!(parse_time("1604966400").unwrap(), time);
+        assert_eq!(parse_time("1604966400000").unwrap(), time);
+        assert_eq!(parse_time("1604966400000000").unwrap(), time);
+        assert_eq!(parse_time("1604967299999").unwrap(), str_to_datetime("2020-11-10 00:14:59"));
+        assert_eq!(parse_time("2020-11-10 00:00:00").unwrap(), time);
+        assert_eq!(parse_time("2020-11-10T00:00:00Z").unwrap(), time);
+        assert!(parse_time("10/11/2020").is_err());
+    }
+
+    #[test]
+    fn parse_test() {
+        let import = CandlesImport::new(
+            &SymbolMinutes::new("BTCUSDT", &Interval::H1, &Market::Spot),
+            "open_time=time,open=open,high=high,low=low,close=close,volume=volume".parse().unwrap(),
+            ';',
+        );
+        let content = "time;open;high;low;close;volume\n\
+            2020-11-10 00:00:00;10;12;9;11;5\n\
+            \n\
+            2020-11-10 01:30:00;10;12;9;11;5\n\
+            2020-11-10 02:00:00;10;10.5;9;11;5\n\
+            2020-11-10 03:00:00;10;12;9;11\n\
+            2020-11-10 04:00:00;1e1;12;9;11;-5\n";
+
+        let parsed = import.parse(content).unwrap();
+
+        assert_eq!(parsed.candles.len(), 1);
+        let candle = &parsed.candles[0];
+        assert_eq!(
+            (candle.open_time, candle.close_time),
+            (str_to_datetime("2020-11-10 00:00:00"), str_to_datetime("2020-11-10 00:59:59"))
+        );
+        assert_eq!(
+            (candle.open, candle.high, candle.low, candle.close, candle.volume),
+            (fdec(10.0), fdec(12.0), fdec(9.0), fdec(11.0), fdec(5.0))
+        );
+        assert_eq!((candle.minutes, candle.market.as_str()), (Decimal::from(60), "spot"));
+        assert_eq!(parsed.rejected.len(), 4);
+        assert!(parsed.rejected[0].starts_with("line 4: misaligned"), "{:?}", parsed.rejected);
+        assert!(parsed.rejected[1].starts_with("line 5: ohlc_bounds"), "{:?}", parsed.rejected);
+        assert!(parsed.rejected[2].starts_with("line 6: 5 fields"), "{:?}", parsed.rejected);
+        assert!(parsed.rejected[3].starts_with("line 7: negative_volume"), "{:?}", parsed.rejected);
+    }
+
+    #[test]
+    fn read_csv_files_test() {
+        let zip = read_csv_files(Path::new("fixtures/import/BTCUSDT-15m-2020-11.zip")).unwrap();
+        let paths = import_paths(Path::new("fixtures/import")).unwrap();
+
+        assert_eq!(zip.len(), 1);
+        assert!(zip[0].0.ends_with("BTCUSDT-15m-2020-11.zip:BTCUSDT-15m-2020-11.csv"));
+        assert!(zip[0].1.starts_with("open_time,open,high"));
+        assert_eq!(paths.len(), 2);
+        assert!(paths[0].ends_with("BTCUSDT-15m-2020-11.zip"));
+    }
+}
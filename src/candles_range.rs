@@ -1,10 +1,9 @@
 use crate::{
     candles_utils::min_max_close_time_from_candles,
-    model::{candle::Candle, open_close::OpenClose},
+    model::{candle::Candle, interval::Interval, open_close::OpenClose},
 };
 use anyhow::*;
-use chrono::prelude::*;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Utc};
 use log::error;
 
 #[derive(Debug)]
@@ -77,11 +76,10 @@ impl<'a> Default for CandlesRanges<'a> {
     }
 }
 
-pub fn candles_ranges<'a>(candles: &[&'a Candle], minutes: &u32) -> anyhow::Result<CandlesRanges<'a>> {
+pub fn candles_ranges<'a>(candles: &[&'a Candle], interval: &Interval) -> anyhow::Result<CandlesRanges<'a>> {
     if candles.is_empty() {
         return Err(anyhow!("candles_ranges: Candles is empty!"));
     }
-    let duration = &Duration::minutes(*minutes as i64);
     let mut error = String::from("");
     // Returns inconsistent candles
     let result = candles
@@ -101,7 +99,7 @@ pub fn candles_ranges<'a>(candles: &[&'a Candle], minutes: &u32) -> anyhow::Resu
                         error = format!("Found duplicate start x end {}! Candles list len {}:", current_d, candles.len());
                     }
 
-                    if current_d - previous_d != *duration {
+                    if current_d != interval.next_open(&previous_d) {
                         previous.0.new_range();
                     }
                 }
@@ -122,7 +120,9 @@ pub fn candles_ranges<'a>(candles: &[&'a Candle], minutes: &u32) -> anyhow::Resu
     Ok(result)
 }
 
-pub fn invert_ranges_close(start_time: &OpenClose, end_time: &OpenClose, ranges: &CandlesRanges, minutes: &u32) -> anyhow::Result<Vec<(OpenClose, OpenClose)>> {
+pub fn invert_ranges_close(
+    start_time: &OpenClose, end_time: &OpenClose, ranges: &CandlesRanges, interval: &Interval,
+) -> anyhow::Result<Vec<(OpenClose, OpenClose)>> {
     fn add_range(ranges: &CandlesRanges, inverted_ranges: &mut Vec<(OpenClose, OpenClose)>, start: OpenClose, end: OpenClose) -> anyhow::Result<()> {
         if start > end {
             let message = format!("Attempt to add range start {} > end {}", start, end);
@@ -144,39 +144,28 @@ pub fn invert_ranges_close(start_time: &OpenClose, end_time: &OpenClose, ranges:
         Ok(())
     }
     let mut inverted_ranges = Vec::new();
-    let duration = Duration::minutes(*minutes as i64);
 
     let first_min = ranges.ranges.first().unwrap().min_max_close()?.0;
     if start_time < &first_min {
-        add_range(&ranges, &mut inverted_ranges, *start_time, first_min - duration)?;
+        add_range(ranges, &mut inverted_ranges, *start_time, first_min.prev(interval))?;
     }
 
     for i in 1..ranges.ranges.len() {
-        let prev = ranges.ranges.get(i - 1).unwrap().min_max_close()?.1 + duration;
-        let curr = ranges.ranges.get(i).unwrap().min_max_close()?.0 - duration;
-        add_range(&ranges, &mut inverted_ranges, prev, curr)?;
+        let prev = ranges.ranges.get(i - 1).unwrap().min_max_close()?.1.next(interval);
+        let curr = ranges.ranges.get(i).unwrap().min_max_close()?.0.prev(interval);
+        add_range(ranges, &mut inverted_ranges, prev, curr)?;
     }
 
     let end_max = ranges.ranges.last().unwrap().min_max_close()?.1;
     if end_time > &end_max {
-        add_range(&ranges, &mut inverted_ranges, end_max + duration, *end_time)?;
+        add_range(ranges, &mut inverted_ranges, end_max.next(interval), *end_time)?;
     }
 
     Ok(inverted_ranges)
 }
 
-pub fn minutes_open_trunc(start_time: &DateTime<Utc>, minutes: &u32) -> DateTime<Utc> {
-    let mut start_time = *start_time;
-    let minute = start_time.minute() - (start_time.minute() % minutes);
-    start_time = start_time.with_minute(minute).unwrap().with_second(0).unwrap();
-    start_time
-}
-
 pub fn candles_to_ranges_missing(
-    start_time: &OpenClose,
-    end_time: &OpenClose,
-    minutes: &u32,
-    candles: &[&Candle],
+    start_time: &OpenClose, end_time: &OpenClose, interval: &Interval, candles: &[&Candle],
 ) -> anyhow::Result<Vec<(OpenClose, OpenClose)>> {
     if candles.is_empty() {
         return Ok(vec![(*start_time, *end_time)]);
@@ -189,12 +178,12 @@ pub fn candles_to_ranges_missing(
     //     return Err(anyhow!("End time {:?} is less than allowed!", end_time));
     // }
 
-    let candles_ranges = match candles_ranges(candles, minutes) {
+    let candles_ranges = match candles_ranges(candles, interval) {
         Ok(candles) => candles,
         Err(e) => bail!("candles_to_ranges_missing: {} {} {}", start_time, end_time, e),
     };
 
-    match invert_ranges_close(&start_time, &end_time, &candles_ranges, minutes) {
+    match invert_ranges_close(start_time, end_time, &candles_ranges, interval) {
         Ok(result) => Ok(result),
         Err(e) => {
             error!(
@@ -216,6 +205,7 @@ pub mod testes {
         candles_utils::{_datetime_to_str, fdec, str_d, str_to_datetime},
         model::open_close::_str_close,
     };
+    use chrono::Duration;
     use std::println;
 
     pub fn str_open(date_time: &str) -> OpenClose {
@@ -240,7 +230,7 @@ pub mod testes {
         let candles = candles_test(&["2020-01-12 12:00:00", "2020-01-12 12:15:00", "2020-11-16 01:15:00", "2020-11-20 11:15:00"]);
 
         let candles_ref = candles.iter().collect::<Vec<_>>();
-        let ranges = candles_ranges(candles_ref.as_slice(), &15).unwrap();
+        let ranges = candles_ranges(candles_ref.as_slice(), &Interval::M15).unwrap();
         println!("Ranges:");
         for range in ranges.ranges.iter() {
             let date_range = range.min_max_close().unwrap();
@@ -265,13 +255,13 @@ pub mod testes {
         let candles = candles_test(&["2020-01-12 12:00:00", "2020-01-12 12:15:00", "2020-11-16 01:15:00", "2020-11-20 11:15:00"]);
 
         let candles_ref = candles.iter().collect::<Vec<_>>();
-        let ranges = candles_ranges(candles_ref.as_slice(), &15).unwrap();
+        let ranges = candles_ranges(candles_ref.as_slice(), &Interval::M15).unwrap();
         println!("Ranges:");
 
         let start_time = OpenClose::Close(str_d("2020-01-01 00:00:00") - Duration::seconds(1));
         let end_time = OpenClose::Close(str_d("2020-11-30 00:00:00") - Duration::seconds(1));
 
-        let inverted_ranges = invert_ranges_close(&start_time, &end_time, &ranges, &15).unwrap();
+        let inverted_ranges = invert_ranges_close(&start_time, &end_time, &ranges, &Interval::M15).unwrap();
 
         println!("Inverted ranges {} {}:", start_time, end_time);
         for inverted_range in inverted_ranges.iter() {
@@ -295,12 +285,12 @@ pub mod testes {
         let candles = candles_test(&["2020-01-12 12:00:00", "2020-01-12 12:15:00", "2020-11-16 01:15:00", "2020-11-20 11:15:00"]);
 
         let candles_ref = candles.iter().collect::<Vec<_>>();
-        let ranges = candles_ranges(candles_ref.as_slice(), &15).unwrap();
+        let ranges = candles_ranges(candles_ref.as_slice(), &Interval::M15).unwrap();
 
         let start_time = OpenClose::Close(str_d("2020-01-01 00:00:00") - Duration::seconds(1));
         let end_time = OpenClose::Close(str_d("2020-11-30 00:00:00") - Duration::seconds(1));
 
-        let inverted_ranges = invert_ranges_close(&start_time, &end_time, &ranges, &15).unwrap();
+        let inverted_ranges = invert_ranges_close(&start_time, &end_time, &ranges, &Interval::M15).unwrap();
 
         println!("Inverted ranges {} {}:", start_time, end_time);
         for inverted_range in inverted_ranges.iter() {
@@ -321,16 +311,16 @@ pub mod testes {
 
     #[test]
     fn minutes_open_trunc_test() {
-        let truncated = minutes_open_trunc(&str_d("2020-01-01 00:00:00"), &15);
+        let truncated = Interval::M15.open_trunc(&str_d("2020-01-01 00:00:00"));
         assert_eq!(truncated, str_d("2020-01-01 00:00:00"));
 
-        let truncated = minutes_open_trunc(&str_d("2020-01-01 00:17:00"), &15);
+        let truncated = Interval::M15.open_trunc(&str_d("2020-01-01 00:17:00"));
         assert_eq!(truncated, str_d("2020-01-01 00:15:00"));
 
-        let truncated = minutes_open_trunc(&str_d("2020-01-01 00:14:59"), &15);
+        let truncated = Interval::M15.open_trunc(&str_d("2020-01-01 00:14:59"));
         assert_eq!(truncated, str_d("2020-01-01 00:00:00"));
 
-        let truncated = minutes_open_trunc(&str_d("2020-01-01 00:31:00"), &15);
+        let truncated = Interval::M15.open_trunc(&str_d("2020-01-01 00:31:00"));
         assert_eq!(truncated, str_d("2020-01-01 00:30:00"));
 
         println!("{}", truncated);
@@ -338,13 +328,13 @@ pub mod testes {
 
     #[test]
     fn candles_to_ranges_missing_test() {
-        let start_time = OpenClose::from_str("2020-01-01 00:00:00", &15);
-        let end_time = OpenClose::from_str("2020-11-30 00:00:00", &15);
+        let start_time = OpenClose::from_str("2020-01-01 00:00:00", &Interval::M15);
+        let end_time = OpenClose::from_str("2020-11-30 00:00:00", &Interval::M15);
 
         let candles = candles_test(&["2020-01-12 12:00:00", "2020-01-12 12:15:00", "2020-11-16 01:15:00", "2020-11-20 11:15:00"]);
 
         let candles_ref = candles.iter().collect::<Vec<_>>();
-        let ranges_missing = candles_to_ranges_missing(&start_time, &end_time, &15, candles_ref.as_slice()).unwrap();
+        let ranges_missing = candles_to_ranges_missing(&start_time, &end_time, &Interval::M15, candles_ref.as_slice()).unwrap();
 
         println!("ranges_missing ({}):", ranges_missing.len());
         for range in ranges_missing.iter() {
@@ -375,13 +365,13 @@ pub mod testes {
 
     #[test]
     fn candles_to_ranges_missing_exact_bound_test() {
-        let start_time = OpenClose::from_str("2020-01-12 12:00:00", &15);
-        let end_time = OpenClose::from_str("2020-11-20 11:15:00", &15);
+        let start_time = OpenClose::from_str("2020-01-12 12:00:00", &Interval::M15);
+        let end_time = OpenClose::from_str("2020-11-20 11:15:00", &Interval::M15);
 
         let candles = candles_test(&["2020-01-12 12:00:00", "2020-01-12 12:15:00", "2020-11-16 01:15:00", "2020-11-20 11:15:00"]);
 
         let candles_ref = candles.iter().collect::<Vec<_>>();
-        let ranges_missing = candles_to_ranges_missing(&start_time, &end_time, &15, candles_ref.as_slice()).unwrap();
+        let ranges_missing = candles_to_ranges_missing(&start_time, &end_time, &Interval::M15, candles_ref.as_slice()).unwrap();
 
         println!("ranges_missing ({}):", ranges_missing.len());
         for range in ranges_missing.iter() {
@@ -403,12 +393,12 @@ pub mod testes {
     #[test]
     fn missing_candle_test() {
         let candles = candles_test(&["2020-10-11 09:30:00", "2020-10-11 09:45:00", "2020-10-11 10:15:00", "2020-10-11 10:30:00"]);
-        let start_time = OpenClose::from_date(&candles.first().unwrap().open_time, &15);
-        let end_time = OpenClose::from_date(&candles.last().unwrap().open_time, &15);
+        let start_time = OpenClose::from_date(&candles.first().unwrap().open_time, &Interval::M15);
+        let end_time = OpenClose::from_date(&candles.last().unwrap().open_time, &Interval::M15);
         let candles_ref = candles.iter().collect::<Vec<_>>();
-        let ranges_missing = candles_to_ranges_missing(&start_time, &end_time, &15, candles_ref.as_slice()).unwrap();
+        let ranges_missing = candles_to_ranges_missing(&start_time, &end_time, &Interval::M15, candles_ref.as_slice()).unwrap();
 
-        let missing_candle = OpenClose::from_str("2020-10-11 10:00:00", &15);
+        let missing_candle = OpenClose::from_str("2020-10-11 10:00:00", &Interval::M15);
 
         println!("ranges_missing ({}):", ranges_missing.len());
         for range in ranges_missing.iter() {
@@ -419,4 +409,36 @@ pub mod testes {
             (missing_candle, missing_candle)
         );
     }
+
+    #[test]
+    fn candles_to_ranges_missing_interval_test() {
+        let candle =
+            |start: &str, end: &str, minutes: u32| Candle::new(0, start, end, "BTCUSDT", minutes, fdec(1.0), fdec(1.0), fdec(1.0), fdec(1.0), fdec(1.0));
+
+        // 4h candles missing 08:00
+        let candles = [
+            candle("2020-10-11 00:00:00", "2020-10-11 03:59:59", 240),
+            candle("2020-10-11 04:00:00", "2020-10-11 07:59:59", 240),
+            candle("2020-10-11 12:00:00", "2020-10-11 15:59:59", 240),
+        ];
+        let candles_ref = candles.iter().collect::<Vec<_>>();
+        let start_time = OpenClose::from_str("2020-10-11 01:00:00", &Interval::H4);
+        let end_time = OpenClose::from_str("2020-10-11 13:00:00", &Interval::H4);
+        let ranges_missing = candles_to_ranges_missing(&start_time, &end_time, &Interval::H4, candles_ref.as_slice()).unwrap();
+        let missing_candle = OpenClose::from_str("2020-10-11 08:00:00", &Interval::H4);
+        assert_eq!(ranges_missing, vec![(missing_candle, missing_candle)]);
+
+        // Month candles follow calendar, february is not missing
+        let candles = [
+            candle("2020-01-01 00:00:00", "2020-01-31 23:59:59", 43200),
+            candle("2020-02-01 00:00:00", "2020-02-29 23:59:59", 43200),
+            candle("2020-03-01 00:00:00", "2020-03-31 23:59:59", 43200),
+        ];
+        let candles_ref = candles.iter().collect::<Vec<_>>();
+        assert_eq!(candles_ranges(candles_ref.as_slice(), &Interval::Month1).unwrap().ranges.len(), 1);
+        let start_time = OpenClose::from_str("2020-01-10 00:00:00", &Interval::Month1);
+        let end_time = OpenClose::from_str("2020-05-10 00:00:00", &Interval::Month1);
+        let ranges_missing = candles_to_ranges_missing(&start_time, &end_time, &Interval::Month1, candles_ref.as_slice()).unwrap();
+        assert_eq!(ranges_missing, vec![(str_open("2020-04-01 00:00:00"), str_open("2020-05-01 00:00:00"))]);
+    }
 }
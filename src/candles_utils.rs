@@ -1,8 +1,9 @@
 use std::str::FromStr;
 
-use crate::model::{candle::Candle, open_close::OpenClose};
+use crate::model::{candle::Candle, exchange_name::ExchangeName, interval::Interval, market::Market, open_close::OpenClose};
+use anyhow::bail;
 use binance::model::KlineSummary;
-use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 use rust_decimal::Decimal;
 use ta::DataItem;
 
@@ -23,7 +24,7 @@ pub fn fdec(value: f64) -> Decimal {
 }
 
 /// Convert binance Kline to app Candle
-pub fn kline_to_candle(summary: &KlineSummary, symbol: &str, minutes: u32, id: &Decimal) -> Candle {
+pub fn kline_to_candle(summary: &KlineSummary, symbol: &str, minutes: u32, market: &Market, id: &Decimal) -> Candle {
     let open_time = timestamp_to_datetime(&(summary.open_time as u64));
     let close_time = timestamp_to_datetime(&(summary.close_time as u64));
 
@@ -38,9 +39,32 @@ pub fn kline_to_candle(summary: &KlineSummary, symbol: &str, minutes: u32, id: &
         close: fdec(summary.close),
         volume: fdec(summary.volume),
         close_time,
+        market: market.to_string(),
+        exchange: ExchangeName::Binance.to_string(),
     }
 }
 
+/// Convert Binance kline csv line (e.g. from data.binance.vision dumps) to Binance Kline
+pub fn csv_to_kline(line: &str) -> anyhow::Result<KlineSummary> {
+    let fields = line.split(',').map(|f| f.trim()).collect::<Vec<_>>();
+    if fields.len() < 11 {
+        bail!("Kline csv line has {} fields, expected 11 or more: {}", fields.len(), line);
+    }
+    Ok(KlineSummary {
+        open_time: fields[0].parse()?,
+        open: fields[1].parse()?,
+        high: fields[2].parse()?,
+        low: fields[3].parse()?,
+        close: fields[4].parse()?,
+        volume: fields[5].parse()?,
+        close_time: fields[6].parse()?,
+        quote_asset_volume: fields[7].parse()?,
+        number_of_trades: fields[8].parse()?,
+        taker_buy_base_asset_volume: fields[9].parse()?,
+        taker_buy_quote_asset_volume: fields[10].parse()?,
+    })
+}
+
 /// Convert numeric date to String iso formatted
 pub fn timestamp_to_datetime(timestamp: &u64) -> DateTime<Utc> {
     let naive = NaiveDateTime::from_timestamp((timestamp / 1000) as i64, 0);
@@ -95,7 +119,7 @@ pub fn _candles_sorted_ok(candles: &[&Candle]) -> bool {
 }
 
 /// Returns inconsistent candles
-pub fn inconsistent_candles(candles: &[&Candle], duration: &Duration) -> Vec<Candle> {
+pub fn inconsistent_candles(candles: &[&Candle], interval: &Interval) -> Vec<Candle> {
     candles
         .iter()
         .map(Some)
@@ -104,7 +128,7 @@ pub fn inconsistent_candles(candles: &[&Candle], duration: &Duration) -> Vec<Can
                 if let Some(current_c) = current {
                     let previous_d = previous_c.open_time;
                     let current_d = current_c.open_time;
-                    if current_d - previous_d != *duration {
+                    if current_d != interval.next_open(&previous_d) {
                         previous.0.push((*current_c).clone());
                     }
                 }
@@ -178,10 +202,10 @@ pub mod tests {
         assert_eq!(_candles_sorted_ok(&[&c1, &c1]), false);
         assert_eq!(_candles_sorted_ok(&[&c2, &c2]), false);
 
-        assert_eq!(inconsistent_candles(&[&c1, &c2], &d15m).len(), 0);
-        assert_eq!(inconsistent_candles(&[&c2, &c1], &d15m).len(), 1);
-        assert_eq!(inconsistent_candles(&[&c1, &c1], &d15m).len(), 1);
-        assert_eq!(inconsistent_candles(&[&c2, &c2], &d15m).len(), 1);
+        assert_eq!(inconsistent_candles(&[&c1, &c2], &Interval::M15).len(), 0);
+        assert_eq!(inconsistent_candles(&[&c2, &c1], &Interval::M15).len(), 1);
+        assert_eq!(inconsistent_candles(&[&c1, &c1], &Interval::M15).len(), 1);
+        assert_eq!(inconsistent_candles(&[&c2, &c2], &Interval::M15).len(), 1);
 
         let c3 = Candle::new(
             0,
@@ -209,6 +233,6 @@ pub mod tests {
             fdec(100.0),
         );
 
-        assert_eq!(inconsistent_candles(&[&c3, &c4], &d15m).len(), 1);
+        assert_eq!(inconsistent_candles(&[&c3, &c4], &Interval::M15).len(), 1);
     }
 }
@@ -0,0 +1,274 @@
+use crate::model::{candle::Candle, interval::Interval};
+use ifmt::{iformat, iwrite};
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::{collections::BTreeMap, fmt};
+
+/// Integrity rule a candle can break
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rule {
+    /// Open time not on a bound of interval
+    Misaligned,
+    /// Close time isn't open time + interval - 1s
+    CloseTime,
+    /// High under max of open and close or low over min of them
+    OhlcBounds,
+    /// Zero or negative price
+    NonPositivePrice,
+    NegativeVolume,
+    /// Open time of a previous candle repeated
+    Duplicate,
+    /// High or low away from previous close beyond threshold
+    Spike,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Rule::Misaligned => "misaligned",
+            Rule::CloseTime => "close_time",
+            Rule::OhlcBounds => "ohlc_bounds",
+            Rule::NonPositivePrice => "non_positive_price",
+            Rule::NegativeVolume => "negative_volume",
+            Rule::Duplicate => "duplicate",
+            Rule::Spike => "spike",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub rule: Rule,
+    pub detail: String,
+}
+
+impl Violation {
+    fn new(rule: Rule, detail: String) -> Self {
+        Self { rule, detail }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        iwrite!(f, "{self.rule}: {self.detail}")
+    }
+}
+
+/// Candle breaking at least one rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidCandle {
+    pub candle: Candle,
+    pub violations: Vec<Violation>,
+}
+
+impl InvalidCandle {
+    /// Violations joined, kept as reason of quarantine
+    pub fn reason(&self) -> String {
+        self.violations.iter().map(|v| v.to_string()).collect::<Vec<_>>().join("; ")
+    }
+}
+
+/// Candles checked and the invalid ones, sorted by open time
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub candles: usize,
+    pub invalid: Vec<InvalidCandle>,
+}
+
+impl ValidationReport {
+    /// Candles breaking each rule
+    pub fn counts(&self) -> BTreeMap<Rule, usize> {
+        let mut counts = BTreeMap::new();
+        for violation in self.invalid.iter().flat_map(|i| i.violations.iter()) {
+            *counts.entry(violation.rule).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let counts = self
+            .counts()
+            .iter()
+            .map(|(rule, count)| (rule.to_string(), json!(count)))
+            .collect::<serde_json::Map<_, _>>();
+        let invalid = self
+            .invalid
+            .iter()
+            .map(|i| {
+                json!({
+                    "id": i.candle.id.to_string(),
+                    "open_time": i.candle.open_time.to_rfc3339(),
+                    "violations": i.violations.iter().map(|v| json!({"rule": v.rule.to_string(), "detail": v.detail})).collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({ "candles": self.candles, "counts": counts, "invalid": invalid })
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = self
+            .counts()
+            .iter()
+            .map(|(rule, count)| iformat!("{rule} {count}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        iwrite!(f, "{self.candles} candles, {self.invalid.len()} invalid")?;
+        if !counts.is_empty() {
+            iwrite!(f, ": {counts}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Rules a candle breaks by itself: aligned open time, close time of interval, prices bounded by high and low and positive, no negative volume
+pub fn candle_violations(candle: &Candle, interval: &Interval) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    if interval.open_trunc(&candle.open_time) != candle.open_time {
+        violations.push(Violation::new(
+            Rule::Misaligned,
+            iformat!("open time {candle.open_time} isn't aligned on {interval}"),
+        ));
+    }
+    let close_time = interval.close(&candle.open_time);
+    if candle.close_time != close_time {
+        violations.push(Violation::new(Rule::CloseTime, iformat!("close time {candle.close_time} isn't {close_time}")));
+    }
+    if candle.high < candle.open.max(candle.close) || candle.low > candle.open.min(candle.close) {
+        violations.push(Violation::new(
+            Rule::OhlcBounds,
+            iformat!("open {candle.open} high {candle.high} low {candle.low} close {candle.close}"),
+        ));
+    }
+    if [candle.open, candle.high, candle.low, candle.close].iter().any(|p| *p <= Decimal::ZERO) {
+        violations.push(Violation::new(
+            Rule::NonPositivePrice,
+            iformat!("open {candle.open} high {candle.high} low {candle.low} close {candle.close}"),
+        ));
+    }
+    if candle.volume.is_sign_negative() && !candle.volume.is_zero() {
+        violations.push(Violation::new(Rule::NegativeVolume, iformat!("volume {candle.volume}")));
+    }
+    violations
+}
+
+/// Checks candles sorted by open time against every rule.
+///
+/// A candle repeating an open time is a duplicate, a spike is a high or low further from previous close than `spike` (ratio, e.g. 0.2 for 20%).
+pub fn validate_candles(candles: &[Candle], interval: &Interval, spike: Decimal) -> ValidationReport {
+    let mut report = ValidationReport {
+        candles: candles.len(),
+        ..ValidationReport::default()
+    };
+    let mut previous: Option<&Candle> = None;
+    for candle in candles.iter() {
+        let mut violations = candle_violations(candle, interval);
+        match previous {
+            Some(previous) if previous.open_time == candle.open_time => {
+                violations.push(Violation::new(Rule::Duplicate, iformat!("open time {candle.open_time} of candle {previous.id}")));
+            }
+            Some(previous) if previous.close > Decimal::ZERO => {
+                let change = (candle.high / previous.close - Decimal::ONE).max(Decimal::ONE - candle.low / previous.close);
+                if change > spike {
+                    violations.push(Violation::new(Rule::Spike, iformat!("{change.round_dp(4)} from previous close {previous.close}")));
+                }
+            }
+            _ => {}
+        }
+        if violations.iter().all(|v| v.rule != Rule::Duplicate) {
+            previous = Some(candle);
+        }
+        if !violations.is_empty() {
+            report.invalid.push(InvalidCandle {
+                candle: candle.clone(),
+                violations,
+            });
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candles_utils::{fdec, str_to_datetime};
+
+    fn candle(open_time: &str, close_time: &str, open: f64, high: f64, low: f64, close: f64, volume: f64) -> Candle {
+        Candle::new(
+            0,
+            open_time,
+            close_time,
+            "BTCUSDT",
+            15,
+            fdec(open),
+            fdec(high),
+            fdec(low),
+            fdec(close),
+            fdec(volume),
+        )
+    }
+
+    #[test]
+    fn candle_violations_test() {
+        let rules = |c: &Candle| candle_violations(c, &Interval::M15).iter().map(|v| v.rule).collect::<Vec<_>>();
+
+        assert!(rules(&candle("2020-11-11 10:00:00", "2020-11-11 10:14:59", 10.0, 12.0, 9.0, 11.0, 1.0)).is_empty());
+        assert_eq!(
+            rules(&candle("2020-11-11 10:05:00", "2020-11-11 10:14:59", 10.0, 12.0, 9.0, 11.0, 1.0)),
+            vec![Rule::Misaligned]
+        );
+        assert_eq!(
+            rules(&candle("2020-11-11 10:00:00", "2020-11-11 10:15:00", 10.0, 12.0, 9.0, 11.0, 1.0)),
+            vec![Rule::CloseTime]
+        );
+        assert_eq!(
+            rules(&candle("2020-11-11 10:00:00", "2020-11-11 10:14:59", 10.0, 10.5, 9.0, 11.0, 1.0)),
+            vec![Rule::OhlcBounds]
+        );
+        assert_eq!(
+            rules(&candle("2020-11-11 10:00:00", "2020-11-11 10:14:59", 10.0, 12.0, 10.5, 11.0, 1.0)),
+            vec![Rule::OhlcBounds]
+        );
+        assert_eq!(
+            rules(&candle("2020-11-11 10:00:00", "2020-11-11 10:14:59", 10.0, 12.0, 0.0, 11.0, 1.0)),
+            vec![Rule::NonPositivePrice]
+        );
+        assert_eq!(
+            rules(&candle("2020-11-11 10:00:00", "2020-11-11 10:14:59", 10.0, 12.0, 9.0, 11.0, -1.0)),
+            vec![Rule::NegativeVolume]
+        );
+        assert!(rules(&candle("2020-11-11 10:00:00", "2020-11-11 10:14:59", 10.0, 12.0, 9.0, 11.0, 0.0)).is_empty());
+    }
+
+    #[test]
+    fn validate_candles_test() {
+        let candles = [
+            candle("2020-11-11 10:00:00", "2020-11-11 10:14:59", 10.0, 12.0, 9.0, 11.0, 1.0),
+            candle("2020-11-11 10:15:00", "2020-11-11 10:29:59", 11.0, 12.0, 10.0, 11.5, 1.0),
+            candle("2020-11-11 10:15:00", "2020-11-11 10:29:59", 11.0, 12.0, 10.0, 11.5, 1.0),
+            candle("2020-11-11 10:30:00", "2020-11-11 10:44:59", 11.5, 20.0, 11.0, 12.0, 1.0),
+            candle("2020-11-11 10:45:00", "2020-11-11 10:59:59", 12.0, 12.5, 11.5, 12.0, -2.0),
+        ];
+
+        let report = validate_candles(&candles, &Interval::M15, fdec(0.2));
+
+        let invalid = report
+            .invalid
+            .iter()
+            .map(|i| (i.candle.open_time, i.violations.iter().map(|v| v.rule).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            invalid,
+            vec![
+                (str_to_datetime("2020-11-11 10:15:00"), vec![Rule::Duplicate]),
+                (str_to_datetime("2020-11-11 10:30:00"), vec![Rule::Spike]),
+                (str_to_datetime("2020-11-11 10:45:00"), vec![Rule::NegativeVolume]),
+            ]
+        );
+        assert_eq!(report.to_string(), "5 candles, 3 invalid: negative_volume 1, duplicate 1, spike 1");
+        assert_eq!(report.to_json()["counts"]["spike"], 1);
+        assert_eq!(report.to_json()["invalid"][1]["violations"][0]["detail"], "0.7391 from previous close 11.5");
+    }
+}
@@ -1,26 +1,110 @@
-use std::time::Instant;
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
 use anyhow::bail;
-use chrono::{Duration, Utc};
-use ifmt::iformat;
-use log::{error, info};
-use rust_decimal_macros::dec;
+use async_std::task;
+use chrono::{DateTime, Duration, Utc};
+use ifmt::{iformat, iwrite};
+use log::{info, warn};
+use rust_decimal::Decimal;
 
 use crate::{
+    candles_import::{import_paths, read_csv_files, CandlesImport, ColumnMapping, ImportStats},
+    candles_range::candles_to_ranges_missing,
     candles_utils::inconsistent_candles,
+    candles_validation::{validate_candles, ValidationReport},
     config::{candles_selection::CandlesSelection, symbol_minutes::SymbolMinutes},
     exchange::Exchange,
-    repository::Repository,
+    model::{candle::Candle, interval::Interval, market::Market, open_close::OpenClose, resampled_candle::ResampledCandle},
+    repository::{Repository, UpsertStats},
+    trades_resample::{candles_mismatches, trades_to_candles, Bucket},
 };
 
+/// Gaps of stored candles found by a repair, the unfillable ones still missing after fetching them from exchange
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RepairStats {
+    pub gaps: usize,
+    pub filled: usize,
+    /// Open times of first and last candle of each range still missing (e.g. exchange maintenance windows)
+    pub unfillable: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+    pub inserted: u64,
+}
+
+impl fmt::Display for RepairStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        iwrite!(
+            f,
+            "{self.gaps} gaps found, {self.filled} filled, {self.unfillable.len()} unfillable: {self.inserted} candles inserted"
+        )
+    }
+}
+
+/// Candles a sync added or revised on symbol minutes and last close time stored after it
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncSummary {
+    pub symbol_minutes: SymbolMinutes,
+    pub stats: UpsertStats,
+    pub last_close_time: Option<DateTime<Utc>>,
+}
+
+impl fmt::Display for SyncSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sm = &self.symbol_minutes;
+        let last_close_time = self.last_close_time.map(|t| t.to_string()).unwrap_or_else(|| "none".to_string());
+        iwrite!(
+            f,
+            "{sm.symbol} {sm.interval} {sm.market}: {self.stats.inserted} added, {self.stats.revised} revised, last close time {last_close_time}"
+        )
+    }
+}
+
+/// Pairs synchronized at the same time, exchange requests still wait for its rate limits
+pub const SYNC_CONCURRENCY: usize = 4;
+
+/// Synchronizes each pair (see `Checker::synchronize`), at most `SYNC_CONCURRENCY` at the same time.
+///
+/// A pair failing doesn't stop the others, results are in order of pairs.
+pub async fn synchronize_all(
+    pairs: &[SymbolMinutes], repository: Arc<dyn Repository + Send + Sync>, exchange: Arc<dyn Exchange + Send + Sync>,
+) -> Vec<(SymbolMinutes, anyhow::Result<SyncSummary>)> {
+    let queue = Arc::new(Mutex::new(pairs.iter().cloned().enumerate().collect::<VecDeque<_>>()));
+    // Exchange calls block, each worker runs on a blocking thread
+    let workers = (0..SYNC_CONCURRENCY.min(pairs.len()))
+        .map(|_| {
+            let (queue, repository, exchange) = (queue.clone(), repository.clone(), exchange.clone());
+            task::spawn_blocking(move || {
+                let mut results = Vec::new();
+                while let Some((index, symbol_minutes)) = queue.lock().unwrap().pop_front() {
+                    let checker = Checker::new(&symbol_minutes, repository.as_ref(), exchange.as_ref());
+                    let result = task::block_on(checker.synchronize());
+                    results.push((index, symbol_minutes, result));
+                }
+                results
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut results = Vec::new();
+    for worker in workers {
+        results.extend(worker.await);
+    }
+    results.sort_by_key(|(index, _, _)| *index);
+    results.into_iter().map(|(_, symbol_minutes, result)| (symbol_minutes, result)).collect()
+}
+
 pub struct Checker<'a> {
-    repo: &'a Repository,
-    exchange: &'a Exchange,
+    repo: &'a dyn Repository,
+    exchange: &'a dyn Exchange,
     symbol_minutes: &'a SymbolMinutes,
 }
 
 impl<'a> Checker<'a> {
-    pub fn new(symbol_minutes: &'a SymbolMinutes, repository: &'a Repository, exchange: &'a Exchange) -> Self {
+    pub fn new(symbol_minutes: &'a SymbolMinutes, repository: &'a dyn Repository, exchange: &'a dyn Exchange) -> Self {
         Checker {
             repo: repository,
             exchange,
@@ -28,91 +112,585 @@ impl<'a> Checker<'a> {
         }
     }
 
-    pub fn synchronize(&self) -> anyhow::Result<()> {
-        loop {
-            self.repo.delete_last_candle(&self.symbol_minutes);
+    /// Retrieves candles from last one stored (or from last 180 days), candles revised by exchange are updated
+    pub async fn synchronize(&self) -> anyhow::Result<SyncSummary> {
+        let last_close_time = self.repo.last_close_time(self.symbol_minutes).await?;
+        info!("{}", iformat!("Last close time: {last_close_time:?}"));
+
+        // Last candle could be incomplete when it was imported, it is retrieved again
+        let start_time = match last_close_time {
+            Some(last_close_time) => self.symbol_minutes.interval.open_trunc(&last_close_time),
+            None => Utc::now() - Duration::days(180),
+        };
 
-            let mut last_close_time = self.repo.last_close_time(&self.symbol_minutes);
+        // Exchange walks pages from start time until now
+        let mut candles = self.exchange.candles_with_progress(self.symbol_minutes, &Some(start_time), &None, &mut |p| {
+            info!(
+                "{}",
+                iformat!("Retrieved page {p.page}/{p.pages} candles: {p.candles} last open time: {p.last_open_time:?}")
+            );
+        })?;
 
-            // If not found last candle then assume last 180 days
-            let last_close_time = last_close_time.get_or_insert_with(|| Utc::now() - Duration::days(180));
+        let stats = self.repo.upsert_candles(&mut candles).await?;
+        info!("{}", iformat!("Imported candles: {stats}"));
+        Ok(SyncSummary {
+            symbol_minutes: self.symbol_minutes.clone(),
+            stats,
+            last_close_time: self.repo.last_close_time(self.symbol_minutes).await?,
+        })
+    }
 
-            info!("{}", iformat!("Last close time: {last_close_time:?}"));
+    /// Retrieves funding rates after last one stored (or from last 180 days)
+    pub async fn synchronize_funding_rates(&self) -> anyhow::Result<()> {
+        self.check_futures()?;
+        let symbol = &self.symbol_minutes.symbol;
+        let last_funding_time = self.repo.last_funding_time(&self.symbol_minutes.exchange, symbol).await?;
+        info!("{}", iformat!("Last funding time: {last_funding_time:?}"));
 
-            let d1 = dec!(1);
+        let start_time = last_funding_time
+            .map(|t| t + Duration::seconds(1))
+            .unwrap_or_else(|| Utc::now() - Duration::days(180));
+        let funding_rates = self.exchange.funding_rates(symbol, &start_time, &Utc::now())?;
+        let inserted = self.repo.insert_funding_rates(&funding_rates).await?;
 
-            let mut candles = self.exchange.candles(
-                &self.symbol_minutes,
-                &Some(*last_close_time),
-                &None, //  + Duration::minutes(*self.minutes as i64)
-            )?;
+        info!("{}", iformat!("Imported funding rates: {inserted}"));
+        Ok(())
+    }
 
-            let mut last_id = self.repo.last_id();
+    /// Retrieves open interest snapshots of interval after last one stored (or from last 30 days)
+    pub async fn synchronize_open_interests(&self) -> anyhow::Result<()> {
+        self.check_futures()?;
+        let symbol = &self.symbol_minutes.symbol;
+        let interval = &self.symbol_minutes.interval;
+        let last_time = self.repo.last_open_interest_time(&self.symbol_minutes.exchange, symbol, interval).await?;
+        info!("{}", iformat!("Last open interest time: {last_time:?}"));
 
-            // Assign id to new candles
-            candles.iter_mut().for_each(|c| {
-                c.id = {
-                    last_id += d1;
-                    last_id
-                }
-            });
-
-            // ### This is a cool example to demonstrate error handling, where error is a type, it's possible capture, filter and transform (adding some semi-context with tuples)
-            // Insert candles on repository with `add_candle` and filter by errors
-            let candles_errors = candles
-                .iter()
-                .map(|c| (c, self.repo.insert_candle(c)))
-                .filter(|cr| cr.1.is_err())
-                .collect::<Vec<_>>();
-            if !candles_errors.is_empty() {
-                error!("{}", iformat!("Candles add error: {candles_errors.len()}"));
-                bail!("Candles add error");
+        let start_time = last_time.map(|t| t + Duration::seconds(1)).unwrap_or_else(|| Utc::now() - Duration::days(30));
+        let open_interests = self.exchange.open_interests(symbol, interval, &start_time, &Utc::now())?;
+        let inserted = self.repo.insert_open_interests(&open_interests).await?;
+
+        info!("{}", iformat!("Imported open interests: {inserted}"));
+        Ok(())
+    }
+
+    /// Imports aggregate trades between start and end time a day at a time, resuming after last trade stored
+    pub async fn import_trades(&self, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> anyhow::Result<()> {
+        let symbol = &self.symbol_minutes.symbol;
+        let market = &self.symbol_minutes.market;
+        let mut start_time = *start_time;
+        if let Some((last_id, last_time)) = self.repo.last_agg_trade(&self.symbol_minutes.exchange, symbol, market).await? {
+            info!("{}", iformat!("Last aggregate trade: {last_id} {last_time}"));
+            if last_time >= start_time && last_time < *end_time {
+                start_time = last_time + Duration::milliseconds(1);
             }
+        }
+
+        let mut imported = 0;
+        while start_time <= *end_time {
+            let day_end = (start_time + Duration::days(1) - Duration::milliseconds(1)).min(*end_time);
+            let trades = self.exchange.agg_trades(symbol, market, &start_time, &day_end)?;
+            imported += self.repo.insert_agg_trades(&trades).await?;
+            info!("{}", iformat!("Imported aggregate trades until {day_end}: {imported}"));
+            start_time = day_end + Duration::milliseconds(1);
+        }
+        Ok(())
+    }
 
-            info!("{}", iformat!("Imported candles: {candles.len()}"));
-            if candles.is_empty() {
-                break;
+    /// Builds candles of bucket from stored trades, checked against stored klines when bucket is an exchange interval
+    pub async fn resample_trades(&self, bucket: &Bucket, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> anyhow::Result<Vec<Candle>> {
+        let symbol = &self.symbol_minutes.symbol;
+        let market = &self.symbol_minutes.market;
+        let trades = self
+            .repo
+            .agg_trades(&self.symbol_minutes.exchange, symbol, market, start_time, end_time)
+            .await?;
+        let candles = trades_to_candles(&trades, symbol, market, bucket);
+        info!("{}", iformat!("Resampled {trades.len()} aggregate trades to {candles.len()} candles {bucket}"));
+
+        if let (Some(interval), Some(first), Some(last)) = (bucket.interval(), candles.first(), candles.last()) {
+            let symbol_minutes = SymbolMinutes {
+                interval,
+                ..self.symbol_minutes.clone()
+            };
+            let klines = self.repo.candles_by_time(&symbol_minutes, &first.open_time, &last.open_time).await?;
+            let mismatches = candles_mismatches(&candles, &klines);
+            for mismatch in mismatches.iter() {
+                warn!("{}", iformat!("Resampled candle differs from kline: {mismatch}"));
+            }
+            info!("{}", iformat!("Checked against {klines.len()} klines, mismatches: {mismatches.len()}"));
+        }
+        Ok(candles)
+    }
+
+    /// Imports candles from csv files of path (a file or a directory), plain or zipped, in Binance kline layout unless mapping is given.
+    ///
+    /// Invalid rows are rejected, rows repeating an open time or already stored are skipped, each file is inserted at once.
+    pub async fn import_candles(&self, path: &Path, mapping: Option<ColumnMapping>, delimiter: char) -> anyhow::Result<ImportStats> {
+        let import = match mapping {
+            Some(mapping) => CandlesImport::new(self.symbol_minutes, mapping, delimiter),
+            None => CandlesImport::binance(self.symbol_minutes),
+        };
+        let mut stats = ImportStats::default();
+        for path in import_paths(path)? {
+            for (name, content) in read_csv_files(&path)? {
+                let parsed = import.parse(&content)?;
+                for rejected in parsed.rejected.iter() {
+                    warn!("{}", iformat!("Import {name} {rejected}"));
+                }
+                let mut candles = parsed.candles;
+                let rows = candles.len();
+                candles.sort_by_key(|c| c.open_time);
+                candles.dedup_by_key(|c| c.open_time);
+                let distinct = candles.len();
+
+                if let (Some(first), Some(last)) = (candles.first(), candles.last()) {
+                    let stored = self.repo.candles_by_time(self.symbol_minutes, &first.open_time, &last.open_time).await?;
+                    let stored = stored.iter().map(|c| c.open_time).collect::<HashSet<_>>();
+                    candles.retain(|c| !stored.contains(&c.open_time));
+                }
+                let inserted = if candles.is_empty() {
+                    0
+                } else {
+                    self.repo.insert_candles(&mut candles).await?.rows
+                };
+
+                let file_stats = ImportStats {
+                    files: 1,
+                    rows: rows + parsed.rejected.len(),
+                    rejected: parsed.rejected.len(),
+                    duplicated: rows - distinct,
+                    existing: distinct - candles.len(),
+                    inserted,
+                };
+                info!("{}", iformat!("Imported {name}: {file_stats}"));
+                stats += file_stats;
             }
         }
+        Ok(stats)
+    }
+
+    /// Aggregates stored candles into interval, incomplete buckets (gaps on stored candles) are warned and kept flagged.
+    ///
+    /// When materialized resampled candles are saved, updating the ones saved before.
+    pub async fn resample_candles(
+        &self, interval: &Interval, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>, materialize: bool,
+    ) -> anyhow::Result<Vec<ResampledCandle>> {
+        let resampled = self.repo.resample_candles(self.symbol_minutes, interval, start_time, end_time).await?;
+        let incomplete = resampled.iter().filter(|r| !r.is_complete()).collect::<Vec<_>>();
+        for candle in incomplete.iter() {
+            warn!(
+                "{}",
+                iformat!("Incomplete {interval} candle {candle.candle.open_time}: {candle.sources} of {candle.expected} {candle.source} candles")
+            );
+        }
+        info!(
+            "{}",
+            iformat!("Resampled {self.symbol_minutes.interval} candles to {resampled.len()} candles {interval}, incomplete: {incomplete.len()}")
+        );
+        if materialize {
+            let saved = self.repo.save_resampled_candles(&resampled).await?;
+            info!("{}", iformat!("Saved resampled candles: {saved}"));
+        }
+        Ok(resampled)
+    }
+
+    fn check_futures(&self) -> anyhow::Result<()> {
+        if self.symbol_minutes.market != Market::Futures {
+            bail!("Funding rate and open interest exist only on futures market, not on {}", self.symbol_minutes.market);
+        }
         Ok(())
     }
 
-    pub fn check_inconsist(&self, repo: &Repository, selection: &CandlesSelection) {
+    pub async fn check_inconsist(&self, repo: &dyn Repository, selection: &CandlesSelection) -> anyhow::Result<()> {
         let start = Instant::now();
         let start_time = selection.start_time;
         let end_time = selection.end_time;
         info!("{}", iformat!("Check consistent: {self.symbol_minutes:?} {start_time:?} {end_time:?}"));
 
-        let candles = repo.candles_by_time(&self.symbol_minutes, &start_time, &end_time).unwrap_or_default();
+        let candles = repo.candles_by_time(self.symbol_minutes, &start_time, &end_time).await?;
 
         info!("{}", iformat!("Found candles: {candles.len()}"));
 
         let candles_ref: Vec<_> = candles.iter().collect();
 
-        let inconsist = inconsistent_candles(candles_ref.as_slice(), &Duration::minutes(self.symbol_minutes.minutes as i64));
+        let inconsist = inconsistent_candles(candles_ref.as_slice(), &self.symbol_minutes.interval);
         info!("{}", iformat!("Inconsist candles: {inconsist.len()}"));
         for candle in inconsist.iter() {
             info!("{}", iformat!("{candle}"));
         }
         info!("{}", iformat!("Elapsed: {start.elapsed():?}"));
+        Ok(())
     }
 
-    pub fn delete_inconsist(&self) {
+    /// Finds gaps of stored candles between start and end time and fetches exactly those ranges from exchange.
+    ///
+    /// Each gap is verified again once inserted, what exchange doesn't have is reported unfillable.
+    /// Gaps end at last closed candle, the one in progress isn't final yet.
+    pub async fn repair(&self, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> anyhow::Result<RepairStats> {
+        let interval = &self.symbol_minutes.interval;
+        let end_time = (*end_time).min(interval.prev_open(&Utc::now()));
+        let candles = self.repo.candles_by_time(self.symbol_minutes, start_time, &end_time).await?;
+        let gaps = candles_to_ranges_missing(
+            &OpenClose::from_date(start_time, interval),
+            &OpenClose::from_date(&end_time, interval),
+            interval,
+            candles.iter().collect::<Vec<_>>().as_slice(),
+        )?;
+        info!("{}", iformat!("Found candles: {candles.len()} gaps: {gaps.len()}"));
+
+        let mut stats = RepairStats {
+            gaps: gaps.len(),
+            ..RepairStats::default()
+        };
+        for (gap_start, gap_end) in gaps.iter() {
+            let (gap_start, gap_end) = (gap_start.open(interval), gap_end.open(interval));
+            let mut fetched = self.exchange.candles(self.symbol_minutes, &Some(gap_start), &Some(gap_end))?;
+            // A sync or live feed may have stored some of them since gaps were found
+            if !fetched.is_empty() {
+                stats.inserted += self.repo.upsert_candles(&mut fetched).await?.inserted;
+            }
+
+            let stored = self.repo.candles_by_time(self.symbol_minutes, &gap_start, &gap_end).await?;
+            let missing = candles_to_ranges_missing(
+                &OpenClose::from_date(&gap_start, interval),
+                &OpenClose::from_date(&gap_end, interval),
+                interval,
+                stored.iter().collect::<Vec<_>>().as_slice(),
+            )?;
+            info!("{}", iformat!("Gap {gap_start} - {gap_end}: {fetched.len()} candles fetched"));
+            if missing.is_empty() {
+                stats.filled += 1;
+            }
+            for (missing_start, missing_end) in missing.iter() {
+                let missing = (missing_start.open(interval), missing_end.open(interval));
+                warn!("{}", iformat!("Unfillable gap {missing.0} - {missing.1}: not on exchange"));
+                stats.unfillable.push(missing);
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Checks stored candles between start and end time against integrity rules (see `validate_candles`), spike as a ratio of previous close.
+    ///
+    /// When quarantined invalid candles are moved out of candles to table `candle_quarantine` with their violations.
+    pub async fn validate_candles(
+        &self, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>, spike: Decimal, quarantine: bool,
+    ) -> anyhow::Result<ValidationReport> {
+        let candles = self.repo.candles_by_time(self.symbol_minutes, start_time, end_time).await?;
+        let report = validate_candles(&candles, &self.symbol_minutes.interval, spike);
+        for invalid in report.invalid.iter() {
+            warn!("{}", iformat!("Invalid candle {invalid.candle} (id {invalid.candle.id}): {invalid.reason()}"));
+        }
+        info!("{}", iformat!("Validated {report}"));
+        if quarantine && !report.invalid.is_empty() {
+            let candles = report.invalid.iter().map(|i| (i.candle.clone(), i.reason())).collect::<Vec<_>>();
+            let moved = self.repo.quarantine_candles(&candles).await?;
+            info!("{}", iformat!("Quarantined candles: {moved}"));
+        }
+        Ok(report)
+    }
+
+    pub async fn delete_inconsist(&self) -> anyhow::Result<()> {
         let end_time = Utc::now();
         let start_time = end_time - Duration::days(180);
-        let repo = Repository::new().unwrap();
-
-        let candles = repo.candles_by_time(&self.symbol_minutes, &start_time, &end_time).unwrap_or_default();
+        let candles = self.repo.candles_by_time(self.symbol_minutes, &start_time, &end_time).await?;
 
         info!("{}", iformat!("Found candles: {candles.len()}"));
 
         let candles_ref: Vec<_> = candles.iter().collect();
 
         info!("Inconsist candles:");
-        let inconsist = inconsistent_candles(candles_ref.as_slice(), &Duration::minutes(self.symbol_minutes.minutes as i64));
+        let inconsist = inconsistent_candles(candles_ref.as_slice(), &self.symbol_minutes.interval);
         for candle in inconsist.iter() {
             info!("{}", iformat!("{candle}"));
-            self.repo.delete_candle(&candle.id);
+            self.repo.delete_candle(&candle.id).await?;
         }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        candles_utils::str_to_datetime,
+        exchange::replay_exchange::{tests::replay_exchange, ReplayExchange},
+        model::{
+            agg_trade::AggTrade, exchange_name::ExchangeName, funding_rate::FundingRate, interval::Interval, open_interest::OpenInterest,
+            symbol_info::SymbolInfo,
+        },
+        repository::memory_repository::MemoryRepository,
+    };
+    use rust_decimal::Decimal;
+
+    #[async_std::test]
+    async fn synchronize_overlap_test() -> anyhow::Result<()> {
+        let repo = MemoryRepository::new();
+        let exchange = replay_exchange();
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Spot);
+
+        // Last candle stored while still open, with a close price revised later by exchange
+        let klines = exchange.candles(&symbol_minutes, &None, &None)?;
+        let mut history = klines[..10].to_vec();
+        history[9].close += Decimal::ONE;
+        repo.insert_candles(&mut history).await?;
+
+        let checker = Checker::new(&symbol_minutes, &repo, &exchange);
+        checker.synchronize().await?;
+        // Same range again, nothing changes
+        checker.synchronize().await?;
+
+        let stored = repo
+            .candles_by_time(&symbol_minutes, &klines[0].open_time, &klines.last().unwrap().open_time)
+            .await?;
+        assert_eq!(
+            stored.iter().map(|c| (c.open_time, c.close)).collect::<Vec<_>>(),
+            klines.iter().map(|c| (c.open_time, c.close)).collect::<Vec<_>>()
+        );
+        assert_eq!(stored[9].id, history[9].id);
+        assert_eq!(repo.candle_revision(&history[9].id).await?.map(|r| r.0), Some(1));
+        assert_eq!(repo.candle_revision(&history[8].id).await?, None);
+        Ok(())
+    }
+
+    /// Pairs synchronized together, one failing without stopping the others
+    #[async_std::test]
+    async fn synchronize_all_test() -> anyhow::Result<()> {
+        let repo = Arc::new(MemoryRepository::new());
+        let exchange = Arc::new(replay_exchange());
+        let spot = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Spot);
+        let futures = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Futures);
+        let unknown = SymbolMinutes::new("NOPEUSDT", &Interval::M15, &Market::Spot);
+        let mut klines = Vec::new();
+        for symbol_minutes in [&spot, &futures].iter() {
+            let candles = exchange.candles(symbol_minutes, &None, &None)?;
+            repo.insert_candles(&mut candles[..10].to_vec()).await?;
+            klines.push(candles);
+        }
+
+        let results = synchronize_all(&[spot.clone(), unknown.clone(), futures.clone()], repo.clone(), exchange.clone()).await;
+
+        assert_eq!(results.iter().map(|(pair, _)| pair.clone()).collect::<Vec<_>>(), vec![spot, unknown, futures]);
+        assert!(results[1].1.is_err());
+        for (i, result) in [&results[0].1, &results[2].1].iter().enumerate() {
+            let summary = result.as_ref().unwrap();
+            assert_eq!(summary.stats.inserted as usize, klines[i].len() - 10);
+            assert_eq!(summary.last_close_time, Some(klines[i].last().unwrap().close_time));
+        }
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn synchronize_futures_data_test() -> anyhow::Result<()> {
+        let repo = MemoryRepository::new();
+        let exchange = replay_exchange();
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Futures);
+
+        // Repository already knows first day, sync retrieves the remaining
+        let start = str_to_datetime("2020-11-10 00:00:00");
+        let first_day = str_to_datetime("2020-11-10 23:59:59");
+        repo.insert_funding_rates(&exchange.funding_rates("BTCUSDT", &start, &first_day)?).await?;
+        repo.insert_open_interests(&exchange.open_interests("BTCUSDT", &Interval::M15, &start, &first_day)?)
+            .await?;
+
+        let checker = Checker::new(&symbol_minutes, &repo, &exchange);
+        checker.synchronize_funding_rates().await?;
+        checker.synchronize_open_interests().await?;
+        // Nothing new on second sync
+        checker.synchronize_funding_rates().await?;
+
+        let end = str_to_datetime("2020-11-12 23:59:59");
+        let funding_rates = repo.funding_rates(&ExchangeName::Binance, "BTCUSDT", &start, &end).await?;
+        let open_interests = repo.open_interests(&ExchangeName::Binance, "BTCUSDT", &Interval::M15, &start, &end).await?;
+
+        assert_eq!(funding_rates, exchange.funding_rates("BTCUSDT", &start, &end)?);
+        assert_eq!(open_interests.len(), 288);
+
+        let spot = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Spot);
+        assert!(Checker::new(&spot, &repo, &exchange).synchronize_funding_rates().await.is_err());
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn import_resample_trades_test() -> anyhow::Result<()> {
+        let repo = MemoryRepository::new();
+        let exchange = replay_exchange();
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Futures);
+
+        let start = str_to_datetime("2020-11-11 00:00:00");
+        let end = str_to_datetime("2020-11-11 23:59:59");
+        let checker = Checker::new(&symbol_minutes, &repo, &exchange);
+        checker.import_trades(&start, &end).await?;
+        // Resumes after last trade, nothing new
+        checker.import_trades(&start, &end).await?;
+
+        let trades = repo.agg_trades(&ExchangeName::Binance, "BTCUSDT", &Market::Futures, &start, &end).await?;
+        let candles = checker.resample_trades(&"2m".parse()?, &start, &end).await?;
+
+        assert_eq!(trades, exchange.agg_trades("BTCUSDT", &Market::Futures, &start, &end)?);
+        assert_eq!(candles.len(), 29);
+        Ok(())
+    }
+
+    /// Two gaps inside exchange data filled, one after its end left unfillable
+    #[async_std::test]
+    async fn repair_test() -> anyhow::Result<()> {
+        let repo = MemoryRepository::new();
+        let exchange = replay_exchange();
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Spot);
+        let klines = exchange.candles(&symbol_minutes, &None, &None)?;
+        let mut history = klines
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !(10..13).contains(i) && *i != 100)
+            .map(|(_, c)| c.clone())
+            .collect::<Vec<_>>();
+        repo.insert_candles(&mut history).await?;
+
+        let checker = Checker::new(&symbol_minutes, &repo, &exchange);
+        let end = klines.last().unwrap().open_time + Duration::hours(2);
+        let stats = checker.repair(&klines[0].open_time, &end).await?;
+        // Nothing left to fetch but what exchange doesn't have
+        let again = checker.repair(&klines[0].open_time, &end).await?;
+
+        let stored = repo.candles_by_time(&symbol_minutes, &klines[0].open_time, &end).await?;
+        assert_eq!((stats.gaps, stats.filled, stats.inserted), (3, 2, 4));
+        assert_eq!(stats.unfillable, vec![(klines.last().unwrap().open_time + Duration::minutes(15), end)]);
+        assert_eq!((again.gaps, again.filled, again.inserted, again.unfillable.len()), (1, 0, 0, 1));
+        assert_eq!(
+            stored.iter().map(|c| (c.open_time, c.close)).collect::<Vec<_>>(),
+            klines.iter().map(|c| (c.open_time, c.close)).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    /// Replay exchange storing first candle of each page asked, like a sync running meanwhile
+    struct RacingExchange<'a> {
+        exchange: ReplayExchange,
+        repo: &'a MemoryRepository,
+    }
+
+    impl Exchange for RacingExchange<'_> {
+        fn candles_page(
+            &self, symbol_minutes: &SymbolMinutes, start_time: &Option<DateTime<Utc>>, end_time: &Option<DateTime<Utc>>,
+        ) -> anyhow::Result<Vec<Candle>> {
+            let candles = self.exchange.candles_page(symbol_minutes, start_time, end_time)?;
+            task::block_on(self.repo.upsert_candles(&mut candles[..1.min(candles.len())].to_vec()))?;
+            Ok(candles)
+        }
+
+        fn symbols_info(&self, market: &Market) -> anyhow::Result<Vec<SymbolInfo>> {
+            self.exchange.symbols_info(market)
+        }
+
+        fn funding_rates(&self, symbol: &str, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> anyhow::Result<Vec<FundingRate>> {
+            self.exchange.funding_rates(symbol, start_time, end_time)
+        }
+
+        fn open_interests(&self, symbol: &str, interval: &Interval, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> anyhow::Result<Vec<OpenInterest>> {
+            self.exchange.open_interests(symbol, interval, start_time, end_time)
+        }
+
+        fn agg_trades(&self, symbol: &str, market: &Market, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> anyhow::Result<Vec<AggTrade>> {
+            self.exchange.agg_trades(symbol, market, start_time, end_time)
+        }
+    }
+
+    /// Candles of a gap stored by someone else while repairing don't abort it
+    #[async_std::test]
+    async fn repair_concurrent_sync_test() -> anyhow::Result<()> {
+        let repo = MemoryRepository::new();
+        let exchange = RacingExchange {
+            exchange: replay_exchange(),
+            repo: &repo,
+        };
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Spot);
+        let klines = exchange.candles(&symbol_minutes, &None, &None)?;
+        repo.delete_candles(&symbol_minutes).await?;
+        let mut history = klines
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !(10..13).contains(i))
+            .map(|(_, c)| c.clone())
+            .collect::<Vec<_>>();
+        repo.insert_candles(&mut history).await?;
+
+        let checker = Checker::new(&symbol_minutes, &repo, &exchange);
+        let (start, end) = (klines[0].open_time, klines.last().unwrap().open_time);
+        let stats = checker.repair(&start, &end).await?;
+
+        let stored = repo.candles_by_time(&symbol_minutes, &start, &end).await?;
+        assert_eq!((stats.gaps, stats.filled, stats.inserted), (1, 1, 2));
+        assert_eq!(stored.len(), klines.len());
+        Ok(())
+    }
+
+    /// Candle with broken prices and a spike reported, then quarantined out of stored candles
+    #[async_std::test]
+    async fn validate_candles_test() -> anyhow::Result<()> {
+        let repo = MemoryRepository::new();
+        let exchange = replay_exchange();
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Spot);
+        let mut klines = exchange.candles(&symbol_minutes, &None, &None)?[..20].to_vec();
+        klines[5].high = klines[5].open.min(klines[5].close) - Decimal::ONE;
+        klines[12].high *= Decimal::new(2, 0);
+        repo.insert_candles(&mut klines).await?;
+
+        let checker = Checker::new(&symbol_minutes, &repo, &exchange);
+        let (start, end) = (klines[0].open_time, klines[19].open_time);
+        let report = checker.validate_candles(&start, &end, Decimal::new(2, 1), false).await?;
+        let quarantined = checker.validate_candles(&start, &end, Decimal::new(2, 1), true).await?;
+        let again = checker.validate_candles(&start, &end, Decimal::new(2, 1), false).await?;
+
+        assert_eq!(report.to_string(), "20 candles, 2 invalid: ohlc_bounds 1, spike 1");
+        assert_eq!(quarantined, report);
+        assert_eq!((again.candles, again.invalid.len()), (18, 0));
+        let reasons = repo
+            .quarantined_candles(&symbol_minutes)
+            .await?
+            .into_iter()
+            .map(|(c, reason)| (c.id, reason))
+            .collect::<Vec<_>>();
+        assert_eq!(reasons, report.invalid.iter().map(|i| (i.candle.id, i.reason())).collect::<Vec<_>>());
+        Ok(())
+    }
+
+    /// Binance zip then a generic csv overlapping it, invalid and repeated rows skipped
+    #[async_std::test]
+    async fn import_candles_test() -> anyhow::Result<()> {
+        let repo = MemoryRepository::new();
+        let exchange = replay_exchange();
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Spot);
+        let checker = Checker::new(&symbol_minutes, &repo, &exchange);
+        let zip = Path::new("fixtures/import/BTCUSDT-15m-2020-11.zip");
+
+        let binance = checker.import_candles(zip, None, ',').await?;
+        let mapping = "open_time=Date,open=Open,high=High,low=Low,close=Close,volume=Volume".parse()?;
+        let generic = checker
+            .import_candles(Path::new("fixtures/import/BTCUSDT-15m-ohlcv.csv"), Some(mapping), ';')
+            .await?;
+        let again = checker.import_candles(zip, None, ',').await?;
+
+        let stored = repo
+            .candles_by_time(&symbol_minutes, &str_to_datetime("2020-11-10 00:00:00"), &str_to_datetime("2020-11-10 23:59:59"))
+            .await?;
+        let klines = exchange.candles(&symbol_minutes, &None, &None)?;
+        assert_eq!(
+            (binance.rows, binance.rejected, binance.duplicated, binance.existing, binance.inserted),
+            (9, 1, 1, 0, 7)
+        );
+        assert_eq!(
+            (generic.rows, generic.rejected, generic.duplicated, generic.existing, generic.inserted),
+            (5, 1, 0, 2, 2)
+        );
+        assert_eq!((again.existing, again.inserted), (7, 0));
+        assert_eq!(stored.len(), 9);
+        assert_eq!(
+            (stored[0].open_time, stored[0].close_time, stored[0].close),
+            (klines[0].open_time, klines[0].close_time, klines[0].close)
+        );
+        assert_eq!(stored[8].open_time, str_to_datetime("2020-11-10 02:15:00"));
+        assert_eq!(stored[8].close, Decimal::new(1535075, 2));
+        Ok(())
     }
 }
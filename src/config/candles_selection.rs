@@ -1,5 +1,6 @@
 use super::symbol_minutes::SymbolMinutes;
-use chrono::{DateTime, Duration, Utc};
+use crate::model::{interval::Interval, market::Market};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Hash, Eq, PartialEq, PartialOrd, Debug, Clone)]
 pub struct CandlesSelection {
@@ -12,21 +13,21 @@ pub struct CandlesSelection {
 }
 
 impl CandlesSelection {
-    pub fn last_n(symbol: &str, minutes: &u32, last: u32, now: DateTime<Utc>) -> Self {
+    pub fn last_n(symbol: &str, interval: &Interval, market: &Market, last: u32, now: DateTime<Utc>) -> Self {
         let end_time = now;
-        let start_time = end_time - (Duration::minutes((minutes * last) as i64));
+        let start_time = end_time - (interval.duration() * last as i32);
 
         Self {
-            symbol_minutes: SymbolMinutes::new(symbol, minutes),
+            symbol_minutes: SymbolMinutes::new(symbol, interval, market),
             start_time,
             end_time,
             heikin_ashi: true,
         }
     }
 
-    pub fn new(symbol: &str, minutes: &u32, start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> Self {
+    pub fn new(symbol: &str, interval: &Interval, market: &Market, start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> Self {
         Self {
-            symbol_minutes: SymbolMinutes::new(symbol, minutes),
+            symbol_minutes: SymbolMinutes::new(symbol, interval, market),
             start_time,
             end_time,
             heikin_ashi: true,
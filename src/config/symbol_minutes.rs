@@ -1,16 +1,24 @@
+use crate::model::{exchange_name::ExchangeName, interval::Interval, market::Market};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Hash, Eq, PartialEq, PartialOrd, Debug, Clone)]
 pub struct SymbolMinutes {
     pub symbol: String,
-    pub minutes: u32,
+    #[serde(alias = "minutes")]
+    pub interval: Interval,
+    #[serde(default)]
+    pub market: Market,
+    #[serde(default)]
+    pub exchange: ExchangeName,
 }
 
 impl SymbolMinutes {
-    pub fn new(symbol: &str, minutes: &u32) -> Self {
+    pub fn new(symbol: &str, interval: &Interval, market: &Market) -> Self {
         Self {
             symbol: symbol.into(),
-            minutes: *minutes,
+            interval: *interval,
+            market: *market,
+            exchange: ExchangeName::default(),
         }
     }
 }
@@ -0,0 +1,98 @@
+use crate::model::{agg_trade::AggTrade, exchange_name::ExchangeName, market::Market};
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// Max aggregate trades returned by one exchange call
+pub const AGG_TRADES_LIMIT: u16 = 1000;
+
+/// Exchange refuses start and end time more than one hour apart
+pub fn agg_trades_window() -> Duration {
+    Duration::hours(1)
+}
+
+#[derive(Debug, Deserialize)]
+struct AggTradeItem {
+    #[serde(rename = "a")]
+    id: i64,
+    #[serde(rename = "p")]
+    price: String,
+    #[serde(rename = "q")]
+    quantity: String,
+    #[serde(rename = "T")]
+    time: i64,
+    #[serde(rename = "m")]
+    is_buyer_maker: bool,
+}
+
+fn millis_to_datetime(millis: i64) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(millis).unwrap()
+}
+
+/// Parses aggregate trades response (spot `/api/v3/aggTrades` or futures `/fapi/v1/aggTrades`)
+pub fn parse_agg_trades(text: &str, symbol: &str, market: &Market) -> anyhow::Result<Vec<AggTrade>> {
+    let items: Vec<AggTradeItem> = serde_json::from_str(text)?;
+    items
+        .into_iter()
+        .map(|item| {
+            Ok(AggTrade {
+                exchange: ExchangeName::Binance,
+                symbol: symbol.to_string(),
+                market: *market,
+                id: item.id,
+                price: Decimal::from_str(&item.price)?,
+                quantity: Decimal::from_str(&item.quantity)?,
+                time: millis_to_datetime(item.time),
+                is_buyer_maker: item.is_buyer_maker,
+            })
+        })
+        .collect()
+}
+
+/// Converts Binance aggregate trades csv line (data.binance.vision dumps) to aggregate trade.
+///
+/// Fields are id, price, quantity, first trade id, last trade id, time and buyer maker (spot dumps add best match).
+pub fn csv_to_agg_trade(line: &str, symbol: &str, market: &Market) -> anyhow::Result<AggTrade> {
+    let fields = line.split(',').map(|f| f.trim()).collect::<Vec<_>>();
+    if fields.len() < 7 {
+        bail!("Aggregate trade csv line has {} fields, expected 7 or more: {}", fields.len(), line);
+    }
+    Ok(AggTrade {
+        exchange: ExchangeName::Binance,
+        symbol: symbol.to_string(),
+        market: *market,
+        id: fields[0].parse()?,
+        price: Decimal::from_str(fields[1])?,
+        quantity: Decimal::from_str(fields[2])?,
+        time: millis_to_datetime(fields[5].parse()?),
+        is_buyer_maker: fields[6].to_lowercase().parse().map_err(|_| anyhow!("Invalid buyer maker {}", fields[6]))?,
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn parse_agg_trades_test() {
+        let text = r#"[{"a":412000000,"p":"15592.18","q":"19.268","f":905000000,"l":905000000,"T":1605088843914,"m":true}]"#;
+        let trades = parse_agg_trades(text, "BTCUSDT", &Market::Futures).unwrap();
+        assert_eq!(trades[0].id, 412000000);
+        assert_eq!(trades[0].price, dec!(15592.18));
+        assert_eq!(trades[0].time.timestamp_millis(), 1605088843914);
+        assert!(trades[0].is_buyer_maker);
+
+        let trade = csv_to_agg_trade("412000000,15592.18,19.268,905000000,905000000,1605088843914,True,True", "BTCUSDT", &Market::Spot).unwrap();
+        assert_eq!(
+            trade,
+            AggTrade {
+                market: Market::Spot,
+                ..trades[0].clone()
+            }
+        );
+        assert!(csv_to_agg_trade("412000000,15592.18", "BTCUSDT", &Market::Spot).is_err());
+    }
+}
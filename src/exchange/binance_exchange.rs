@@ -0,0 +1,422 @@
+use super::{
+    agg_trades::{agg_trades_window, parse_agg_trades, AGG_TRADES_LIMIT},
+    exchange_info::parse_exchange_info,
+    futures_data::{check_open_interest_interval, parse_funding_rates, parse_open_interests, FUNDING_RATES_LIMIT, OPEN_INTERESTS_LIMIT},
+    scheduler::{Failure, RequestError, Scheduler},
+    Exchange, KLINES_LIMIT,
+};
+use crate::{
+    candles_utils::{datetime_to_timestamp, kline_to_candle},
+    config::symbol_minutes::SymbolMinutes,
+    model::{
+        agg_trade::AggTrade, candle::Candle, funding_rate::FundingRate, interval::Interval, market::Market, open_interest::OpenInterest,
+        symbol_info::SymbolInfo,
+    },
+};
+use anyhow::{bail, Result};
+use binance::{
+    account::Account,
+    api::Binance,
+    errors::{Error as BinanceError, ErrorKind},
+    futures::market::FuturesMarket,
+    market::Market as SpotMarket,
+    model::Balance,
+};
+use chrono::{DateTime, Duration, Utc};
+use ifmt::iformat;
+use log::{debug, error, info, warn};
+use std::{env, time::Duration as StdDuration};
+
+/// Request weight allowed per minute by spot API
+const SPOT_WEIGHT_LIMIT: u32 = 1200;
+/// Request weight allowed per minute by USD-M futures API
+const FUTURES_WEIGHT_LIMIT: u32 = 2400;
+/// Spot klines weight
+const SPOT_KLINES_WEIGHT: u32 = 2;
+/// Futures klines weight for 500..=1000 limit
+const FUTURES_KLINES_WEIGHT: u32 = 5;
+/// Spot exchange info weight
+const SPOT_EXCHANGE_INFO_WEIGHT: u32 = 20;
+/// Futures exchange info weight
+const FUTURES_EXCHANGE_INFO_WEIGHT: u32 = 1;
+/// Futures funding rate and open interest statistics weight
+const FUTURES_DATA_WEIGHT: u32 = 1;
+/// Spot aggregate trades weight
+const SPOT_AGG_TRADES_WEIGHT: u32 = 2;
+/// Futures aggregate trades weight
+const FUTURES_AGG_TRADES_WEIGHT: u32 = 20;
+/// Open interest statistics are kept by exchange only for the last 30 days
+const OPEN_INTEREST_DAYS: i64 = 30;
+/// Spot account information weight
+const SPOT_ACCOUNT_WEIGHT: u32 = 10;
+/// Retries of rate limited and transient failures
+const MAX_RETRIES: u32 = 6;
+/// Binance "Too many requests" error code
+const TOO_MANY_REQUESTS: i16 = -1003;
+
+const SPOT_API_URL: &str = "https://api.binance.com";
+const FUTURES_API_URL: &str = "https://fapi.binance.com";
+
+/// Binance exchange, market data is public so API keys are only required by private endpoints (account, orders)
+pub struct BinanceExchange {
+    api_key: Option<String>,
+    secret_key: Option<String>,
+    spot_scheduler: Scheduler,
+    futures_scheduler: Scheduler,
+}
+
+impl BinanceExchange {
+    /// Reads optional API keys from `API_KEY` and `SECRET_KEY` environment variables
+    pub fn new() -> BinanceExchange {
+        Self::with_credentials(env::var("API_KEY").ok(), env::var("SECRET_KEY").ok())
+    }
+
+    pub fn with_credentials(api_key: Option<String>, secret_key: Option<String>) -> BinanceExchange {
+        // Spot and futures have their own limits, keep a margin for other clients sharing the IP
+        BinanceExchange {
+            api_key,
+            secret_key,
+            spot_scheduler: Scheduler::new(SPOT_WEIGHT_LIMIT * 8 / 10, StdDuration::from_secs(60)).with_retries(
+                MAX_RETRIES,
+                StdDuration::from_secs(1),
+                StdDuration::from_secs(120),
+            ),
+            futures_scheduler: Scheduler::new(FUTURES_WEIGHT_LIMIT * 8 / 10, StdDuration::from_secs(60)).with_retries(
+                MAX_RETRIES,
+                StdDuration::from_secs(1),
+                StdDuration::from_secs(120),
+            ),
+        }
+    }
+
+    pub fn futures_market(&self) -> FuturesMarket {
+        Binance::new(self.api_key.clone(), self.secret_key.clone())
+    }
+
+    pub fn spot_market(&self) -> SpotMarket {
+        Binance::new(self.api_key.clone(), self.secret_key.clone())
+    }
+
+    /// Spot account, a private endpoint
+    pub fn spot_account(&self) -> Result<Account> {
+        let (api_key, secret_key) = self.credentials("spot account")?;
+        Ok(Binance::new(Some(api_key), Some(secret_key)))
+    }
+
+    /// Spot balances not zero
+    pub fn balances(&self) -> Result<Vec<Balance>> {
+        let account = self.spot_account()?;
+        let information = self.spot_scheduler.run(SPOT_ACCOUNT_WEIGHT, || account.get_account().map_err(request_error))?;
+        Ok(information
+            .balances
+            .into_iter()
+            .filter(|b| b.free.parse::<f64>().unwrap_or_default() != 0.0 || b.locked.parse::<f64>().unwrap_or_default() != 0.0)
+            .collect())
+    }
+
+    /// Walks a futures endpoint answering items from `startTime`, at most `limit` per page, until end time
+    fn futures_pages<T>(
+        &self, path: &str, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>, limit: u16, parse: impl Fn(&str) -> Result<Vec<T>>,
+        time: impl Fn(&T) -> DateTime<Utc>,
+    ) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut start_time = *start_time;
+        let end_timestamp = datetime_to_timestamp(end_time);
+        loop {
+            let start_timestamp = datetime_to_timestamp(&start_time);
+            let url = iformat!("{FUTURES_API_URL}{path}&startTime={start_timestamp}&endTime={end_timestamp}&limit={limit}");
+            let content = self.futures_scheduler.run(FUTURES_DATA_WEIGHT, || http_get(&url))?;
+            let page = parse(&content)?;
+            let count = page.len();
+            let last_time = page.last().map(&time);
+            items.extend(page);
+            match last_time {
+                Some(last_time) if count >= limit as usize && last_time < *end_time => start_time = last_time + Duration::milliseconds(1),
+                _ => break,
+            }
+        }
+        Ok(items)
+    }
+
+    fn agg_trades_page(&self, symbol: &str, market: &Market, query: &str) -> Result<Vec<AggTrade>> {
+        let content = match market {
+            Market::Spot => {
+                let url = iformat!("{SPOT_API_URL}/api/v3/aggTrades?symbol={symbol}&{query}&limit={AGG_TRADES_LIMIT}");
+                self.spot_scheduler.run(SPOT_AGG_TRADES_WEIGHT, || http_get(&url))
+            }
+            Market::Futures => {
+                let url = iformat!("{FUTURES_API_URL}/fapi/v1/aggTrades?symbol={symbol}&{query}&limit={AGG_TRADES_LIMIT}");
+                self.futures_scheduler.run(FUTURES_AGG_TRADES_WEIGHT, || http_get(&url))
+            }
+        }?;
+        parse_agg_trades(&content, symbol, market)
+    }
+
+    fn credentials(&self, endpoint: &str) -> Result<(String, String)> {
+        match (&self.api_key, &self.secret_key) {
+            (Some(api_key), Some(secret_key)) => Ok((api_key.clone(), secret_key.clone())),
+            _ => bail!(
+                "Binance {} is a private endpoint, it requires API_KEY and SECRET_KEY environment variables (or .env file)",
+                endpoint
+            ),
+        }
+    }
+}
+
+impl Exchange for BinanceExchange {
+    fn symbols_info(&self, market: &Market) -> anyhow::Result<Vec<SymbolInfo>> {
+        let content = match market {
+            Market::Spot => {
+                let url = iformat!("{SPOT_API_URL}/api/v3/exchangeInfo");
+                self.spot_scheduler.run(SPOT_EXCHANGE_INFO_WEIGHT, || http_get(&url))?
+            }
+            Market::Futures => {
+                let url = iformat!("{FUTURES_API_URL}/fapi/v1/exchangeInfo");
+                self.futures_scheduler.run(FUTURES_EXCHANGE_INFO_WEIGHT, || http_get(&url))?
+            }
+        };
+        parse_exchange_info(&content, market)
+    }
+
+    fn funding_rates(&self, symbol: &str, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> anyhow::Result<Vec<FundingRate>> {
+        let path = iformat!("/fapi/v1/fundingRate?symbol={symbol}");
+        self.futures_pages(&path, start_time, end_time, FUNDING_RATES_LIMIT, parse_funding_rates, |f| f.funding_time)
+    }
+
+    fn open_interests(&self, symbol: &str, interval: &Interval, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> anyhow::Result<Vec<OpenInterest>> {
+        check_open_interest_interval(interval)?;
+        let oldest = Utc::now() - Duration::days(OPEN_INTEREST_DAYS) + Duration::minutes(1);
+        let start_time = if *start_time < oldest {
+            warn!(
+                "{}",
+                iformat!("Open interest is available only for the last {OPEN_INTEREST_DAYS} days, starting at {oldest}")
+            );
+            oldest
+        } else {
+            *start_time
+        };
+        if start_time > *end_time {
+            return Ok(Vec::new());
+        }
+        let path = iformat!("/futures/data/openInterestHist?symbol={symbol}&period={interval}");
+        self.futures_pages(
+            &path,
+            &start_time,
+            end_time,
+            OPEN_INTERESTS_LIMIT,
+            |text| parse_open_interests(text, interval),
+            |o| o.time,
+        )
+    }
+
+    /// First page by time window (exchange allows at most one hour), following pages by id until end time
+    fn agg_trades(&self, symbol: &str, market: &Market, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> anyhow::Result<Vec<AggTrade>> {
+        let mut trades: Vec<AggTrade> = Vec::new();
+        let mut window_start = *start_time;
+        // Windows without trades are skipped until first trade
+        while trades.is_empty() && window_start <= *end_time {
+            let window_end = (window_start + agg_trades_window() - Duration::milliseconds(1)).min(*end_time);
+            let query = iformat!("startTime={window_start.timestamp_millis()}&endTime={window_end.timestamp_millis()}");
+            trades = self.agg_trades_page(symbol, market, &query)?;
+            window_start = window_end + Duration::milliseconds(1);
+        }
+        while let Some(last) = trades.last() {
+            if last.time > *end_time {
+                break;
+            }
+            let query = iformat!("fromId={last.id + 1}");
+            let page = self.agg_trades_page(symbol, market, &query)?;
+            if page.is_empty() {
+                break;
+            }
+            debug!(
+                "{}",
+                iformat!("exchange: {symbol} {trades.len()} aggregate trades until {page.last().unwrap().time}")
+            );
+            trades.extend(page);
+        }
+        trades.retain(|t| t.time <= *end_time);
+        Ok(trades)
+    }
+
+    fn candles_page(
+        &self, symbol_minutes: &SymbolMinutes, start_time: &Option<DateTime<Utc>>, end_time: &Option<DateTime<Utc>>,
+    ) -> anyhow::Result<Vec<Candle>> {
+        let start_time = *start_time;
+        let mut end_time = *end_time;
+
+        let mut candles = Vec::new();
+
+        if let Some(st) = start_time {
+            if let Some(et) = end_time {
+                if st == et {
+                    end_time = Some(et + Duration::seconds(1));
+                }
+            }
+        }
+
+        let start_time = start_time.map(|d| datetime_to_timestamp(&d));
+        let end_time = end_time.map(|d| datetime_to_timestamp(&d));
+
+        let symbol = symbol_minutes.symbol.to_string();
+        let interval = symbol_minutes.interval.to_string();
+        let klines = match symbol_minutes.market {
+            Market::Spot => self.spot_scheduler.run(SPOT_KLINES_WEIGHT, || {
+                self.spot_market()
+                    .get_klines(symbol.as_str(), interval.as_str(), KLINES_LIMIT, start_time, end_time)
+                    .map_err(request_error)
+            }),
+            Market::Futures => self.futures_scheduler.run(FUTURES_KLINES_WEIGHT, || {
+                self.futures_market()
+                    .get_klines(symbol.as_str(), interval.as_str(), KLINES_LIMIT, start_time, end_time)
+                    .map_err(request_error)
+            }),
+        };
+
+        debug!(
+            "{}",
+            iformat!("exchange: used weight spot {self.spot_scheduler.used_weight()} futures {self.futures_scheduler.used_weight()}")
+        );
+
+        match klines {
+            Ok(answer) => {
+                match answer {
+                    binance::model::KlineSummaries::AllKlineSummaries(summaries) => {
+                        for summary in summaries {
+                            let candle = kline_to_candle(
+                                &summary,
+                                &symbol_minutes.symbol,
+                                symbol_minutes.interval.minutes(),
+                                &symbol_minutes.market,
+                                &0u32.into(),
+                            );
+                            info!("{}", iformat!("exchange: {candle}"));
+                            candles.push(candle);
+                        }
+                    }
+                }
+                Ok(candles)
+            }
+            Err(e) => {
+                let error = iformat!("exchange: {e}");
+                error!("*** {}", error);
+                bail!(error)
+            }
+        }
+    }
+}
+
+/// Classifies binance error to decide about retry
+fn failure(e: &BinanceError) -> Failure {
+    match e.kind() {
+        ErrorKind::ReqError(_) | ErrorKind::IoError(_) => Failure::Transient,
+        ErrorKind::BinanceError(content) if content.code == TOO_MANY_REQUESTS => Failure::RateLimited,
+        ErrorKind::Msg(msg) if msg.ends_with(": 429") => Failure::RateLimited,
+        ErrorKind::Msg(msg) if msg.ends_with(": 418") => Failure::Banned,
+        ErrorKind::Msg(msg) if msg.starts_with("Received response: 5") || msg == "Internal Server Error" || msg == "Service Unavailable" => Failure::Transient,
+        _ => Failure::Fatal,
+    }
+}
+
+/// Public endpoint GET, answers response body
+fn http_get(url: &str) -> Result<String, RequestError> {
+    let response = reqwest::blocking::get(url).map_err(|e| RequestError::new(Failure::Transient, &e.to_string()))?;
+    let status = response.status();
+    if status.is_success() {
+        return response.text().map_err(|e| RequestError::new(Failure::Transient, &e.to_string()));
+    }
+    let failure = match status.as_u16() {
+        429 => Failure::RateLimited,
+        418 => Failure::Banned,
+        500..=599 => Failure::Transient,
+        _ => Failure::Fatal,
+    };
+    let error = RequestError::new(failure, &iformat!("{url} received response: {status}"));
+    error!("{}", error);
+    Err(error)
+}
+
+fn request_error(e: BinanceError) -> RequestError {
+    for ec in e.iter() {
+        if let Some(source) = ec.source() {
+            error!("### {}", source);
+        }
+        error!("{}", ec);
+    }
+    RequestError::new(failure(&e), &e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use ifmt::iprintln;
+
+    use super::*;
+    use crate::model::interval::Interval;
+
+    #[test]
+    fn failure_test() {
+        let msg = |m: &str| BinanceError::from_kind(ErrorKind::Msg(m.to_string()));
+        assert_eq!(failure(&msg("Received response: 429")), Failure::RateLimited);
+        assert_eq!(failure(&msg("Received response: 418")), Failure::Banned);
+        assert_eq!(failure(&msg("Received response: 504")), Failure::Transient);
+        assert_eq!(failure(&msg("Service Unavailable")), Failure::Transient);
+        assert_eq!(failure(&msg("Unauthorized")), Failure::Fatal);
+        let io = BinanceError::from(std::io::Error::new(std::io::ErrorKind::TimedOut, "timeout"));
+        assert_eq!(failure(&io), Failure::Transient);
+    }
+
+    #[test]
+    fn private_without_credentials_test() {
+        let exchange = BinanceExchange::with_credentials(None, None);
+        let error = exchange.balances().unwrap_err().to_string();
+        assert!(error.contains("API_KEY"), "{}", error);
+    }
+
+    #[test]
+    #[ignore = "requires network"]
+    fn symbols_info_test() {
+        let exchange = BinanceExchange::with_credentials(None, None);
+        for market in [Market::Spot, Market::Futures].iter() {
+            let symbol_info = exchange.symbol_info("BTCUSDT", market).unwrap();
+            iprintln!("{symbol_info}");
+            assert!(!symbol_info.tick_size.is_zero() && !symbol_info.step_size.is_zero());
+        }
+    }
+
+    #[test]
+    #[ignore = "requires network"]
+    fn futures_data_test() {
+        let exchange = BinanceExchange::with_credentials(None, None);
+        let end = Utc::now();
+        let funding_rates = exchange.funding_rates("BTCUSDT", &(end - Duration::days(2)), &end).unwrap();
+        assert!(funding_rates.len() >= 5);
+        let open_interests = exchange.open_interests("BTCUSDT", &Interval::H1, &(end - Duration::days(1)), &end).unwrap();
+        assert!(open_interests.len() >= 23);
+        iprintln!("{funding_rates.last().unwrap()} {open_interests.last().unwrap()}");
+    }
+
+    #[test]
+    #[ignore = "requires network"]
+    fn agg_trades_test() {
+        let exchange = BinanceExchange::with_credentials(None, None);
+        let end = Utc::now() - Duration::minutes(1);
+        let trades = exchange.agg_trades("BTCUSDT", &Market::Futures, &(end - Duration::minutes(2)), &end).unwrap();
+        assert!(trades.windows(2).all(|w| w[1].id == w[0].id + 1));
+        assert!(trades.iter().all(|t| t.symbol == "BTCUSDT" && t.time <= end));
+        iprintln!("{trades.len()} {trades.last().unwrap()}");
+    }
+
+    #[test]
+    #[ignore = "requires network"]
+    fn candles_test() {
+        dotenv::dotenv().ok();
+        let exchange = BinanceExchange::new();
+        let start = Utc::now() - Duration::minutes(15);
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Futures);
+        let candles = exchange.candles(&symbol_minutes, &Some(start), &None).unwrap();
+        for candle in candles {
+            iprintln!("{candle}");
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::model::{exchange_name::ExchangeName, market::Market, symbol_info::SymbolInfo};
+use anyhow::anyhow;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// Exchange information, declared here because `binance::model::Filters` fails on filter types it doesn't know
+#[derive(Debug, Deserialize)]
+struct ExchangeInformation {
+    symbols: Vec<Symbol>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Symbol {
+    symbol: String,
+    base_asset: String,
+    quote_asset: String,
+    filters: Vec<Filter>,
+}
+
+/// Any filter, only the fields used by `SymbolInfo`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Filter {
+    filter_type: String,
+    tick_size: Option<String>,
+    step_size: Option<String>,
+    min_qty: Option<String>,
+    min_notional: Option<String>,
+    /// Futures MIN_NOTIONAL value
+    notional: Option<String>,
+}
+
+/// Parses exchange info response (spot `/api/v3/exchangeInfo` or futures `/fapi/v1/exchangeInfo`)
+pub fn parse_exchange_info(text: &str, market: &Market) -> anyhow::Result<Vec<SymbolInfo>> {
+    let information: ExchangeInformation = serde_json::from_str(text)?;
+    let updated_at = Utc::now();
+    information
+        .symbols
+        .into_iter()
+        .map(|symbol| {
+            let mut symbol_info = SymbolInfo {
+                exchange: ExchangeName::Binance,
+                symbol: symbol.symbol,
+                market: *market,
+                base_asset: symbol.base_asset,
+                quote_asset: symbol.quote_asset,
+                tick_size: Decimal::ZERO,
+                step_size: Decimal::ZERO,
+                min_qty: Decimal::ZERO,
+                min_notional: Decimal::ZERO,
+                updated_at,
+            };
+            for filter in symbol.filters {
+                match filter.filter_type.as_str() {
+                    "PRICE_FILTER" => symbol_info.tick_size = decimal(&filter.tick_size)?,
+                    "LOT_SIZE" => {
+                        symbol_info.step_size = decimal(&filter.step_size)?;
+                        symbol_info.min_qty = decimal(&filter.min_qty)?;
+                    }
+                    // Spot replaced MIN_NOTIONAL by NOTIONAL, futures name the value just "notional"
+                    "MIN_NOTIONAL" | "NOTIONAL" => symbol_info.min_notional = decimal(&filter.min_notional.or(filter.notional))?,
+                    _ => {}
+                }
+            }
+            Ok(symbol_info)
+        })
+        .collect()
+}
+
+fn decimal(value: &Option<String>) -> anyhow::Result<Decimal> {
+    match value {
+        Some(value) => Decimal::from_str(value).map_err(|e| anyhow!("Invalid filter value {}: {}", value, e)),
+        None => Ok(Decimal::ZERO),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use std::{fs, path::Path};
+
+    #[test]
+    fn parse_exchange_info_test() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/klines");
+
+        let spot = fs::read_to_string(fixtures.join("spot/exchangeInfo.json")).unwrap();
+        let spot = parse_exchange_info(&spot, &Market::Spot).unwrap();
+        let btc = spot.iter().find(|s| s.symbol == "BTCUSDT").unwrap();
+        assert_eq!(btc.market, Market::Spot);
+        assert_eq!(btc.base_asset, "BTC");
+        assert_eq!(btc.quote_asset, "USDT");
+        assert_eq!(btc.tick_size, dec!(0.01));
+        assert_eq!(btc.step_size, dec!(0.00001));
+        assert_eq!(btc.min_qty, dec!(0.00001));
+        assert_eq!(btc.min_notional, dec!(5));
+
+        let futures = fs::read_to_string(fixtures.join("futures/exchangeInfo.json")).unwrap();
+        let futures = parse_exchange_info(&futures, &Market::Futures).unwrap();
+        let btc = futures.iter().find(|s| s.symbol == "BTCUSDT").unwrap();
+        assert_eq!(btc.tick_size, dec!(0.10));
+        assert_eq!(btc.step_size, dec!(0.001));
+        assert_eq!(btc.min_notional, dec!(100));
+    }
+}
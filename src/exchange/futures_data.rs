@@ -0,0 +1,125 @@
+use crate::{
+    candles_utils::timestamp_to_datetime,
+    model::{exchange_name::ExchangeName, funding_rate::FundingRate, interval::Interval, open_interest::OpenInterest},
+};
+use anyhow::bail;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// Max funding rates returned by one exchange call
+pub const FUNDING_RATES_LIMIT: u16 = 1000;
+/// Max open interest snapshots returned by one exchange call
+pub const OPEN_INTERESTS_LIMIT: u16 = 500;
+
+/// Open interest statistics periods answered by exchange
+pub const OPEN_INTEREST_INTERVALS: [Interval; 9] = [
+    Interval::M5,
+    Interval::M15,
+    Interval::M30,
+    Interval::H1,
+    Interval::H2,
+    Interval::H4,
+    Interval::H6,
+    Interval::H12,
+    Interval::D1,
+];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FundingRateItem {
+    symbol: String,
+    funding_rate: String,
+    funding_time: i64,
+    /// Empty on old fundings
+    #[serde(default)]
+    mark_price: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenInterestItem {
+    symbol: String,
+    sum_open_interest: String,
+    sum_open_interest_value: String,
+    timestamp: i64,
+}
+
+/// Parses funding rate history response (`/fapi/v1/fundingRate`)
+pub fn parse_funding_rates(text: &str) -> anyhow::Result<Vec<FundingRate>> {
+    let items: Vec<FundingRateItem> = serde_json::from_str(text)?;
+    items
+        .into_iter()
+        .map(|item| {
+            Ok(FundingRate {
+                exchange: ExchangeName::Binance,
+                symbol: item.symbol,
+                funding_time: timestamp_to_datetime(&(item.funding_time as u64)),
+                funding_rate: Decimal::from_str(&item.funding_rate)?,
+                mark_price: if item.mark_price.is_empty() {
+                    None
+                } else {
+                    Some(Decimal::from_str(&item.mark_price)?)
+                },
+            })
+        })
+        .collect()
+}
+
+/// Parses open interest statistics response (`/futures/data/openInterestHist`)
+pub fn parse_open_interests(text: &str, interval: &Interval) -> anyhow::Result<Vec<OpenInterest>> {
+    let items: Vec<OpenInterestItem> = serde_json::from_str(text)?;
+    items
+        .into_iter()
+        .map(|item| {
+            Ok(OpenInterest {
+                exchange: ExchangeName::Binance,
+                symbol: item.symbol,
+                interval: *interval,
+                time: timestamp_to_datetime(&(item.timestamp as u64)),
+                sum_open_interest: Decimal::from_str(&item.sum_open_interest)?,
+                sum_open_interest_value: Decimal::from_str(&item.sum_open_interest_value)?,
+            })
+        })
+        .collect()
+}
+
+pub fn check_open_interest_interval(interval: &Interval) -> anyhow::Result<()> {
+    if !OPEN_INTEREST_INTERVALS.contains(interval) {
+        bail!("Open interest is not available for interval {} (5m, 15m, 30m, 1h, 2h, 4h, 6h, 12h, 1d)", interval);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::candles_utils::str_to_datetime;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn parse_funding_rates_test() {
+        let text = r#"[
+            {"symbol":"BTCUSDT","fundingTime":1604966400000,"fundingRate":"0.00010000","markPrice":""},
+            {"symbol":"BTCUSDT","fundingTime":1604995200000,"fundingRate":"-0.00025130","markPrice":"15330.12000000"}
+        ]"#;
+        let funding_rates = parse_funding_rates(text).unwrap();
+        assert_eq!(funding_rates.len(), 2);
+        assert_eq!(funding_rates[0].funding_time, str_to_datetime("2020-11-10 00:00:00"));
+        assert_eq!(funding_rates[0].mark_price, None);
+        assert_eq!(funding_rates[1].funding_rate, dec!(-0.0002513));
+        assert_eq!(funding_rates[1].mark_price, Some(dec!(15330.12)));
+    }
+
+    #[test]
+    fn parse_open_interests_test() {
+        let text = r#"[{"symbol":"BTCUSDT","sumOpenInterest":"20403.637","sumOpenInterestValue":"312614567.0781","timestamp":1605088800000}]"#;
+        let open_interests = parse_open_interests(text, &Interval::M15).unwrap();
+        assert_eq!(open_interests[0].time, str_to_datetime("2020-11-11 10:00:00"));
+        assert_eq!(open_interests[0].interval, Interval::M15);
+        assert_eq!(open_interests[0].sum_open_interest, dec!(20403.637));
+
+        assert!(check_open_interest_interval(&Interval::H1).is_ok());
+        assert!(check_open_interest_interval(&Interval::W1).is_err());
+    }
+}
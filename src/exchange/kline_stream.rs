@@ -0,0 +1,208 @@
+use crate::{
+    candles_utils::kline_to_candle,
+    config::symbol_minutes::SymbolMinutes,
+    model::{candle::Candle, market::Market},
+};
+use anyhow::bail;
+use binance::model::KlineSummary;
+use ifmt::iformat;
+use log::debug;
+use serde::Deserialize;
+use std::{net::TcpStream, time::Duration};
+use tungstenite::{client::AutoStream, stream::Stream, Message, WebSocket};
+
+pub const SPOT_STREAM_URL: &str = "wss://stream.binance.com:9443/ws";
+pub const FUTURES_STREAM_URL: &str = "wss://fstream.binance.com/ws";
+
+/// Exchange pings every 3 minutes, a longer silence means a dead connection
+const READ_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Kline update received from stream
+#[derive(Debug, Clone)]
+pub enum KlineUpdate {
+    /// Final candle, it won't change anymore
+    Closed(Candle),
+    /// Candle still open, updated on each trade
+    InProgress(Candle),
+}
+
+/// Kline stream event, declared here because `binance::model::Kline` trade ids are i32 and futures ids overflow them
+#[derive(Debug, Deserialize)]
+struct KlineEvent {
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "k")]
+    kline: Kline,
+}
+
+#[derive(Debug, Deserialize)]
+struct Kline {
+    #[serde(rename = "t")]
+    start_time: i64,
+    #[serde(rename = "T")]
+    end_time: i64,
+    #[serde(rename = "i")]
+    interval: String,
+    #[serde(rename = "o")]
+    open: String,
+    #[serde(rename = "c")]
+    close: String,
+    #[serde(rename = "h")]
+    high: String,
+    #[serde(rename = "l")]
+    low: String,
+    #[serde(rename = "v")]
+    volume: String,
+    #[serde(rename = "x")]
+    is_final_bar: bool,
+}
+
+/// Stream url for symbol minutes klines, `base_url` defaults to Binance url of market
+pub fn kline_stream_url(base_url: &Option<String>, symbol_minutes: &SymbolMinutes) -> String {
+    let base_url = base_url.clone().unwrap_or_else(|| {
+        match symbol_minutes.market {
+            Market::Spot => SPOT_STREAM_URL,
+            Market::Futures => FUTURES_STREAM_URL,
+        }
+        .to_string()
+    });
+    iformat!("{base_url.trim_end_matches('/')}/{symbol_minutes.symbol.to_lowercase()}@kline_{symbol_minutes.interval}")
+}
+
+/// Parses a kline event message
+pub fn parse_kline_update(text: &str, symbol_minutes: &SymbolMinutes) -> anyhow::Result<KlineUpdate> {
+    let event: KlineEvent = serde_json::from_str(text)?;
+    if event.symbol != symbol_minutes.symbol || event.kline.interval != symbol_minutes.interval.as_str() {
+        bail!("Kline event {} {} not expected for {:?}", event.symbol, event.kline.interval, symbol_minutes);
+    }
+
+    let kline = event.kline;
+    let summary = KlineSummary {
+        open_time: kline.start_time,
+        open: kline.open.parse()?,
+        high: kline.high.parse()?,
+        low: kline.low.parse()?,
+        close: kline.close.parse()?,
+        volume: kline.volume.parse()?,
+        close_time: kline.end_time,
+        quote_asset_volume: 0.0,
+        number_of_trades: 0,
+        taker_buy_base_asset_volume: 0.0,
+        taker_buy_quote_asset_volume: 0.0,
+    };
+    let candle = kline_to_candle(
+        &summary,
+        &symbol_minutes.symbol,
+        symbol_minutes.interval.minutes(),
+        &symbol_minutes.market,
+        &0u32.into(),
+    );
+    Ok(if kline.is_final_bar {
+        KlineUpdate::Closed(candle)
+    } else {
+        KlineUpdate::InProgress(candle)
+    })
+}
+
+/// Websocket connection to a kline stream
+pub struct KlineStream {
+    symbol_minutes: SymbolMinutes,
+    socket: WebSocket<AutoStream>,
+}
+
+impl KlineStream {
+    pub fn connect(url: &str, symbol_minutes: &SymbolMinutes) -> anyhow::Result<Self> {
+        let (socket, _) = tungstenite::connect(url)?;
+        let tcp: &TcpStream = match socket.get_ref() {
+            Stream::Plain(tcp) => tcp,
+            Stream::Tls(tls) => tls.get_ref(),
+        };
+        tcp.set_read_timeout(Some(READ_TIMEOUT))?;
+        Ok(Self {
+            symbol_minutes: symbol_minutes.clone(),
+            socket,
+        })
+    }
+
+    /// Waits next kline update, fails when connection is lost or closed
+    pub fn next_update(&mut self) -> anyhow::Result<KlineUpdate> {
+        loop {
+            match self.socket.read_message()? {
+                Message::Text(text) => return parse_kline_update(&text, &self.symbol_minutes),
+                Message::Close(frame) => bail!("Kline stream closed: {:?}", frame),
+                // Pong is answered by tungstenite on next read/write
+                message => debug!("Kline stream message ignored: {:?}", message),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{candles_utils::str_to_datetime, model::interval::Interval};
+    use rust_decimal::prelude::ToPrimitive;
+    use rust_decimal_macros::dec;
+
+    /// Binance kline event message
+    pub fn kline_event(candle: &Candle, is_final_bar: bool) -> String {
+        format!(
+            r#"{{"e":"kline","E":{},"s":"{}","k":{{"t":{},"T":{},"s":"{}","i":"{}","f":5000000000,"L":5000000100,"o":"{}","c":"{}","h":"{}","l":"{}","v":"{}","n":100,"x":{},"q":"0","V":"0","Q":"0","B":"0"}}}}"#,
+            candle.close_time.timestamp_millis(),
+            candle.symbol,
+            candle.open_time.timestamp_millis(),
+            candle.close_time.timestamp_millis(),
+            candle.symbol,
+            Interval::from_minutes(candle.minutes.to_u32().unwrap()).unwrap(),
+            candle.open,
+            candle.close,
+            candle.high,
+            candle.low,
+            candle.volume,
+            is_final_bar
+        )
+    }
+
+    #[test]
+    fn kline_stream_url_test() {
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Futures);
+        assert_eq!(kline_stream_url(&None, &symbol_minutes), "wss://fstream.binance.com/ws/btcusdt@kline_15m");
+        let url = kline_stream_url(&Some("ws://127.0.0.1:9000/ws/".into()), &symbol_minutes);
+        assert_eq!(url, "ws://127.0.0.1:9000/ws/btcusdt@kline_15m");
+    }
+
+    #[test]
+    fn parse_kline_update_test() {
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Spot);
+        let candle = Candle {
+            id: dec!(0),
+            symbol: "BTCUSDT".into(),
+            minutes: dec!(15),
+            open_time: str_to_datetime("2020-11-11 10:00:00"),
+            close_time: str_to_datetime("2020-11-11 10:14:59"),
+            open: dec!(15300.5),
+            high: dec!(15350),
+            low: dec!(15290.25),
+            close: dec!(15320),
+            volume: dec!(123.456),
+            market: "spot".into(),
+            exchange: "binance".into(),
+        };
+
+        match parse_kline_update(&kline_event(&candle, true), &symbol_minutes).unwrap() {
+            KlineUpdate::Closed(closed) => {
+                assert_eq!(closed.open_time, candle.open_time);
+                assert_eq!(closed.close, candle.close);
+                assert_eq!(closed.market, "spot");
+            }
+            update => panic!("Unexpected {:?}", update),
+        }
+        assert!(matches!(
+            parse_kline_update(&kline_event(&candle, false), &symbol_minutes).unwrap(),
+            KlineUpdate::InProgress(_)
+        ));
+
+        let other = SymbolMinutes::new("ETHUSDT", &Interval::M15, &Market::Spot);
+        assert!(parse_kline_update(&kline_event(&candle, true), &other).is_err());
+    }
+}
@@ -0,0 +1,98 @@
+pub mod agg_trades;
+pub mod binance_exchange;
+pub mod exchange_info;
+pub mod futures_data;
+pub mod kline_stream;
+pub mod replay_exchange;
+pub mod scheduler;
+
+use crate::{
+    config::symbol_minutes::SymbolMinutes,
+    model::{
+        agg_trade::AggTrade, candle::Candle, funding_rate::FundingRate, interval::Interval, market::Market, open_interest::OpenInterest,
+        symbol_info::SymbolInfo,
+    },
+};
+use anyhow::anyhow;
+use chrono::{DateTime, Duration, Utc};
+
+/// Max klines returned by one exchange call
+pub const KLINES_LIMIT: u16 = 1000;
+
+/// Progress of a paginated candles retrieve
+#[derive(Debug, Clone)]
+pub struct PageProgress {
+    pub page: usize,
+    pub pages: usize,
+    pub candles: usize,
+    pub last_open_time: Option<DateTime<Utc>>,
+}
+
+/// Source of candles, implemented by live exchanges and by offline replays
+pub trait Exchange {
+    /// Retrieves a single page, at most `page_limit()` candles from start time (or the last ones until end time)
+    fn candles_page(&self, symbol_minutes: &SymbolMinutes, start_time: &Option<DateTime<Utc>>, end_time: &Option<DateTime<Utc>>)
+        -> anyhow::Result<Vec<Candle>>;
+
+    /// Trading rules of all symbols of market
+    fn symbols_info(&self, market: &Market) -> anyhow::Result<Vec<SymbolInfo>>;
+
+    fn symbol_info(&self, symbol: &str, market: &Market) -> anyhow::Result<SymbolInfo> {
+        self.symbols_info(market)?
+            .into_iter()
+            .find(|s| s.symbol == symbol)
+            .ok_or_else(|| anyhow!("Symbol {} not found on {} exchange info", symbol, market))
+    }
+
+    /// Funding rates of a perpetual futures symbol with funding time between start and end time
+    fn funding_rates(&self, symbol: &str, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> anyhow::Result<Vec<FundingRate>>;
+
+    /// Open interest snapshots of a futures symbol, one each interval, between start and end time
+    fn open_interests(&self, symbol: &str, interval: &Interval, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> anyhow::Result<Vec<OpenInterest>>;
+
+    /// Aggregate trades of symbol with trade time between start and end time, sorted by id
+    fn agg_trades(&self, symbol: &str, market: &Market, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> anyhow::Result<Vec<AggTrade>>;
+
+    fn page_limit(&self) -> u16 {
+        KLINES_LIMIT
+    }
+
+    /// Retrieves all candles between start and end time (default now), sorted and without duplicates
+    fn candles(&self, symbol_minutes: &SymbolMinutes, start_time: &Option<DateTime<Utc>>, end_time: &Option<DateTime<Utc>>) -> anyhow::Result<Vec<Candle>> {
+        self.candles_with_progress(symbol_minutes, start_time, end_time, &mut |_| {})
+    }
+
+    /// Same as `candles`, calling `progress` after each page retrieved
+    fn candles_with_progress(
+        &self, symbol_minutes: &SymbolMinutes, start_time: &Option<DateTime<Utc>>, end_time: &Option<DateTime<Utc>>, progress: &mut dyn FnMut(&PageProgress),
+    ) -> anyhow::Result<Vec<Candle>> {
+        // Without start there is no window to split, exchange returns last candles
+        let start_time = match start_time {
+            Some(start_time) => *start_time,
+            None => return self.candles_page(symbol_minutes, &None, end_time),
+        };
+        let end_time = end_time.unwrap_or_else(Utc::now);
+
+        let page_duration = symbol_minutes.interval.duration() * self.page_limit() as i32;
+        let pages = (((end_time - start_time).num_seconds() / page_duration.num_seconds()) + 1).max(1) as usize;
+
+        let mut candles = Vec::new();
+        for page in 0..pages {
+            let page_start = start_time + page_duration * page as i32;
+            let page_end = (page_start + page_duration - Duration::seconds(1)).min(end_time);
+            let mut page_candles = self.candles_page(symbol_minutes, &Some(page_start), &Some(page_end))?;
+            candles.append(&mut page_candles);
+            progress(&PageProgress {
+                page: page + 1,
+                pages,
+                candles: candles.len(),
+                last_open_time: candles.last().map(|c| c.open_time),
+            });
+        }
+
+        candles.retain(|c| c.open_time >= start_time && c.open_time <= end_time);
+        candles.sort();
+        candles.dedup_by(|a, b| a.open_time == b.open_time);
+        Ok(candles)
+    }
+}
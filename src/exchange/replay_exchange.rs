@@ -0,0 +1,290 @@
+use super::{
+    agg_trades::csv_to_agg_trade,
+    exchange_info::parse_exchange_info,
+    futures_data::{check_open_interest_interval, parse_funding_rates, parse_open_interests},
+    Exchange, KLINES_LIMIT,
+};
+use crate::{
+    candles_utils::{csv_to_kline, kline_to_candle},
+    config::symbol_minutes::SymbolMinutes,
+    model::{
+        agg_trade::AggTrade, candle::Candle, funding_rate::FundingRate, interval::Interval, market::Market, open_interest::OpenInterest,
+        symbol_info::SymbolInfo,
+    },
+};
+use anyhow::{anyhow, bail, Context};
+use chrono::{DateTime, Duration, Utc};
+use ifmt::iformat;
+use log::debug;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+/// Exchange that serves klines from local files, for offline runs and tests.
+///
+/// Files are Binance kline csv dumps named `{market}/{symbol}-{interval}*.csv` (e.g. `futures/BTCUSDT-15m-2020-11.csv`),
+/// all files matching a symbol/interval are merged.
+/// Symbols info come from `{market}/exchangeInfo.json`, a Binance exchange info response.
+/// Funding rates and open interest come from `futures/{symbol}-fundingRate.json` and `futures/{symbol}-openInterestHist-{interval}.json`,
+/// Binance responses of whole history.
+/// Aggregate trades come from `{market}/{symbol}-aggTrades*.csv`, Binance aggregate trades csv dumps.
+pub struct ReplayExchange {
+    dir: PathBuf,
+    page_limit: u16,
+    klines: RwLock<HashMap<SymbolMinutes, Vec<Candle>>>,
+}
+
+impl ReplayExchange {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            page_limit: KLINES_LIMIT,
+            klines: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Max candles per page, to exercise pagination with small files
+    #[cfg(test)]
+    pub fn with_page_limit(mut self, page_limit: u16) -> Self {
+        self.page_limit = page_limit;
+        self
+    }
+
+    /// Sorted csv files of market named `{prefix}.csv` or `{prefix}-*.csv`
+    fn csv_paths(&self, market: &Market, prefix: &str) -> anyhow::Result<Vec<PathBuf>> {
+        let dir = self.dir.join(market.as_str());
+        let mut paths = fs::read_dir(&dir)
+            .with_context(|| format!("Replay directory {:?} not readable", dir))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                (name == iformat!("{prefix}.csv") || name.starts_with(&iformat!("{prefix}-"))) && name.ends_with(".csv")
+            })
+            .collect::<Vec<_>>();
+        if paths.is_empty() {
+            bail!("Replay: no file {}*.csv in {:?}", prefix, dir);
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Calls `parse` on each data line of csv files, skipping header and empty lines
+    fn parse_csv<T>(&self, paths: &[PathBuf], parse: impl Fn(&str) -> anyhow::Result<T>) -> anyhow::Result<Vec<T>> {
+        let mut items = Vec::new();
+        for path in paths.iter() {
+            debug!("Replay: loading {:?}...", path);
+            let content = fs::read_to_string(path)?;
+            for (i, line) in content.lines().enumerate() {
+                if line.is_empty() || !line.starts_with(|c: char| c.is_ascii_digit()) {
+                    continue;
+                }
+                items.push(parse(line).map_err(|e| anyhow!("{:?} line {}: {}", path, i + 1, e))?);
+            }
+        }
+        Ok(items)
+    }
+
+    fn load(&self, symbol_minutes: &SymbolMinutes) -> anyhow::Result<Vec<Candle>> {
+        let paths = self.csv_paths(&symbol_minutes.market, &iformat!("{symbol_minutes.symbol}-{symbol_minutes.interval}"))?;
+        let mut candles = self.parse_csv(&paths, |line| {
+            let kline = csv_to_kline(line)?;
+            Ok(kline_to_candle(
+                &kline,
+                &symbol_minutes.symbol,
+                symbol_minutes.interval.minutes(),
+                &symbol_minutes.market,
+                &0u32.into(),
+            ))
+        })?;
+        candles.sort();
+        candles.dedup_by(|a, b| a.open_time == b.open_time);
+        Ok(candles)
+    }
+
+    fn read_futures(&self, name: &str) -> anyhow::Result<String> {
+        let path = self.dir.join(Market::Futures.as_str()).join(name);
+        fs::read_to_string(&path).with_context(|| format!("Replay: {:?} not readable", path))
+    }
+}
+
+impl Exchange for ReplayExchange {
+    fn symbols_info(&self, market: &Market) -> anyhow::Result<Vec<SymbolInfo>> {
+        let path = self.dir.join(market.as_str()).join("exchangeInfo.json");
+        let content = fs::read_to_string(&path).with_context(|| format!("Replay: exchange info {:?} not readable", path))?;
+        parse_exchange_info(&content, market)
+    }
+
+    fn funding_rates(&self, symbol: &str, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> anyhow::Result<Vec<FundingRate>> {
+        let mut funding_rates = parse_funding_rates(&self.read_futures(&iformat!("{symbol}-fundingRate.json"))?)?;
+        funding_rates.retain(|f| f.funding_time >= *start_time && f.funding_time <= *end_time);
+        Ok(funding_rates)
+    }
+
+    fn open_interests(&self, symbol: &str, interval: &Interval, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> anyhow::Result<Vec<OpenInterest>> {
+        check_open_interest_interval(interval)?;
+        let mut open_interests = parse_open_interests(&self.read_futures(&iformat!("{symbol}-openInterestHist-{interval}.json"))?, interval)?;
+        open_interests.retain(|o| o.time >= *start_time && o.time <= *end_time);
+        Ok(open_interests)
+    }
+
+    fn agg_trades(&self, symbol: &str, market: &Market, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> anyhow::Result<Vec<AggTrade>> {
+        let paths = self.csv_paths(market, &iformat!("{symbol}-aggTrades"))?;
+        let mut trades = self.parse_csv(&paths, |line| csv_to_agg_trade(line, symbol, market))?;
+        trades.retain(|t| t.time >= *start_time && t.time <= *end_time);
+        trades.sort_by_key(|t| t.id);
+        trades.dedup_by_key(|t| t.id);
+        Ok(trades)
+    }
+
+    fn page_limit(&self) -> u16 {
+        self.page_limit
+    }
+
+    fn candles_page(
+        &self, symbol_minutes: &SymbolMinutes, start_time: &Option<DateTime<Utc>>, end_time: &Option<DateTime<Utc>>,
+    ) -> anyhow::Result<Vec<Candle>> {
+        if !self.klines.read().unwrap().contains_key(symbol_minutes) {
+            let candles = self.load(symbol_minutes)?;
+            self.klines.write().unwrap().insert(symbol_minutes.clone(), candles);
+        }
+        let klines = self.klines.read().unwrap();
+        let candles = klines.get(symbol_minutes).unwrap();
+
+        // Same semantics as Binance klines endpoint: from start time forward, or last ones until end time
+        let mut end_time = *end_time;
+        if let (Some(st), Some(et)) = (start_time, end_time) {
+            if *st == et {
+                end_time = Some(et + Duration::seconds(1));
+            }
+        }
+        let in_range = candles
+            .iter()
+            .filter(|c| start_time.map_or(true, |st| c.open_time >= st) && end_time.map_or(true, |et| c.open_time <= et))
+            .collect::<Vec<_>>();
+
+        let limit = self.page_limit as usize;
+        let page = if start_time.is_some() || in_range.len() <= limit {
+            in_range.into_iter().take(limit).cloned().collect()
+        } else {
+            in_range[in_range.len() - limit..].iter().map(|c| (*c).clone()).collect()
+        };
+        Ok(page)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        candles_utils::str_to_datetime,
+        model::{interval::Interval, market::Market},
+    };
+
+    pub fn replay_exchange() -> ReplayExchange {
+        ReplayExchange::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/klines"))
+    }
+
+    #[test]
+    fn candles_test() {
+        let exchange = replay_exchange();
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Futures);
+        let start = str_to_datetime("2020-11-11 10:00:00");
+        let end = str_to_datetime("2020-11-11 11:00:00");
+        let candles = exchange.candles(&symbol_minutes, &Some(start), &Some(end)).unwrap();
+
+        assert_eq!(candles.len(), 5);
+        assert_eq!(candles.first().unwrap().open_time, start);
+        assert_eq!(candles.last().unwrap().open_time, end);
+        assert_eq!(candles.first().unwrap().close_time, str_to_datetime("2020-11-11 10:14:59"));
+    }
+
+    #[test]
+    fn candles_pagination_test() {
+        let exchange = replay_exchange().with_page_limit(50);
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Futures);
+        let start = str_to_datetime("2020-11-10 00:00:00");
+        let end = str_to_datetime("2020-11-12 23:45:00");
+
+        let mut progresses = Vec::new();
+        let candles = exchange
+            .candles_with_progress(&symbol_minutes, &Some(start), &Some(end), &mut |p| progresses.push(p.clone()))
+            .unwrap();
+
+        assert_eq!(candles.len(), 288);
+        assert_eq!(candles.first().unwrap().open_time, start);
+        assert_eq!(candles.last().unwrap().open_time, end);
+        assert!(candles.windows(2).all(|w| w[1].open_time - w[0].open_time == chrono::Duration::minutes(15)));
+
+        assert_eq!(progresses.len(), 6);
+        assert!(progresses.iter().all(|p| p.pages == 6));
+        assert_eq!(progresses.last().unwrap().candles, 288);
+        assert_eq!(progresses.last().unwrap().last_open_time, Some(end));
+    }
+
+    #[test]
+    fn candles_unknown_symbol_test() {
+        let exchange = replay_exchange();
+        let symbol_minutes = SymbolMinutes::new("XXXUSDT", &Interval::M15, &Market::Futures);
+        assert!(exchange.candles(&symbol_minutes, &None, &None).is_err());
+    }
+
+    #[test]
+    fn candles_market_test() {
+        let exchange = replay_exchange();
+        let start = Some(str_to_datetime("2020-11-11 10:00:00"));
+        let futures = exchange
+            .candles(&SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Futures), &start, &None)
+            .unwrap();
+        let spot = exchange
+            .candles(&SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Spot), &start, &None)
+            .unwrap();
+
+        assert_eq!(futures.first().unwrap().open_time, spot.first().unwrap().open_time);
+        assert_ne!(futures.first().unwrap().close, spot.first().unwrap().close);
+        assert_eq!(spot.first().unwrap().market, "spot");
+    }
+
+    #[test]
+    fn symbol_info_test() {
+        let exchange = replay_exchange();
+        let symbol_info = exchange.symbol_info("BTCUSDT", &Market::Futures).unwrap();
+        assert_eq!(symbol_info.market, Market::Futures);
+        assert_eq!(symbol_info.quote_asset, "USDT");
+        assert!(exchange.symbol_info("XXXUSDT", &Market::Futures).is_err());
+    }
+
+    #[test]
+    fn futures_data_test() {
+        let exchange = replay_exchange();
+        let start = str_to_datetime("2020-11-11 00:00:00");
+        let end = str_to_datetime("2020-11-11 16:00:00");
+
+        let funding_rates = exchange.funding_rates("BTCUSDT", &start, &end).unwrap();
+        assert_eq!(
+            funding_rates.iter().map(|f| f.funding_time).collect::<Vec<_>>(),
+            vec![start, str_to_datetime("2020-11-11 08:00:00"), end]
+        );
+
+        let open_interests = exchange.open_interests("BTCUSDT", &Interval::M15, &start, &end).unwrap();
+        assert_eq!(open_interests.len(), 65);
+        assert!(exchange.open_interests("BTCUSDT", &Interval::H1, &start, &end).is_err());
+    }
+
+    #[test]
+    fn agg_trades_test() {
+        let exchange = replay_exchange();
+        let start = str_to_datetime("2020-11-11 10:15:00");
+        let end = str_to_datetime("2020-11-11 10:29:59");
+        let trades = exchange
+            .agg_trades("BTCUSDT", &Market::Futures, &start, &(end + Duration::milliseconds(999)))
+            .unwrap();
+
+        assert_eq!(trades.len(), 30);
+        assert!(trades.iter().all(|t| t.time >= start && t.time < end + Duration::seconds(1)));
+        assert!(trades.windows(2).all(|w| w[1].id == w[0].id + 1));
+        assert!(exchange.agg_trades("BTCUSDT", &Market::Spot, &start, &end).is_err());
+    }
+}
@@ -0,0 +1,249 @@
+use anyhow::bail;
+use ifmt::iformat;
+use log::{debug, warn};
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// How a failed request must be handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// HTTP 429, limit hit: wait and retry
+    RateLimited,
+    /// HTTP 418, IP banned: retrying would extend the ban
+    Banned,
+    /// Network errors and 5xx: retry with backoff
+    Transient,
+    /// Any other error (bad symbol, bad parameters...)
+    Fatal,
+}
+
+/// Error of a request, tagged with how to handle it
+#[derive(Debug)]
+pub struct RequestError {
+    pub failure: Failure,
+    pub message: String,
+}
+
+impl RequestError {
+    pub fn new(failure: Failure, message: &str) -> Self {
+        Self {
+            failure,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.failure, self.message)
+    }
+}
+
+impl std::error::Error for RequestError {}
+
+/// Schedules requests under an exchange weight limit per time window.
+///
+/// Used weight is tracked locally in a sliding window, a request waits until its weight fits.
+/// Rate limited and transient errors are retried with exponential backoff and jitter.
+pub struct Scheduler {
+    limit: u32,
+    window: Duration,
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    used: Mutex<VecDeque<(Instant, u32)>>,
+}
+
+impl Scheduler {
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            max_retries: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(60),
+            used: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn with_retries(mut self, max_retries: u32, base_delay: Duration, max_delay: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Weight used in current window
+    pub fn used_weight(&self) -> u32 {
+        let mut used = self.used.lock().unwrap();
+        Self::expire(&mut used, self.window);
+        used.iter().map(|u| u.1).sum()
+    }
+
+    /// Runs `request` with `weight`, waiting for the window and retrying recoverable failures
+    pub fn run<T, F>(&self, weight: u32, mut request: F) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Result<T, RequestError>,
+    {
+        let mut attempt = 0;
+        loop {
+            self.acquire(weight);
+            let error = match request() {
+                Ok(result) => return Ok(result),
+                Err(error) => error,
+            };
+
+            match error.failure {
+                Failure::Banned | Failure::Fatal => bail!(error),
+                Failure::RateLimited => self.saturate(),
+                Failure::Transient => {}
+            }
+
+            if attempt >= self.max_retries {
+                bail!("{} (gave up after {} retries)", error, attempt);
+            }
+            let delay = self.backoff(attempt);
+            warn!("{}", iformat!("Request failed, retry {attempt + 1}/{self.max_retries} in {delay:?}: {error}"));
+            thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+
+    /// Waits until weight fits in the window, then registers it
+    fn acquire(&self, weight: u32) {
+        loop {
+            let wait = {
+                let mut used = self.used.lock().unwrap();
+                Self::expire(&mut used, self.window);
+                let total: u32 = used.iter().map(|u| u.1).sum();
+                if total + weight <= self.limit || used.is_empty() {
+                    used.push_back((Instant::now(), weight));
+                    return;
+                }
+                // Oldest request leaving the window frees some weight
+                self.window.checked_sub(used.front().unwrap().0.elapsed()).unwrap_or_default()
+            };
+            debug!("{}", iformat!("Request weight limit {self.limit} reached, waiting {wait:?}"));
+            thread::sleep(wait);
+        }
+    }
+
+    /// Exchange says limit is hit, so nothing else runs until the window is over
+    fn saturate(&self) {
+        let mut used = self.used.lock().unwrap();
+        used.push_back((Instant::now(), self.limit));
+    }
+
+    /// Exponential delay for attempt, capped, plus up to 50% jitter
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .checked_mul(2u32.saturating_pow(attempt))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or_default();
+        let jitter = delay.mul_f64((nanos % 1000) as f64 / 2000.0);
+        delay + jitter
+    }
+
+    fn expire(used: &mut VecDeque<(Instant, u32)>, window: Duration) {
+        while used.front().map(|u| u.0.elapsed() >= window).unwrap_or(false) {
+            used.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler(limit: u32, window_ms: u64) -> Scheduler {
+        Scheduler::new(limit, Duration::from_millis(window_ms)).with_retries(3, Duration::from_millis(1), Duration::from_millis(10))
+    }
+
+    #[test]
+    fn weight_window_test() {
+        let scheduler = scheduler(10, 200);
+        let start = Instant::now();
+        scheduler.run(5, || Ok(())).unwrap();
+        scheduler.run(5, || Ok(())).unwrap();
+        assert_eq!(scheduler.used_weight(), 10);
+        assert!(start.elapsed() < Duration::from_millis(200));
+
+        // Third request must wait the first ones leave the window
+        scheduler.run(5, || Ok(())).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        assert_eq!(scheduler.used_weight(), 5);
+    }
+
+    #[test]
+    fn retry_transient_test() {
+        let scheduler = scheduler(100, 1000);
+        let mut calls = 0;
+        let result = scheduler.run(1, || {
+            calls += 1;
+            if calls < 3 {
+                Err(RequestError::new(Failure::Transient, "timeout"))
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        let mut calls = 0;
+        let result: anyhow::Result<()> = scheduler.run(1, || {
+            calls += 1;
+            Err(RequestError::new(Failure::Transient, "timeout"))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 4);
+    }
+
+    #[test]
+    fn no_retry_test() {
+        let scheduler = scheduler(100, 1000);
+        for failure in [Failure::Banned, Failure::Fatal].iter() {
+            let mut calls = 0;
+            let result: anyhow::Result<()> = scheduler.run(1, || {
+                calls += 1;
+                Err(RequestError::new(*failure, "no retry"))
+            });
+            assert!(result.is_err());
+            assert_eq!(calls, 1);
+        }
+    }
+
+    #[test]
+    fn rate_limited_test() {
+        let scheduler = scheduler(100, 200);
+        let start = Instant::now();
+        let mut calls = 0;
+        let result = scheduler.run(1, || {
+            calls += 1;
+            if calls == 1 {
+                Err(RequestError::new(Failure::RateLimited, "Received response: 429"))
+            } else {
+                Ok(())
+            }
+        });
+        assert!(result.is_ok());
+        // Retry waited the whole window
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn backoff_test() {
+        let scheduler = Scheduler::new(100, Duration::from_secs(60)).with_retries(10, Duration::from_millis(100), Duration::from_secs(1));
+        let first = scheduler.backoff(0);
+        assert!(first >= Duration::from_millis(100) && first <= Duration::from_millis(150));
+        let third = scheduler.backoff(2);
+        assert!(third >= Duration::from_millis(400) && third <= Duration::from_millis(600));
+        let capped = scheduler.backoff(20);
+        assert!(capped >= Duration::from_secs(1) && capped <= Duration::from_millis(1500));
+    }
+}
@@ -3,8 +3,10 @@
 
 pub mod analyzers;
 pub mod application;
+mod candles_import;
 pub mod candles_range;
 mod candles_utils;
+mod candles_validation;
 pub mod checker;
 mod config;
 mod exchange;
@@ -13,33 +15,78 @@ mod repository;
 mod strategy;
 mod tac_plotters;
 mod technicals;
+mod trades_resample;
 pub mod utils;
-use application::{app::Application, streamer::Streamer};
+use anyhow::{bail, Context};
+use application::{
+    app::Application,
+    export_selection::{ExportFormat, TacExport},
+    streamer::Streamer,
+};
+use candles_import::ColumnMapping;
 use candles_utils::str_to_datetime;
-use checker::Checker;
+use checker::{synchronize_all, Checker};
 use config::{candles_selection::CandlesSelection, selection::Selection, symbol_minutes::SymbolMinutes};
-use exchange::Exchange;
-use log::{info, LevelFilter};
-use repository::Repository;
-use std::collections::HashMap;
+use exchange::{binance_exchange::BinanceExchange, kline_stream::kline_stream_url, replay_exchange::ReplayExchange, Exchange};
+use log::{info, warn, LevelFilter};
+use model::{interval::Interval, market::Market};
+use repository::{memory_repository::MemoryRepository, pg_repository::PgRepository, Repository};
+use rust_decimal::Decimal;
+use std::{collections::HashMap, env, fs, path::PathBuf, sync::Arc};
 use structopt::StructOpt;
-use technicals::{ema_tac::EmaTac, macd::macd_tac::MacdTac, technical::TechnicalDefinition};
+use technicals::{ema_tac::EmaTac, macd::macd_tac::MacdTac, sma_tac::SmaTac, technical::TechnicalDefinition};
+use trades_resample::Bucket;
 
 #[derive(Debug, StructOpt)]
 #[structopt(about = "Commands")]
 enum Command {
     /// Check content
     Check {},
-    /// Synchronize
-    Sync {},
-    /// Fix records
+    /// Synchronize candles of -y, -m and -k, of pairs of a config file or of every pair stored, several pairs at a time
+    Sync {
+        /// Every symbol, interval and market the repository holds
+        #[structopt(long, conflicts_with = "config")]
+        all: bool,
+        /// Json file with a list of pairs (e.g. [{"symbol": "BTCUSDT", "interval": "15m", "market": "spot"}])
+        #[structopt(long, parse(from_os_str))]
+        config: Option<PathBuf>,
+    },
+    /// Synchronize funding rates (futures market)
+    SyncFunding {},
+    /// Synchronize open interest of interval (futures market, last 30 days)
+    SyncOpenInterest {},
+    /// Delete inconsistent candles of last 180 days
     Fix {},
+    /// Fetch from exchange candles missing between start and end time, gaps exchange doesn't have are reported
+    Repair {},
+    /// Validate stored candles between start and end time: prices, volume, close time, duplicates and spikes
+    Validate {
+        /// Spike threshold, change of high or low from previous close as a ratio (0.2 is 20%)
+        #[structopt(long, default_value = "0.2")]
+        spike: Decimal,
+        /// Move invalid candles to table candle_quarantine
+        #[structopt(long)]
+        quarantine: bool,
+        /// Write report as json to file
+        #[structopt(long, parse(from_os_str))]
+        report: Option<PathBuf>,
+    },
     /// Delete all candles
     DeleteAll,
     /// List  
     List {},
-    /// Import from excange
-    Import {},
+    /// Import candles from csv files or zipped csv files (e.g. Binance monthly kline dumps) of a file or directory
+    Import {
+        /// File or directory of files
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
+        /// Columns of a generic csv, by header name or position (e.g. open_time=Date,open=1,high=2,low=3,close=4,volume=5), default is Binance kline layout
+        #[structopt(short, long)]
+        columns: Option<ColumnMapping>,
+        /// Field delimiter of a generic csv
+        #[structopt(long, default_value = ",")]
+        delimiter: char,
+    },
     /// Plot graph
     Plot {},
     /// Triangle
@@ -48,6 +95,63 @@ enum Command {
     Stream {},
     /// Run trader bot back test
     BackTest {},
+    /// Receive live candles from exchange stream
+    Live {},
+    /// List spot account balances (requires API keys)
+    Balance {},
+    /// Show symbol trading rules (tick size, lot step, min notional)
+    SymbolInfo {},
+    /// Import aggregate trades between start and end time
+    ImportTrades {},
+    /// Build candles from stored aggregate trades, checked against klines of same interval
+    ResampleTrades {
+        /// Candle period, an interval (e.g. 15m) or any count of s, m, h or d (e.g. 45s, 2m)
+        #[structopt(short, long, default_value = "15m")]
+        bucket: Bucket,
+    },
+    /// Export candles of selection, with indicators of tacs, to csv, json lines or columnar binary file
+    Export {
+        /// File to write
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
+        /// Format: csv, jsonl or columnar
+        #[structopt(short, long, default_value = "csv")]
+        format: ExportFormat,
+        /// Tacs whose selected indicators are exported, periods optional (e.g. macd,ema:72 or macd:12:26:9)
+        #[structopt(short, long, use_delimiter = true)]
+        tacs: Vec<TacExport>,
+    },
+    /// Aggregate stored candles into a higher interval, buckets with missing candles flagged incomplete
+    Resample {
+        /// Interval to build, a multiple of stored interval (e.g. 1h, 4h, 1w, 1M)
+        #[structopt(short, long, default_value = "1h")]
+        to: Interval,
+        /// Save resampled candles (table resampled_candle)
+        #[structopt(long)]
+        materialize: bool,
+    },
+}
+
+impl Command {
+    /// Command saves to repository, so it needs a database unless memory is asked
+    fn writes(&self) -> bool {
+        match self {
+            Command::Sync { .. }
+            | Command::SyncFunding {}
+            | Command::SyncOpenInterest {}
+            | Command::Fix {}
+            | Command::Repair {}
+            | Command::DeleteAll
+            | Command::Import { .. }
+            | Command::Stream {}
+            | Command::BackTest {}
+            | Command::Live {}
+            | Command::ImportTrades {} => true,
+            Command::Validate { quarantine, .. } => *quarantine,
+            Command::Resample { materialize, .. } => *materialize,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, StructOpt)]
@@ -59,23 +163,35 @@ struct Opt {
     /// Symbol (e.g. BTCUST)
     #[structopt(short = "y", long, default_value = "BTCUSDT")]
     symbol: String,
-    /// Minutes (e.g. 15)
-    #[structopt(short, long, default_value = "15")]
-    minutes: u32,
+    /// Interval (e.g. 15m, 4h, 1d, 1w, 1M) or its minutes (e.g. 15)
+    #[structopt(short = "m", long, alias = "minutes", default_value = "15m")]
+    interval: Interval,
+    /// Market (spot or futures)
+    #[structopt(short = "k", long, default_value = "futures")]
+    market: Market,
     /// Start date time
     #[structopt(short, long, default_value = "2020-11-01 00:00:00")]
     start_time: String,
     /// End date time
     #[structopt(short, long, default_value = "2020-12-01 00:00:00")]
     end_time: String,
+    /// Replay klines from csv files in this directory instead of calling exchange
+    #[structopt(long)]
+    replay: Option<String>,
+    /// Keep candles in memory instead of database, nothing is saved on exit
+    #[structopt(long)]
+    memory: bool,
+    /// Kline stream base url (e.g. ws://localhost:9000/ws), default is exchange url of market
+    #[structopt(long)]
+    stream_url: Option<String>,
     #[structopt(subcommand)]
     command: Command,
 }
 
 pub fn selection_factory(candles_selection: CandlesSelection) -> Selection {
     let mut tacs = HashMap::new();
-    for tac in vec![MacdTac::definition(), EmaTac::definition()] {
-        tacs.insert(tac.name.clone(), tac);
+    for tac in [MacdTac::definition(), EmaTac::definition(), SmaTac::definition()].iter() {
+        tacs.insert(tac.name.clone(), tac.clone());
     }
     Selection {
         tacs,
@@ -84,6 +200,45 @@ pub fn selection_factory(candles_selection: CandlesSelection) -> Selection {
     }
 }
 
+pub fn exchange_factory(replay: &Option<String>) -> Arc<dyn Exchange + Send + Sync> {
+    match replay {
+        Some(dir) => Arc::new(ReplayExchange::new(dir)),
+        None => Arc::new(BinanceExchange::new()),
+    }
+}
+
+/// In-memory when asked (content lost on exit), otherwise Postgres of database url.
+///
+/// Without database url only commands not writing fall back to memory, the others fail.
+pub async fn repository_factory(database_url: &Option<String>, memory: bool, writes: bool) -> anyhow::Result<Arc<dyn Repository + Send + Sync>> {
+    match database_url {
+        _ if memory => {
+            warn!("Using in-memory repository, nothing is kept after exit");
+            Ok(Arc::new(MemoryRepository::new()))
+        }
+        Some(database_url) => Ok(Arc::new(PgRepository::new(database_url).await?)),
+        None if writes => bail!("DATABASE_URL not set, command saves to repository (use --memory to keep it in memory only)"),
+        None => {
+            warn!("DATABASE_URL not set, using in-memory repository");
+            Ok(Arc::new(MemoryRepository::new()))
+        }
+    }
+}
+
+/// Pairs to synchronize: every pair stored, pairs of json config file or pair of command line
+async fn sync_pairs(all: bool, config: &Option<PathBuf>, repo: &dyn Repository, symbol_minutes: &SymbolMinutes) -> anyhow::Result<Vec<SymbolMinutes>> {
+    if all {
+        return Ok(repo.symbols_minutes().await?.into_iter().map(|(symbol_minutes, _)| symbol_minutes).collect());
+    }
+    match config {
+        Some(config) => {
+            let text = fs::read_to_string(config).with_context(|| format!("Sync config {:?} not readable", config))?;
+            serde_json::from_str(&text).with_context(|| format!("Sync config {:?} is not a list of pairs", config))
+        }
+        None => Ok(vec![symbol_minutes.clone()]),
+    }
+}
+
 #[async_std::main]
 async fn main() -> anyhow::Result<()> {
     let opt = Opt::from_args();
@@ -92,45 +247,115 @@ async fn main() -> anyhow::Result<()> {
 
     utils::log_utils::setup_log(level, module_path!());
 
-    dotenv::dotenv()?;
-    let exchange: Exchange = Exchange::new()?;
-    let repo: Repository = Repository::new()?;
+    // Environment variables may come from shell instead of .env file
+    dotenv::dotenv().ok();
+    let exchange = exchange_factory(&opt.replay);
+    let repo = repository_factory(&env::var("DATABASE_URL").ok(), opt.memory, opt.command.writes()).await?;
 
-    let candles_selection = CandlesSelection::new(&opt.symbol, &opt.minutes, str_to_datetime(&opt.start_time), str_to_datetime(&opt.end_time));
+    let candles_selection = CandlesSelection::new(
+        &opt.symbol,
+        &opt.interval,
+        &opt.market,
+        str_to_datetime(&opt.start_time),
+        str_to_datetime(&opt.end_time),
+    );
     let selection = selection_factory(candles_selection.clone());
 
-    let symbol_minutes = SymbolMinutes::new(&opt.symbol, &opt.minutes);
-    let checker = Checker::new(&symbol_minutes, &repo, &exchange);
+    let symbol_minutes = SymbolMinutes::new(&opt.symbol, &opt.interval, &opt.market);
+    let checker = Checker::new(&symbol_minutes, repo.as_ref(), exchange.as_ref());
 
-    let mut app = Application::new(Repository::new()?, Exchange::new()?, &checker, selection);
+    let mut app = Application::new(repo.clone(), exchange.clone(), &checker, selection);
 
     match opt.command {
         Command::Check {} => {
-            checker.check_inconsist(&repo, &candles_selection);
+            checker.check_inconsist(repo.as_ref(), &candles_selection).await?;
         }
-        Command::Sync {} => {
-            checker.synchronize()?;
+        Command::Sync { all, config } => {
+            let pairs = sync_pairs(all, &config, repo.as_ref(), &symbol_minutes).await?;
+            let results = synchronize_all(&pairs, repo.clone(), exchange.clone()).await;
+            let failed = results.iter().filter(|(_, result)| result.is_err()).count();
+            for (pair, result) in results.iter() {
+                match result {
+                    Ok(summary) => info!("{}", summary),
+                    Err(e) => warn!("{} {} {}: failed, {}", pair.symbol, pair.interval, pair.market, e),
+                }
+            }
+            if failed > 0 {
+                bail!("Sync failed on {} of {} pairs", failed, results.len());
+            }
         }
+        Command::SyncFunding {} => checker.synchronize_funding_rates().await?,
+        Command::SyncOpenInterest {} => checker.synchronize_open_interests().await?,
         Command::Fix {} => {
-            checker.delete_inconsist();
+            checker.delete_inconsist().await?;
+        }
+        Command::Repair {} => {
+            let stats = checker.repair(&str_to_datetime(&opt.start_time), &str_to_datetime(&opt.end_time)).await?;
+            info!("Repair finished: {}", stats);
+        }
+        Command::Validate { spike, quarantine, report } => {
+            let validation = checker
+                .validate_candles(&str_to_datetime(&opt.start_time), &str_to_datetime(&opt.end_time), spike, quarantine)
+                .await?;
+            if let Some(report) = report {
+                fs::write(&report, serde_json::to_string_pretty(&validation.to_json())?)?;
+                info!("Report written to {:?}", report);
+            }
         }
         Command::DeleteAll {} => {
             info!("Deleting all candles...");
-            repo.delete_all_candles()?;
+            repo.delete_all_candles().await?;
         }
         Command::List {} => {
-            repo.list_candles(&opt.symbol, &opt.minutes, &10);
+            repo.list_candles(&symbol_minutes, &10).await?;
         }
         Command::Plot {} => app.plot_selection()?,
         Command::Stream {} => {
             let mut streamer = Streamer::new(&mut app);
             streamer.run()?;
         }
-        Command::Import {} => {}
+        Command::Import { path, columns, delimiter } => {
+            let stats = checker.import_candles(&path, columns, delimiter).await?;
+            info!("Import finished: {}", stats);
+        }
         Command::Triangle {} => {
             app.plot_triangles()?;
         }
         Command::BackTest {} => app.run_back_test()?,
+        Command::Live {} => app.run_live(&kline_stream_url(&opt.stream_url, &symbol_minutes))?,
+        Command::Balance {} => {
+            for balance in BinanceExchange::new().balances()? {
+                info!("{} free: {} locked: {}", balance.asset, balance.free, balance.locked);
+            }
+        }
+        Command::SymbolInfo {} => info!("{}", app.symbol_info()?),
+        Command::ImportTrades {} => {
+            checker
+                .import_trades(&str_to_datetime(&opt.start_time), &str_to_datetime(&opt.end_time))
+                .await?
+        }
+        Command::ResampleTrades { bucket } => {
+            for candle in checker
+                .resample_trades(&bucket, &str_to_datetime(&opt.start_time), &str_to_datetime(&opt.end_time))
+                .await?
+            {
+                info!("{}", candle);
+            }
+        }
+        Command::Export { path, format, tacs } => app.export(&tacs, &format, &path)?,
+        Command::Resample { to, materialize } => {
+            let resampled = checker
+                .resample_candles(&to, &str_to_datetime(&opt.start_time), &str_to_datetime(&opt.end_time), materialize)
+                .await?;
+            for candle in resampled {
+                let flag = if candle.is_complete() {
+                    String::new()
+                } else {
+                    format!(" incomplete {}/{}", candle.sources, candle.expected)
+                };
+                info!("{}{}", candle.candle, flag);
+            }
+        }
     };
     info!("Exiting program");
     Ok(())
@@ -0,0 +1,30 @@
+use super::{exchange_name::ExchangeName, market::Market};
+use chrono::{DateTime, Utc};
+use ifmt::iwrite;
+use rust_decimal::Decimal;
+use std::fmt::Display;
+
+/// Aggregate trade: trades of one taker order at the same price
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AggTrade {
+    pub exchange: ExchangeName,
+    pub symbol: String,
+    pub market: Market,
+    /// Aggregate trade id, sequential by symbol and market
+    pub id: i64,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    /// Trade time, with milliseconds
+    pub time: DateTime<Utc>,
+    /// Buyer was the maker, so it was a sell taker order
+    pub is_buyer_maker: bool,
+}
+
+impl Display for AggTrade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        iwrite!(
+            f,
+            "{self.symbol} {self.market} {self.id} {self.time} price {self.price} quantity {self.quantity} buyer maker {self.is_buyer_maker}"
+        )
+    }
+}
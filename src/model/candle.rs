@@ -1,4 +1,4 @@
-use super::open_close::OpenClose;
+use super::{exchange_name::ExchangeName, market::Market, open_close::OpenClose};
 use crate::candles_utils::{str_to_datetime, time_to_str};
 use chrono::{DateTime, Utc};
 use ifmt::iwrite;
@@ -17,6 +17,8 @@ pub struct Candle {
     pub low: Decimal,
     pub close: Decimal,
     pub volume: Decimal,
+    pub market: String,
+    pub exchange: String,
 }
 
 impl Candle {
@@ -43,6 +45,8 @@ impl Candle {
             low,
             close,
             volume,
+            market: Market::default().to_string(),
+            exchange: ExchangeName::default().to_string(),
         }
     }
 
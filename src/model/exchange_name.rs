@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Exchange a symbol is traded on, same symbol and market on two exchanges are distinct candles
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum ExchangeName {
+    Binance,
+}
+
+impl Default for ExchangeName {
+    fn default() -> Self {
+        ExchangeName::Binance
+    }
+}
+
+impl ExchangeName {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExchangeName::Binance => "binance",
+        }
+    }
+}
+
+impl fmt::Display for ExchangeName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ExchangeName {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "binance" => Ok(ExchangeName::Binance),
+            _ => Err(format!("Content {} is not valid exchange (binance)!", value)),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn exchange_name_test() {
+        assert_eq!("binance".parse::<ExchangeName>().unwrap(), ExchangeName::Binance);
+        assert_eq!(ExchangeName::default().to_string(), "binance");
+        assert!("kraken".parse::<ExchangeName>().is_err());
+    }
+}
@@ -0,0 +1,27 @@
+use super::exchange_name::ExchangeName;
+use chrono::{DateTime, Duration, Utc};
+use ifmt::iwrite;
+use rust_decimal::Decimal;
+use std::fmt::Display;
+
+/// Perpetual futures are funded every 8 hours
+pub fn funding_period() -> Duration {
+    Duration::hours(8)
+}
+
+/// Funding rate paid between long and short positions of a perpetual futures symbol at funding time
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FundingRate {
+    pub exchange: ExchangeName,
+    pub symbol: String,
+    pub funding_time: DateTime<Utc>,
+    pub funding_rate: Decimal,
+    /// Mark price at funding time, exchange doesn't answer it for old fundings
+    pub mark_price: Option<Decimal>,
+}
+
+impl Display for FundingRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        iwrite!(f, "{self.symbol} {self.funding_time} rate {self.funding_rate} mark price {self.mark_price:?}")
+    }
+}
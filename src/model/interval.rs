@@ -0,0 +1,250 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+/// Candle interval, with the same codes used by Binance klines (1m ... 1M)
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "IntervalValue", into = "String")]
+pub enum Interval {
+    M1,
+    M3,
+    M5,
+    M15,
+    M30,
+    H1,
+    H2,
+    H4,
+    H6,
+    H8,
+    H12,
+    D1,
+    D3,
+    W1,
+    Month1,
+}
+
+impl Default for Interval {
+    fn default() -> Self {
+        Interval::M15
+    }
+}
+
+/// Interval accepted on json configs, as code ("4h") or as minutes (15)
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IntervalValue {
+    Minutes(u32),
+    Code(String),
+}
+
+const MINUTES_HOUR: u32 = 60;
+const MINUTES_DAY: u32 = 24 * MINUTES_HOUR;
+
+impl Interval {
+    pub const ALL: [Interval; 15] = [
+        Interval::M1,
+        Interval::M3,
+        Interval::M5,
+        Interval::M15,
+        Interval::M30,
+        Interval::H1,
+        Interval::H2,
+        Interval::H4,
+        Interval::H6,
+        Interval::H8,
+        Interval::H12,
+        Interval::D1,
+        Interval::D3,
+        Interval::W1,
+        Interval::Month1,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Interval::M1 => "1m",
+            Interval::M3 => "3m",
+            Interval::M5 => "5m",
+            Interval::M15 => "15m",
+            Interval::M30 => "30m",
+            Interval::H1 => "1h",
+            Interval::H2 => "2h",
+            Interval::H4 => "4h",
+            Interval::H6 => "6h",
+            Interval::H8 => "8h",
+            Interval::H12 => "12h",
+            Interval::D1 => "1d",
+            Interval::D3 => "3d",
+            Interval::W1 => "1w",
+            Interval::Month1 => "1M",
+        }
+    }
+
+    /// Nominal minutes, month is 30 days. It is the value stored on candle `minutes` column.
+    pub fn minutes(&self) -> u32 {
+        match self {
+            Interval::M1 => 1,
+            Interval::M3 => 3,
+            Interval::M5 => 5,
+            Interval::M15 => 15,
+            Interval::M30 => 30,
+            Interval::H1 => MINUTES_HOUR,
+            Interval::H2 => 2 * MINUTES_HOUR,
+            Interval::H4 => 4 * MINUTES_HOUR,
+            Interval::H6 => 6 * MINUTES_HOUR,
+            Interval::H8 => 8 * MINUTES_HOUR,
+            Interval::H12 => 12 * MINUTES_HOUR,
+            Interval::D1 => MINUTES_DAY,
+            Interval::D3 => 3 * MINUTES_DAY,
+            Interval::W1 => 7 * MINUTES_DAY,
+            Interval::Month1 => 30 * MINUTES_DAY,
+        }
+    }
+
+    pub fn from_minutes(minutes: u32) -> Result<Self, String> {
+        Interval::ALL
+            .iter()
+            .find(|i| i.minutes() == minutes)
+            .copied()
+            .ok_or_else(|| format!("Minutes {} is not a valid interval!", minutes))
+    }
+
+    /// Nominal duration, see `minutes`
+    pub fn duration(&self) -> Duration {
+        Duration::minutes(self.minutes() as i64)
+    }
+
+    /// Open time of interval containing date time.
+    ///
+    /// Intervals until 3d are aligned to epoch, weeks start on monday and months on first day.
+    pub fn open_trunc(&self, date_time: &DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Interval::Month1 => first_day(date_time.year(), date_time.month()),
+            Interval::W1 => {
+                let monday = first_day(1970, 1) + Duration::days(4);
+                let weeks = (*date_time - monday).num_seconds().div_euclid(self.duration().num_seconds());
+                monday + Duration::weeks(weeks)
+            }
+            _ => {
+                let seconds = self.duration().num_seconds();
+                Utc.timestamp_opt(date_time.timestamp().div_euclid(seconds) * seconds, 0).unwrap()
+            }
+        }
+    }
+
+    /// Open time of next interval
+    pub fn next_open(&self, open: &DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Interval::Month1 => {
+                let open = self.open_trunc(open);
+                let (year, month) = if open.month() == 12 {
+                    (open.year() + 1, 1)
+                } else {
+                    (open.year(), open.month() + 1)
+                };
+                first_day(year, month)
+            }
+            _ => self.open_trunc(open) + self.duration(),
+        }
+    }
+
+    /// Open time of previous interval
+    pub fn prev_open(&self, open: &DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Interval::Month1 => self.open_trunc(&(self.open_trunc(open) - Duration::days(1))),
+            _ => self.open_trunc(open) - self.duration(),
+        }
+    }
+
+    /// Close time (last second) of interval opened at `open`
+    pub fn close(&self, open: &DateTime<Utc>) -> DateTime<Utc> {
+        self.next_open(open) - Duration::seconds(1)
+    }
+}
+
+fn first_day(year: i32, month: u32) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&NaiveDate::from_ymd_opt(year, month, 1).unwrap().and_hms_opt(0, 0, 0).unwrap())
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Interval {
+    type Err = String;
+
+    /// Parses interval code (e.g. "1h", "1M") or minutes (e.g. "60")
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Ok(minutes) = value.parse::<u32>() {
+            return Interval::from_minutes(minutes);
+        }
+        Interval::ALL.iter().find(|i| i.as_str() == value).copied().ok_or_else(|| {
+            format!(
+                "Content {} is not valid interval (1m, 3m, 5m, 15m, 30m, 1h, 2h, 4h, 6h, 8h, 12h, 1d, 3d, 1w, 1M)!",
+                value
+            )
+        })
+    }
+}
+
+impl TryFrom<IntervalValue> for Interval {
+    type Error = String;
+
+    fn try_from(value: IntervalValue) -> Result<Self, Self::Error> {
+        match value {
+            IntervalValue::Minutes(minutes) => Interval::from_minutes(minutes),
+            IntervalValue::Code(code) => code.parse(),
+        }
+    }
+}
+
+impl From<Interval> for String {
+    fn from(interval: Interval) -> Self {
+        interval.as_str().to_string()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::candles_utils::str_d;
+
+    #[test]
+    fn interval_parse_test() {
+        assert_eq!("4h".parse::<Interval>().unwrap(), Interval::H4);
+        assert_eq!("60".parse::<Interval>().unwrap(), Interval::H1);
+        assert_eq!("1M".parse::<Interval>().unwrap(), Interval::Month1);
+        assert_eq!("1m".parse::<Interval>().unwrap(), Interval::M1);
+        assert!("7m".parse::<Interval>().is_err());
+        assert!(Interval::from_minutes(45).is_err());
+
+        assert_eq!(serde_json::from_str::<Interval>("15").unwrap(), Interval::M15);
+        assert_eq!(serde_json::from_str::<Interval>("\"1d\"").unwrap(), Interval::D1);
+        assert_eq!(serde_json::to_string(&Interval::W1).unwrap(), "\"1w\"");
+    }
+
+    #[test]
+    fn interval_open_trunc_test() {
+        assert_eq!(Interval::M15.open_trunc(&str_d("2020-01-01 00:17:00")), str_d("2020-01-01 00:15:00"));
+        assert_eq!(Interval::H1.open_trunc(&str_d("2020-01-01 10:59:59")), str_d("2020-01-01 10:00:00"));
+        assert_eq!(Interval::H4.open_trunc(&str_d("2020-01-01 03:30:00")), str_d("2020-01-01 00:00:00"));
+        assert_eq!(Interval::H4.open_trunc(&str_d("2020-01-01 13:30:00")), str_d("2020-01-01 12:00:00"));
+        assert_eq!(Interval::D1.open_trunc(&str_d("2020-01-01 23:59:59")), str_d("2020-01-01 00:00:00"));
+        // 2020-11-11 is a wednesday
+        assert_eq!(Interval::W1.open_trunc(&str_d("2020-11-11 10:00:00")), str_d("2020-11-09 00:00:00"));
+        assert_eq!(Interval::W1.open_trunc(&str_d("2020-11-09 00:00:00")), str_d("2020-11-09 00:00:00"));
+        assert_eq!(Interval::Month1.open_trunc(&str_d("2020-02-29 10:00:00")), str_d("2020-02-01 00:00:00"));
+    }
+
+    #[test]
+    fn interval_step_test() {
+        assert_eq!(Interval::H1.next_open(&str_d("2020-01-01 23:00:00")), str_d("2020-01-02 00:00:00"));
+        assert_eq!(Interval::H1.close(&str_d("2020-01-01 23:00:00")), str_d("2020-01-01 23:59:59"));
+        assert_eq!(Interval::W1.next_open(&str_d("2020-11-09 00:00:00")), str_d("2020-11-16 00:00:00"));
+        assert_eq!(Interval::Month1.next_open(&str_d("2020-12-01 00:00:00")), str_d("2021-01-01 00:00:00"));
+        assert_eq!(Interval::Month1.close(&str_d("2020-02-01 00:00:00")), str_d("2020-02-29 23:59:59"));
+        assert_eq!(Interval::Month1.prev_open(&str_d("2020-03-01 00:00:00")), str_d("2020-02-01 00:00:00"));
+        assert_eq!(Interval::D1.prev_open(&str_d("2020-03-01 00:00:00")), str_d("2020-02-29 00:00:00"));
+    }
+}
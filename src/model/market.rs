@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Market where a symbol is traded, each one has its own klines endpoint
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Market {
+    /// Spot market
+    Spot,
+    /// USD-M perpetual futures market
+    Futures,
+}
+
+impl Default for Market {
+    fn default() -> Self {
+        Market::Futures
+    }
+}
+
+impl Market {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Market::Spot => "spot",
+            Market::Futures => "futures",
+        }
+    }
+}
+
+impl fmt::Display for Market {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Market {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "spot" => Ok(Market::Spot),
+            "futures" => Ok(Market::Futures),
+            _ => Err(format!("Content {} is not valid market (spot, futures)!", value)),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn market_test() {
+        assert_eq!("spot".parse::<Market>().unwrap(), Market::Spot);
+        assert_eq!(Market::Futures.to_string().parse::<Market>().unwrap(), Market::Futures);
+        assert!("margin".parse::<Market>().is_err());
+    }
+}
@@ -1,3 +1,11 @@
+pub mod agg_trade;
 pub mod candle;
+pub mod exchange_name;
+pub mod funding_rate;
+pub mod interval;
+pub mod market;
 pub mod open_close;
+pub mod open_interest;
+pub mod resampled_candle;
 pub mod symbol;
+pub mod symbol_info;
@@ -3,10 +3,8 @@ use std::{cmp::Ordering, convert::TryFrom, fmt, ops::Add, ops::Sub};
 use anyhow::bail;
 use chrono::{DateTime, Duration, Timelike, Utc};
 
-use crate::{
-    candles_range::minutes_open_trunc,
-    candles_utils::{str_d, str_to_datetime},
-};
+use super::interval::Interval;
+use crate::candles_utils::{str_d, str_to_datetime};
 #[derive(Debug, Eq, Copy, Clone)]
 pub enum OpenClose {
     Open(DateTime<Utc>),
@@ -85,38 +83,54 @@ impl TryFrom<&str> for OpenClose {
 }
 
 impl OpenClose {
-    pub fn to_dates(&self, minutes: &u32) -> (DateTime<Utc>, DateTime<Utc>) {
+    pub fn to_dates(self, interval: &Interval) -> (DateTime<Utc>, DateTime<Utc>) {
         match self {
-            OpenClose::OpenClose(o, c) => (*o, *c),
-            OpenClose::Open(o) => (*o, *o + Duration::minutes(*minutes as i64) - Duration::seconds(1)),
-            OpenClose::Close(c) => (*c - Duration::minutes(*minutes as i64) + Duration::seconds(1), *c),
+            OpenClose::OpenClose(o, c) => (o, c),
+            OpenClose::Open(o) => (o, interval.close(&o)),
+            OpenClose::Close(c) => (interval.open_trunc(&c), c),
         }
     }
 
-    pub fn open(&self, minutes: &u32) -> DateTime<Utc> {
-        self.to_dates(minutes).0
+    pub fn open(&self, interval: &Interval) -> DateTime<Utc> {
+        self.to_dates(interval).0
     }
 
-    pub fn close(&self, minutes: &u32) -> DateTime<Utc> {
-        self.to_dates(minutes).1
+    pub fn close(&self, interval: &Interval) -> DateTime<Utc> {
+        self.to_dates(interval).1
     }
 
-    pub fn from_date(date_time: &DateTime<Utc>, minutes: &u32) -> OpenClose {
-        let open = minutes_open_trunc(date_time, minutes);
-        let close = open + Duration::minutes(*minutes as i64) - Duration::seconds(1);
-        OpenClose::OpenClose(open, close)
+    pub fn from_date(date_time: &DateTime<Utc>, interval: &Interval) -> OpenClose {
+        let open = interval.open_trunc(date_time);
+        OpenClose::OpenClose(open, interval.close(&open))
     }
 
-    pub fn from_date_close(close: &DateTime<Utc>, minutes: &u32) -> OpenClose {
-        let close = *close;
-        let open = close + Duration::seconds(1) - Duration::minutes(*minutes as i64);
-        OpenClose::OpenClose(open, close)
+    pub fn from_date_close(close: &DateTime<Utc>, interval: &Interval) -> OpenClose {
+        let open = interval.open_trunc(close);
+        OpenClose::OpenClose(open, *close)
     }
 
-    pub fn from_str(date_time: &str, minutes: &u32) -> OpenClose {
-        let open = minutes_open_trunc(&str_d(date_time), minutes);
-        let close = open + Duration::minutes(*minutes as i64) - Duration::seconds(1);
-        OpenClose::OpenClose(open, close)
+    pub fn from_str(date_time: &str, interval: &Interval) -> OpenClose {
+        OpenClose::from_date(&str_d(date_time), interval)
+    }
+
+    /// Same kind of open/close, moved to next interval
+    pub fn next(&self, interval: &Interval) -> OpenClose {
+        let (open, _) = self.to_dates(interval);
+        self.with_open(interval, interval.next_open(&open))
+    }
+
+    /// Same kind of open/close, moved to previous interval
+    pub fn prev(&self, interval: &Interval) -> OpenClose {
+        let (open, _) = self.to_dates(interval);
+        self.with_open(interval, interval.prev_open(&open))
+    }
+
+    fn with_open(&self, interval: &Interval, open: DateTime<Utc>) -> OpenClose {
+        match self {
+            OpenClose::Open(_) => OpenClose::Open(open),
+            OpenClose::Close(_) => OpenClose::Close(interval.close(&open)),
+            OpenClose::OpenClose(_, _) => OpenClose::OpenClose(open, interval.close(&open)),
+        }
     }
 }
 
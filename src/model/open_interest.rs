@@ -0,0 +1,27 @@
+use super::{exchange_name::ExchangeName, interval::Interval};
+use chrono::{DateTime, Utc};
+use ifmt::iwrite;
+use rust_decimal::Decimal;
+use std::fmt::Display;
+
+/// Open interest statistics of a futures symbol, a snapshot taken each interval
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpenInterest {
+    pub exchange: ExchangeName,
+    pub symbol: String,
+    pub interval: Interval,
+    pub time: DateTime<Utc>,
+    /// Open contracts in base asset
+    pub sum_open_interest: Decimal,
+    /// Open contracts value in quote asset
+    pub sum_open_interest_value: Decimal,
+}
+
+impl Display for OpenInterest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        iwrite!(
+            f,
+            "{self.symbol} {self.interval} {self.time} open interest {self.sum_open_interest} value {self.sum_open_interest_value}"
+        )
+    }
+}
@@ -0,0 +1,147 @@
+use super::{candle::Candle, interval::Interval};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+/// Candle aggregated from stored candles of a lower interval.
+///
+/// A bucket is complete when every source candle it should have is stored, gaps leave it incomplete.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResampledCandle {
+    pub candle: Candle,
+    /// Interval of candles aggregated
+    pub source: Interval,
+    /// Source candles aggregated
+    pub sources: u32,
+    /// Source candles of a complete bucket
+    pub expected: u32,
+}
+
+impl ResampledCandle {
+    pub fn new(candle: Candle, interval: &Interval, source: &Interval, sources: u32) -> Self {
+        let expected = expected_sources(interval, source, &candle.open_time);
+        Self {
+            candle,
+            source: *source,
+            sources,
+            expected,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.sources == self.expected
+    }
+}
+
+/// Source candles of bucket of interval opened at `open`, months and weeks count their actual days
+pub fn expected_sources(interval: &Interval, source: &Interval, open: &DateTime<Utc>) -> u32 {
+    ((interval.next_open(open) - *open).num_minutes() / source.minutes() as i64) as u32
+}
+
+/// Open time range of source candles of buckets opened between start and end time, end excluded
+pub fn resample_range(interval: &Interval, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    (interval.open_trunc(start_time), interval.next_open(&interval.open_trunc(end_time)))
+}
+
+/// Candles of interval aggregated from source candles sorted by open time, one per bucket having any source candle
+pub fn resample(candles: &[Candle], interval: &Interval, source: &Interval) -> Vec<ResampledCandle> {
+    let mut result: Vec<ResampledCandle> = Vec::new();
+    for candle in candles {
+        let open_time = interval.open_trunc(&candle.open_time);
+        match result.last_mut() {
+            Some(last) if last.candle.open_time == open_time => {
+                last.candle.high = last.candle.high.max(candle.high);
+                last.candle.low = last.candle.low.min(candle.low);
+                last.candle.close = candle.close;
+                last.candle.volume += candle.volume;
+                last.sources += 1;
+            }
+            _ => {
+                let resampled = Candle {
+                    id: Decimal::ZERO,
+                    open_time,
+                    close_time: interval.close(&open_time),
+                    minutes: interval.minutes().into(),
+                    ..candle.clone()
+                };
+                result.push(ResampledCandle::new(resampled, interval, source, 1));
+            }
+        }
+    }
+    result
+}
+
+/// Interval can be built from source when it is a multiple of source aligned on same bounds.
+///
+/// Weeks and months start on day bounds, so their source must divide a day.
+pub fn check_resample(interval: &Interval, source: &Interval) -> Result<(), String> {
+    let day = Interval::D1.minutes();
+    let day_aligned = !matches!(interval, Interval::W1 | Interval::Month1) || day % source.minutes() == 0;
+    if interval <= source || interval.minutes() % source.minutes() != 0 || !day_aligned {
+        return Err(format!("Interval {} can't be resampled from {} candles", interval, source));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::candles_utils::{fdec, str_to_datetime};
+
+    #[test]
+    fn check_resample_test() {
+        assert!(check_resample(&Interval::H4, &Interval::M15).is_ok());
+        assert!(check_resample(&Interval::Month1, &Interval::D1).is_ok());
+        assert!(check_resample(&Interval::M15, &Interval::H1).is_err());
+        assert!(check_resample(&Interval::M15, &Interval::M15).is_err());
+        assert!(check_resample(&Interval::W1, &Interval::D3).is_err());
+        assert!(check_resample(&Interval::Month1, &Interval::D3).is_err());
+    }
+
+    #[test]
+    fn expected_sources_test() {
+        assert_eq!(expected_sources(&Interval::H4, &Interval::M15, &str_to_datetime("2020-11-11 08:00:00")), 16);
+        assert_eq!(expected_sources(&Interval::Month1, &Interval::D1, &str_to_datetime("2021-02-01 00:00:00")), 28);
+        assert_eq!(expected_sources(&Interval::Month1, &Interval::H1, &str_to_datetime("2020-12-01 00:00:00")), 31 * 24);
+    }
+
+    #[test]
+    fn resample_test() {
+        let candle = |open_time: &str, close_time: &str, high: f64, low: f64| {
+            Candle::new(
+                0,
+                open_time,
+                close_time,
+                "BTCUSDT",
+                15,
+                fdec(low + 1.0),
+                fdec(high),
+                fdec(low),
+                fdec(high - 1.0),
+                fdec(2.0),
+            )
+        };
+        let candles = [
+            candle("2020-11-11 10:00:00", "2020-11-11 10:14:59", 12.0, 8.0),
+            candle("2020-11-11 10:15:00", "2020-11-11 10:29:59", 14.0, 9.0),
+            candle("2020-11-11 10:45:00", "2020-11-11 10:59:59", 11.0, 7.0),
+            candle("2020-11-11 11:00:00", "2020-11-11 11:14:59", 10.0, 6.0),
+        ];
+
+        let resampled = resample(&candles, &Interval::H1, &Interval::M15);
+
+        assert_eq!(resampled.len(), 2);
+        let first = &resampled[0].candle;
+        assert_eq!(
+            (first.open_time, first.close_time),
+            (str_to_datetime("2020-11-11 10:00:00"), str_to_datetime("2020-11-11 10:59:59"))
+        );
+        assert_eq!(
+            (first.open, first.high, first.low, first.close, first.volume),
+            (fdec(9.0), fdec(14.0), fdec(7.0), fdec(10.0), fdec(6.0))
+        );
+        assert_eq!(first.minutes, Decimal::from(60));
+        assert_eq!((resampled[0].sources, resampled[0].expected), (3, 4));
+        assert!(!resampled[0].is_complete());
+        assert_eq!(resampled[1].sources, 1);
+    }
+}
@@ -0,0 +1,91 @@
+use super::{exchange_name::ExchangeName, market::Market};
+use crate::utils::decimal_utils::DecimalRound;
+use anyhow::bail;
+use chrono::{DateTime, Utc};
+use ifmt::iwrite;
+use rust_decimal::Decimal;
+use std::fmt::Display;
+
+/// Exchange trading rules of a symbol, from exchange info filters
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolInfo {
+    pub exchange: ExchangeName,
+    pub symbol: String,
+    pub market: Market,
+    pub base_asset: String,
+    pub quote_asset: String,
+    /// Price step (PRICE_FILTER), zero when not filtered
+    pub tick_size: Decimal,
+    /// Quantity step (LOT_SIZE), zero when not filtered
+    pub step_size: Decimal,
+    /// Minimum quantity (LOT_SIZE)
+    pub min_qty: Decimal,
+    /// Minimum price * quantity (MIN_NOTIONAL)
+    pub min_notional: Decimal,
+    /// When it was retrieved from exchange
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SymbolInfo {
+    /// Price rounded to nearest tick
+    pub fn round_price(&self, price: &Decimal) -> Decimal {
+        price.round_step(&self.tick_size)
+    }
+
+    /// Quantity rounded down to lot step, so it never exceeds the balance it came from
+    pub fn round_quantity(&self, quantity: &Decimal) -> Decimal {
+        quantity.floor_step(&self.step_size)
+    }
+
+    /// Checks an order of quantity at price is accepted by exchange filters
+    pub fn check_order(&self, price: &Decimal, quantity: &Decimal) -> anyhow::Result<()> {
+        if *quantity < self.min_qty || quantity.is_zero() {
+            bail!("{} quantity {} is less than minimum {}", self.symbol, quantity, self.min_qty);
+        }
+        if price * quantity < self.min_notional {
+            bail!("{} notional {} is less than minimum {}", self.symbol, price * quantity, self.min_notional);
+        }
+        Ok(())
+    }
+}
+
+impl Display for SymbolInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        iwrite!(
+            f,
+            "{self.symbol} {self.market} {self.base_asset}/{self.quote_asset} tick {self.tick_size} step {self.step_size} min qty {self.min_qty} min notional {self.min_notional}"
+        )
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    pub fn symbol_info(symbol: &str, market: &Market) -> SymbolInfo {
+        SymbolInfo {
+            exchange: ExchangeName::Binance,
+            symbol: symbol.into(),
+            market: *market,
+            base_asset: "BTC".into(),
+            quote_asset: "USDT".into(),
+            tick_size: dec!(0.01),
+            step_size: dec!(0.001),
+            min_qty: dec!(0.001),
+            min_notional: dec!(10),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn symbol_info_round_test() {
+        let info = symbol_info("BTCUSDT", &Market::Futures);
+        assert_eq!(info.round_price(&dec!(15321.4761)), dec!(15321.48));
+        assert_eq!(info.round_quantity(&dec!(0.06527999)), dec!(0.065));
+
+        assert!(info.check_order(&dec!(5000), &dec!(0.001)).is_err());
+        assert!(info.check_order(&dec!(15000), &dec!(0.0005)).is_err());
+        assert!(info.check_order(&dec!(15000), &dec!(0.065)).is_ok());
+    }
+}
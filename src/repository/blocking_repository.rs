@@ -0,0 +1,40 @@
+use super::{repository_error::RepositoryResult, Repository};
+use crate::strategy::model::{flow::Flow, order::Order, position::Position, profit::Profit};
+use async_std::task::block_on;
+use std::sync::Arc;
+
+/// Blocking facade of repository, for callers that aren't async (kline stream loop, back test after rayon workers)
+#[derive(Clone)]
+pub struct BlockingRepository {
+    repository: Arc<dyn Repository + Send + Sync>,
+}
+
+impl BlockingRepository {
+    pub fn new(repository: Arc<dyn Repository + Send + Sync>) -> Self {
+        Self { repository }
+    }
+
+    pub fn insert_position(&self, position: &mut Position) -> RepositoryResult<i64> {
+        block_on(self.repository.insert_position(position))
+    }
+
+    pub fn update_position(&self, position: &Position) -> RepositoryResult<()> {
+        block_on(self.repository.update_position(position))
+    }
+
+    pub fn insert_order(&self, order: &mut Order) -> RepositoryResult<i64> {
+        block_on(self.repository.insert_order(order))
+    }
+
+    pub fn update_order(&self, order: &Order) -> RepositoryResult<()> {
+        block_on(self.repository.update_order(order))
+    }
+
+    pub fn insert_profit(&self, profit: &mut Profit) -> RepositoryResult<i64> {
+        block_on(self.repository.insert_profit(profit))
+    }
+
+    pub fn insert_flow(&self, flow: &mut Flow) -> RepositoryResult<i64> {
+        block_on(self.repository.insert_flow(flow))
+    }
+}
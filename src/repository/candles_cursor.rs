@@ -0,0 +1,139 @@
+use super::{repository_error::RepositoryResult, Repository};
+use crate::{config::symbol_minutes::SymbolMinutes, model::candle::Candle};
+use async_std::task::block_on;
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+
+/// Candles read on a page
+pub const CANDLES_PAGE_SIZE: u32 = 10_000;
+
+/// Reads candles of symbol minutes opened between start and end time page by page, sorted by open time,
+/// so a range of years is never held at once.
+///
+/// Each page starts after last open time of previous one, candles inserted meanwhile before it aren't returned.
+pub struct CandlesCursor {
+    repository: Arc<dyn Repository + Send + Sync>,
+    symbol_minutes: SymbolMinutes,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    page_size: u32,
+    finished: bool,
+}
+
+impl CandlesCursor {
+    pub fn new(repository: Arc<dyn Repository + Send + Sync>, symbol_minutes: &SymbolMinutes, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> Self {
+        Self {
+            repository,
+            symbol_minutes: symbol_minutes.clone(),
+            start_time: *start_time,
+            end_time: *end_time,
+            page_size: CANDLES_PAGE_SIZE,
+            finished: false,
+        }
+    }
+
+    /// Next page of candles, none after last one
+    pub async fn next_page(&mut self) -> RepositoryResult<Option<Vec<Candle>>> {
+        if self.finished {
+            return Ok(None);
+        }
+        let page = self
+            .repository
+            .candles_page(&self.symbol_minutes, &self.start_time, &self.end_time, self.page_size)
+            .await?;
+        match page.last() {
+            // Open times are whole seconds
+            Some(last) if page.len() == self.page_size as usize => self.start_time = last.open_time + Duration::seconds(1),
+            Some(_) => self.finished = true,
+            None => {
+                self.finished = true;
+                return Ok(None);
+            }
+        }
+        Ok(Some(page))
+    }
+}
+
+/// Pages read blocking, for callers that aren't async (indicators, export)
+impl Iterator for CandlesCursor {
+    type Item = RepositoryResult<Vec<Candle>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        block_on(self.next_page()).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        candles_utils::{fdec, str_to_datetime},
+        model::{interval::Interval, market::Market},
+        repository::{memory_repository::MemoryRepository, tests::pg_repository},
+    };
+
+    /// Pages cover range once, the last one partial, and an exact multiple of page size ends with no empty page
+    async fn cursor_paged(repository: Arc<dyn Repository + Send + Sync>) {
+        let symbol_minutes = SymbolMinutes::new("CURUSDT", &Interval::M15, &Market::Spot);
+        repository.delete_candles(&symbol_minutes).await.unwrap();
+        let start = str_to_datetime("2020-11-11 00:00:00");
+        let mut candles = (0..10)
+            .map(|n| {
+                let open_time = start + Duration::minutes(15 * n);
+                Candle {
+                    open_time,
+                    close_time: open_time + Duration::seconds(899),
+                    market: Market::Spot.to_string(),
+                    ..Candle::new(
+                        0,
+                        "2020-11-11 00:00:00",
+                        "2020-11-11 00:14:59",
+                        "CURUSDT",
+                        15,
+                        fdec(1.0),
+                        fdec(1.0),
+                        fdec(1.0),
+                        fdec(1.0),
+                        fdec(1.0),
+                    )
+                }
+            })
+            .collect::<Vec<_>>();
+        repository.insert_candles(&mut candles).await.unwrap();
+
+        let end = str_to_datetime("2020-11-11 02:00:00");
+        let mut cursor = CandlesCursor {
+            page_size: 4,
+            ..CandlesCursor::new(repository.clone(), &symbol_minutes, &start, &end)
+        };
+        let mut pages = Vec::new();
+        while let Some(page) = cursor.next_page().await.unwrap() {
+            pages.push(page);
+        }
+        let sizes = CandlesCursor {
+            page_size: 4,
+            ..CandlesCursor::new(repository.clone(), &symbol_minutes, &start, &(start + Duration::minutes(105)))
+        }
+        .map(|page| page.unwrap().len())
+        .collect::<Vec<_>>();
+        repository.delete_candles(&symbol_minutes).await.unwrap();
+
+        assert_eq!(pages.iter().map(|p| p.len()).collect::<Vec<_>>(), vec![4, 4, 1]);
+        assert_eq!(
+            pages.concat().iter().map(|c| c.id).collect::<Vec<_>>(),
+            candles[..9].iter().map(|c| c.id).collect::<Vec<_>>()
+        );
+        assert_eq!(sizes, vec![4, 4]);
+    }
+
+    #[async_std::test]
+    async fn cursor_test() {
+        cursor_paged(Arc::new(MemoryRepository::new())).await;
+    }
+
+    #[async_std::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn pg_cursor_test() {
+        cursor_paged(Arc::new(pg_repository().await)).await;
+    }
+}
@@ -0,0 +1,595 @@
+use super::{
+    candle_key, insert_candles_error, offending_candles,
+    repository_error::{RepositoryError, RepositoryResult},
+    InsertStats, Repository, UpsertStats,
+};
+use crate::{
+    config::symbol_minutes::SymbolMinutes,
+    model::{
+        agg_trade::AggTrade,
+        candle::Candle,
+        exchange_name::ExchangeName,
+        funding_rate::FundingRate,
+        interval::Interval,
+        market::Market,
+        open_interest::OpenInterest,
+        resampled_candle::{check_resample, resample, resample_range, ResampledCandle},
+        symbol_info::SymbolInfo,
+    },
+    strategy::model::{flow::Flow, order::Order, position::Position, profit::Profit},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use std::{
+    collections::{btree_map::Entry, BTreeMap, HashMap},
+    sync::RwLock,
+    time::Instant,
+};
+
+#[derive(Default)]
+struct Tables {
+    /// Candles by id
+    candles: BTreeMap<Decimal, Candle>,
+    /// Last candle id generated
+    candle_id: Decimal,
+    /// Revision count and last revision time by candle id
+    candle_revisions: HashMap<Decimal, (i32, DateTime<Utc>)>,
+    /// Candles quarantined with their reason by id
+    quarantined_candles: BTreeMap<Decimal, (Candle, String)>,
+    /// Resampled candles saved by candle key
+    resampled_candles: BTreeMap<(String, String, Decimal, String, DateTime<Utc>), ResampledCandle>,
+    symbols_info: HashMap<(ExchangeName, String, Market), SymbolInfo>,
+    funding_rates: BTreeMap<(ExchangeName, String, DateTime<Utc>), FundingRate>,
+    open_interests: BTreeMap<(ExchangeName, String, Interval, DateTime<Utc>), OpenInterest>,
+    agg_trades: BTreeMap<(ExchangeName, String, Market, i64), AggTrade>,
+    positions: BTreeMap<i64, Position>,
+    orders: BTreeMap<i64, Order>,
+    profits: BTreeMap<i64, Profit>,
+    flows: BTreeMap<i64, Flow>,
+    /// Last id generated of positions, orders, profits and flows
+    trading_id: i64,
+}
+
+impl Tables {
+    fn next_trading_id(&mut self) -> i64 {
+        self.trading_id += 1;
+        self.trading_id
+    }
+
+    /// Orders and profits belong to a position with same simulation flag
+    fn check_position(&self, position: i64, simulation: bool) -> RepositoryResult<()> {
+        match self.positions.get(&position) {
+            Some(stored) if stored.simulation == simulation => Ok(()),
+            Some(_) => Err(RepositoryError::Constraint(format!("Position {} simulation flag isn't {}", position, simulation))),
+            None => Err(RepositoryError::Constraint(format!("Position {} not found", position))),
+        }
+    }
+}
+
+/// Removes entries of map not kept (`BTreeMap::retain` needs Rust 1.53)
+fn retain<K: Ord + Clone, V>(map: &mut BTreeMap<K, V>, mut keep: impl FnMut(&K, &V) -> bool) {
+    let removed = map.iter().filter(|(k, v)| !keep(k, v)).map(|(k, _)| k.clone()).collect::<Vec<_>>();
+    for key in removed.iter() {
+        map.remove(key);
+    }
+}
+
+/// Repository kept in memory, for tests and runs without database server; content is lost on exit
+#[derive(Default)]
+pub struct MemoryRepository {
+    tables: RwLock<Tables>,
+}
+
+impl MemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Candles of symbol minutes, any order
+    fn candles_of(&self, symbol_minutes: &SymbolMinutes) -> Vec<Candle> {
+        let minutes = Decimal::from(symbol_minutes.interval.minutes());
+        let market = symbol_minutes.market.as_str();
+        let exchange = symbol_minutes.exchange.as_str();
+        let tables = self.tables.read().unwrap();
+        tables
+            .candles
+            .values()
+            .filter(|c| c.symbol == symbol_minutes.symbol && c.minutes == minutes && c.market == market && c.exchange == exchange)
+            .cloned()
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Repository for MemoryRepository {
+    async fn last_close_time(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<Option<DateTime<Utc>>> {
+        Ok(self.candles_of(symbol_minutes).iter().map(|c| c.close_time).max())
+    }
+
+    async fn ranges_symbol_minutes(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<(Option<DateTime<Utc>>, Option<DateTime<Utc>>)> {
+        let candles = self.candles_of(symbol_minutes);
+        Ok((candles.iter().map(|c| c.close_time).min(), candles.iter().map(|c| c.close_time).max()))
+    }
+
+    async fn candle_by_id(&self, id: Decimal) -> RepositoryResult<Option<Candle>> {
+        Ok(self.tables.read().unwrap().candles.get(&id).cloned())
+    }
+
+    async fn symbols_minutes(&self) -> RepositoryResult<Vec<(SymbolMinutes, i64)>> {
+        let mut counts = HashMap::new();
+        for candle in self.tables.read().unwrap().candles.values() {
+            *counts
+                .entry((candle.exchange.clone(), candle.symbol.clone(), candle.minutes, candle.market.clone()))
+                .or_insert(0) += 1;
+        }
+        Ok(counts
+            .into_iter()
+            .filter_map(|((exchange, symbol, minutes, market), count)| {
+                let interval = Interval::from_minutes(minutes.to_u32()?).ok()?;
+                let symbol_minutes = SymbolMinutes {
+                    exchange: exchange.parse().ok()?,
+                    ..SymbolMinutes::new(&symbol, &interval, &market.parse().ok()?)
+                };
+                Some((symbol_minutes, count))
+            })
+            .collect())
+    }
+
+    async fn candles_by_time(&self, symbol_minutes: &SymbolMinutes, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> RepositoryResult<Vec<Candle>> {
+        let in_range = |time: &DateTime<Utc>| time >= start_time && time <= end_time;
+        let mut candles = self.candles_of(symbol_minutes);
+        candles.retain(|c| in_range(&c.open_time) || in_range(&c.close_time));
+        candles.sort_by_key(|c| c.open_time);
+        Ok(candles)
+    }
+
+    async fn candles_page(
+        &self, symbol_minutes: &SymbolMinutes, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>, limit: u32,
+    ) -> RepositoryResult<Vec<Candle>> {
+        let mut candles = self.candles_of(symbol_minutes);
+        candles.retain(|c| &c.open_time >= start_time && &c.open_time <= end_time);
+        candles.sort_by_key(|c| c.open_time);
+        candles.truncate(limit as usize);
+        Ok(candles)
+    }
+
+    async fn last_candles(&self, symbol_minutes: &SymbolMinutes, limit: &i64) -> RepositoryResult<Vec<Candle>> {
+        let mut candles = self.candles_of(symbol_minutes);
+        candles.sort_by_key(|c| std::cmp::Reverse(c.open_time));
+        candles.truncate(*limit as usize);
+        Ok(candles)
+    }
+
+    async fn insert_candle(&self, candle: &mut Candle) -> RepositoryResult<Decimal> {
+        self.insert_candles(std::slice::from_mut(candle)).await?;
+        Ok(candle.id)
+    }
+
+    async fn insert_candles(&self, candles: &mut [Candle]) -> RepositoryResult<InsertStats> {
+        let start = Instant::now();
+        let mut tables = self.tables.write().unwrap();
+        let stored = tables.candles.values().cloned().collect::<Vec<_>>();
+        let offending = offending_candles(candles, &stored);
+        if !offending.is_empty() {
+            return Err(insert_candles_error(&offending, RepositoryError::Constraint("duplicate open time".to_string())));
+        }
+        for candle in candles.iter_mut() {
+            tables.candle_id += Decimal::ONE;
+            candle.id = tables.candle_id;
+            tables.candles.insert(candle.id, candle.clone());
+        }
+        Ok(InsertStats::new(candles.len() as u64, &start))
+    }
+
+    async fn upsert_candles(&self, candles: &mut [Candle]) -> RepositoryResult<UpsertStats> {
+        let start = Instant::now();
+        let offending = offending_candles(candles, &[]);
+        if !offending.is_empty() {
+            return Err(insert_candles_error(&offending, RepositoryError::Constraint("duplicate open time".to_string())));
+        }
+        let mut tables = self.tables.write().unwrap();
+        let stored = tables.candles.values().map(|c| (candle_key(c), c.id)).collect::<HashMap<_, _>>();
+        let (mut inserted, mut revised) = (0, 0);
+        for candle in candles.iter_mut() {
+            match stored.get(&candle_key(candle)) {
+                Some(id) => {
+                    candle.id = *id;
+                    if tables.candles[id] != *candle {
+                        let revision = tables.candle_revisions.get(id).map(|r| r.0).unwrap_or_default();
+                        tables.candle_revisions.insert(*id, (revision + 1, Utc::now()));
+                        tables.candles.insert(*id, candle.clone());
+                        revised += 1;
+                    }
+                }
+                None => {
+                    tables.candle_id += Decimal::ONE;
+                    candle.id = tables.candle_id;
+                    tables.candles.insert(candle.id, candle.clone());
+                    inserted += 1;
+                }
+            }
+        }
+        Ok(UpsertStats::new(inserted, revised, candles.len() as u64 - inserted - revised, &start))
+    }
+
+    async fn candle_revision(&self, id: &Decimal) -> RepositoryResult<Option<(i32, DateTime<Utc>)>> {
+        Ok(self.tables.read().unwrap().candle_revisions.get(id).cloned())
+    }
+
+    async fn delete_all_candles(&self) -> RepositoryResult<()> {
+        let mut tables = self.tables.write().unwrap();
+        tables.candles.clear();
+        tables.candle_revisions.clear();
+        Ok(())
+    }
+
+    async fn delete_candles(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<()> {
+        let minutes = Decimal::from(symbol_minutes.interval.minutes());
+        let market = symbol_minutes.market.as_str();
+        let exchange = symbol_minutes.exchange.as_str();
+        let mut tables = self.tables.write().unwrap();
+        let tables = &mut *tables;
+        retain(&mut tables.candles, |_, c| {
+            !(c.symbol == symbol_minutes.symbol && c.minutes == minutes && c.market == market && c.exchange == exchange)
+        });
+        let candles = &tables.candles;
+        tables.candle_revisions.retain(|id, _| candles.contains_key(id));
+        Ok(())
+    }
+
+    async fn delete_candle(&self, id: &Decimal) -> RepositoryResult<()> {
+        let mut tables = self.tables.write().unwrap();
+        tables.candles.remove(id);
+        tables.candle_revisions.remove(id);
+        Ok(())
+    }
+
+    async fn delete_last_candle(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<()> {
+        match self.candles_of(symbol_minutes).iter().max_by_key(|c| c.close_time) {
+            Some(last) => self.delete_candle(&last.id).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn quarantine_candles(&self, candles: &[(Candle, String)]) -> RepositoryResult<u64> {
+        let mut tables = self.tables.write().unwrap();
+        let mut moved = 0;
+        for (candle, reason) in candles.iter() {
+            if let Some(stored) = tables.candles.remove(&candle.id) {
+                tables.candle_revisions.remove(&candle.id);
+                tables.quarantined_candles.insert(stored.id, (stored, reason.clone()));
+                moved += 1;
+            }
+        }
+        Ok(moved)
+    }
+
+    async fn quarantined_candles(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<Vec<(Candle, String)>> {
+        let minutes = Decimal::from(symbol_minutes.interval.minutes());
+        let tables = self.tables.read().unwrap();
+        let mut quarantined = tables
+            .quarantined_candles
+            .values()
+            .filter(|(c, _)| {
+                c.symbol == symbol_minutes.symbol
+                    && c.minutes == minutes
+                    && c.market == symbol_minutes.market.as_str()
+                    && c.exchange == symbol_minutes.exchange.as_str()
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        quarantined.sort_by_key(|(c, _)| c.open_time);
+        Ok(quarantined)
+    }
+
+    async fn delete_quarantined_candles(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<()> {
+        let minutes = Decimal::from(symbol_minutes.interval.minutes());
+        retain(&mut self.tables.write().unwrap().quarantined_candles, |_, (c, _)| {
+            !(c.symbol == symbol_minutes.symbol
+                && c.minutes == minutes
+                && c.market == symbol_minutes.market.as_str()
+                && c.exchange == symbol_minutes.exchange.as_str())
+        });
+        Ok(())
+    }
+
+    async fn resample_candles(
+        &self, symbol_minutes: &SymbolMinutes, interval: &Interval, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> RepositoryResult<Vec<ResampledCandle>> {
+        check_resample(interval, &symbol_minutes.interval).map_err(RepositoryError::Query)?;
+        let (start_time, end_time) = resample_range(interval, start_time, end_time);
+        let mut candles = self.candles_of(symbol_minutes);
+        candles.retain(|c| c.open_time >= start_time && c.open_time < end_time);
+        candles.sort_by_key(|c| c.open_time);
+        Ok(resample(&candles, interval, &symbol_minutes.interval))
+    }
+
+    async fn save_resampled_candles(&self, candles: &[ResampledCandle]) -> RepositoryResult<u64> {
+        let mut tables = self.tables.write().unwrap();
+        for resampled in candles.iter() {
+            tables.resampled_candles.insert(candle_key(&resampled.candle), resampled.clone());
+        }
+        Ok(candles.len() as u64)
+    }
+
+    async fn resampled_candles(
+        &self, symbol_minutes: &SymbolMinutes, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> RepositoryResult<Vec<ResampledCandle>> {
+        let minutes = Decimal::from(symbol_minutes.interval.minutes());
+        let (start_time, end_time) = resample_range(&symbol_minutes.interval, start_time, end_time);
+        let tables = self.tables.read().unwrap();
+        Ok(tables
+            .resampled_candles
+            .iter()
+            .filter(|((exchange, symbol, key_minutes, market, open_time), _)| {
+                exchange == symbol_minutes.exchange.as_str()
+                    && symbol == &symbol_minutes.symbol
+                    && key_minutes == &minutes
+                    && market == symbol_minutes.market.as_str()
+                    && open_time >= &start_time
+                    && open_time < &end_time
+            })
+            .map(|(_, resampled)| resampled.clone())
+            .collect())
+    }
+
+    async fn delete_resampled_candles(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<()> {
+        let minutes = Decimal::from(symbol_minutes.interval.minutes());
+        retain(
+            &mut self.tables.write().unwrap().resampled_candles,
+            |(exchange, symbol, key_minutes, market, _), _| {
+                !(exchange == symbol_minutes.exchange.as_str()
+                    && symbol == &symbol_minutes.symbol
+                    && key_minutes == &minutes
+                    && market == symbol_minutes.market.as_str())
+            },
+        );
+        Ok(())
+    }
+
+    async fn symbol_info(&self, exchange: &ExchangeName, symbol: &str, market: &Market) -> RepositoryResult<Option<SymbolInfo>> {
+        Ok(self.tables.read().unwrap().symbols_info.get(&(*exchange, symbol.to_string(), *market)).cloned())
+    }
+
+    async fn save_symbols_info(&self, symbols_info: &[SymbolInfo]) -> RepositoryResult<()> {
+        let mut tables = self.tables.write().unwrap();
+        for symbol_info in symbols_info.iter() {
+            tables
+                .symbols_info
+                .insert((symbol_info.exchange, symbol_info.symbol.clone(), symbol_info.market), symbol_info.clone());
+        }
+        Ok(())
+    }
+
+    async fn delete_symbol_info(&self, exchange: &ExchangeName, symbol: &str, market: &Market) -> RepositoryResult<()> {
+        self.tables.write().unwrap().symbols_info.remove(&(*exchange, symbol.to_string(), *market));
+        Ok(())
+    }
+
+    async fn last_funding_time(&self, exchange: &ExchangeName, symbol: &str) -> RepositoryResult<Option<DateTime<Utc>>> {
+        let tables = self.tables.read().unwrap();
+        Ok(tables
+            .funding_rates
+            .values()
+            .filter(|f| &f.exchange == exchange && f.symbol == symbol)
+            .map(|f| f.funding_time)
+            .max())
+    }
+
+    async fn funding_rates(
+        &self, exchange: &ExchangeName, symbol: &str, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> RepositoryResult<Vec<FundingRate>> {
+        let tables = self.tables.read().unwrap();
+        let range = (*exchange, symbol.to_string(), *start_time)..=(*exchange, symbol.to_string(), *end_time);
+        Ok(tables.funding_rates.range(range).map(|(_, f)| f.clone()).collect())
+    }
+
+    async fn insert_funding_rates(&self, funding_rates: &[FundingRate]) -> RepositoryResult<u64> {
+        let mut tables = self.tables.write().unwrap();
+        let mut inserted = 0;
+        for funding_rate in funding_rates.iter() {
+            let key = (funding_rate.exchange, funding_rate.symbol.clone(), funding_rate.funding_time);
+            if let Entry::Vacant(entry) = tables.funding_rates.entry(key) {
+                entry.insert(funding_rate.clone());
+                inserted += 1;
+            }
+        }
+        Ok(inserted)
+    }
+
+    async fn delete_funding_rates(&self, exchange: &ExchangeName, symbol: &str) -> RepositoryResult<()> {
+        retain(&mut self.tables.write().unwrap().funding_rates, |(e, s, _), _| !(e == exchange && s == symbol));
+        Ok(())
+    }
+
+    async fn last_open_interest_time(&self, exchange: &ExchangeName, symbol: &str, interval: &Interval) -> RepositoryResult<Option<DateTime<Utc>>> {
+        let tables = self.tables.read().unwrap();
+        Ok(tables
+            .open_interests
+            .keys()
+            .filter(|(e, s, i, _)| e == exchange && s == symbol && i == interval)
+            .map(|(_, _, _, time)| *time)
+            .max())
+    }
+
+    async fn open_interests(
+        &self, exchange: &ExchangeName, symbol: &str, interval: &Interval, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> RepositoryResult<Vec<OpenInterest>> {
+        let tables = self.tables.read().unwrap();
+        let range = (*exchange, symbol.to_string(), *interval, *start_time)..=(*exchange, symbol.to_string(), *interval, *end_time);
+        Ok(tables.open_interests.range(range).map(|(_, o)| o.clone()).collect())
+    }
+
+    async fn insert_open_interests(&self, open_interests: &[OpenInterest]) -> RepositoryResult<u64> {
+        let mut tables = self.tables.write().unwrap();
+        let mut inserted = 0;
+        for open_interest in open_interests.iter() {
+            let key = (open_interest.exchange, open_interest.symbol.clone(), open_interest.interval, open_interest.time);
+            if let Entry::Vacant(entry) = tables.open_interests.entry(key) {
+                entry.insert(open_interest.clone());
+                inserted += 1;
+            }
+        }
+        Ok(inserted)
+    }
+
+    async fn delete_open_interests(&self, exchange: &ExchangeName, symbol: &str, interval: &Interval) -> RepositoryResult<()> {
+        retain(&mut self.tables.write().unwrap().open_interests, |(e, s, i, _), _| {
+            !(e == exchange && s == symbol && i == interval)
+        });
+        Ok(())
+    }
+
+    async fn last_agg_trade(&self, exchange: &ExchangeName, symbol: &str, market: &Market) -> RepositoryResult<Option<(i64, DateTime<Utc>)>> {
+        let tables = self.tables.read().unwrap();
+        let range = (*exchange, symbol.to_string(), *market, i64::MIN)..=(*exchange, symbol.to_string(), *market, i64::MAX);
+        Ok(tables.agg_trades.range(range).next_back().map(|(_, t)| (t.id, t.time)))
+    }
+
+    async fn agg_trades(
+        &self, exchange: &ExchangeName, symbol: &str, market: &Market, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> RepositoryResult<Vec<AggTrade>> {
+        let tables = self.tables.read().unwrap();
+        let range = (*exchange, symbol.to_string(), *market, i64::MIN)..=(*exchange, symbol.to_string(), *market, i64::MAX);
+        Ok(tables
+            .agg_trades
+            .range(range)
+            .map(|(_, t)| t)
+            .filter(|t| t.time >= *start_time && t.time <= *end_time)
+            .cloned()
+            .collect())
+    }
+
+    async fn insert_agg_trades(&self, trades: &[AggTrade]) -> RepositoryResult<u64> {
+        let mut tables = self.tables.write().unwrap();
+        let mut inserted = 0;
+        for trade in trades.iter() {
+            let key = (trade.exchange, trade.symbol.clone(), trade.market, trade.id);
+            if let Entry::Vacant(entry) = tables.agg_trades.entry(key) {
+                entry.insert(trade.clone());
+                inserted += 1;
+            }
+        }
+        Ok(inserted)
+    }
+
+    async fn delete_agg_trades(&self, exchange: &ExchangeName, symbol: &str, market: &Market) -> RepositoryResult<()> {
+        retain(&mut self.tables.write().unwrap().agg_trades, |(e, s, m, _), _| {
+            !(e == exchange && s == symbol && m == market)
+        });
+        Ok(())
+    }
+
+    async fn insert_position(&self, position: &mut Position) -> RepositoryResult<i64> {
+        let mut tables = self.tables.write().unwrap();
+        position.id = tables.next_trading_id();
+        tables.positions.insert(position.id, position.clone());
+        Ok(position.id)
+    }
+
+    async fn update_position(&self, position: &Position) -> RepositoryResult<()> {
+        let mut tables = self.tables.write().unwrap();
+        match tables.positions.get_mut(&position.id) {
+            Some(stored) => *stored = position.clone(),
+            None => return Err(RepositoryError::NotFound(format!("Position {} not found", position.id))),
+        }
+        Ok(())
+    }
+
+    async fn positions(&self, symbol: &str, simulation: bool) -> RepositoryResult<Vec<Position>> {
+        let tables = self.tables.read().unwrap();
+        Ok(tables
+            .positions
+            .values()
+            .filter(|p| p.symbol == symbol && p.simulation == simulation)
+            .cloned()
+            .collect())
+    }
+
+    async fn delete_position(&self, id: i64) -> RepositoryResult<()> {
+        let mut tables = self.tables.write().unwrap();
+        tables.positions.remove(&id);
+        retain(&mut tables.orders, |_, o| o.position != id);
+        retain(&mut tables.profits, |_, p| p.position != id);
+        retain(&mut tables.flows, |_, f| f.position != id);
+        Ok(())
+    }
+
+    async fn insert_order(&self, order: &mut Order) -> RepositoryResult<i64> {
+        let mut tables = self.tables.write().unwrap();
+        tables.check_position(order.position, order.simulation)?;
+        order.id = tables.next_trading_id();
+        tables.orders.insert(order.id, order.clone());
+        Ok(order.id)
+    }
+
+    async fn update_order(&self, order: &Order) -> RepositoryResult<()> {
+        let mut tables = self.tables.write().unwrap();
+        match tables.orders.get_mut(&order.id) {
+            Some(stored) if stored.simulation == order.simulation => *stored = order.clone(),
+            _ => return Err(RepositoryError::NotFound(format!("Order {} not found", order.id))),
+        }
+        Ok(())
+    }
+
+    async fn orders(&self, position: i64) -> RepositoryResult<Vec<Order>> {
+        let mut orders = self
+            .tables
+            .read()
+            .unwrap()
+            .orders
+            .values()
+            .filter(|o| o.position == position)
+            .cloned()
+            .collect::<Vec<_>>();
+        orders.sort_by_key(|o| (o.date, o.id));
+        Ok(orders)
+    }
+
+    async fn insert_profit(&self, profit: &mut Profit) -> RepositoryResult<i64> {
+        let mut tables = self.tables.write().unwrap();
+        tables.check_position(profit.position, profit.simulation)?;
+        profit.id = tables.next_trading_id();
+        tables.profits.insert(profit.id, profit.clone());
+        Ok(profit.id)
+    }
+
+    async fn profits(&self, position: i64) -> RepositoryResult<Vec<Profit>> {
+        let mut profits = self
+            .tables
+            .read()
+            .unwrap()
+            .profits
+            .values()
+            .filter(|p| p.position == position)
+            .cloned()
+            .collect::<Vec<_>>();
+        profits.sort_by_key(|p| (p.sold_date, p.id));
+        Ok(profits)
+    }
+
+    async fn insert_flow(&self, flow: &mut Flow) -> RepositoryResult<i64> {
+        let mut tables = self.tables.write().unwrap();
+        if !tables.positions.contains_key(&flow.position) {
+            return Err(RepositoryError::Constraint(format!("Position {} not found", flow.position)));
+        }
+        flow.id = tables.next_trading_id();
+        tables.flows.insert(flow.id, flow.clone());
+        Ok(flow.id)
+    }
+
+    async fn flows(&self, position: i64) -> RepositoryResult<Vec<Flow>> {
+        let mut flows = self
+            .tables
+            .read()
+            .unwrap()
+            .flows
+            .values()
+            .filter(|f| f.position == position)
+            .cloned()
+            .collect::<Vec<_>>();
+        flows.sort_by_key(|f| (f.date, f.id));
+        Ok(flows)
+    }
+}
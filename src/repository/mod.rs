@@ -0,0 +1,733 @@
+pub mod blocking_repository;
+pub mod candles_cursor;
+pub mod memory_repository;
+pub mod pg_repository;
+pub mod repository_error;
+
+use crate::{
+    config::symbol_minutes::SymbolMinutes,
+    model::{
+        agg_trade::AggTrade, candle::Candle, exchange_name::ExchangeName, funding_rate::FundingRate, interval::Interval, market::Market,
+        open_interest::OpenInterest, resampled_candle::ResampledCandle, symbol_info::SymbolInfo,
+    },
+    strategy::model::{flow::Flow, order::Order, position::Position, profit::Profit},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use ifmt::{iformat, iwrite};
+use log::info;
+use repository_error::{RepositoryError, RepositoryResult};
+use rust_decimal::Decimal;
+use std::{collections::HashSet, fmt::Display, time::Instant};
+
+/// Offending candles named on insert errors
+const OFFENDING_CANDLES_SHOWN: usize = 5;
+
+/// Rows inserted and time spent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InsertStats {
+    pub rows: u64,
+    pub elapsed: std::time::Duration,
+}
+
+impl InsertStats {
+    pub fn new(rows: u64, start: &Instant) -> Self {
+        Self {
+            rows,
+            elapsed: start.elapsed(),
+        }
+    }
+
+    pub fn rows_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            return self.rows as f64;
+        }
+        self.rows as f64 / seconds
+    }
+}
+
+impl Display for InsertStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        iwrite!(f, "{self.rows} rows in {self.elapsed:?} ({self.rows_per_second():.0} rows/s)")
+    }
+}
+
+/// Candles inserted, revised (stored with different values) and unchanged, and time spent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UpsertStats {
+    pub inserted: u64,
+    pub revised: u64,
+    pub unchanged: u64,
+    pub elapsed: std::time::Duration,
+}
+
+impl UpsertStats {
+    pub fn new(inserted: u64, revised: u64, unchanged: u64, start: &Instant) -> Self {
+        Self {
+            inserted,
+            revised,
+            unchanged,
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+impl Display for UpsertStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rows = self.inserted + self.revised + self.unchanged;
+        let stats = InsertStats { rows, elapsed: self.elapsed };
+        iwrite!(f, "{self.inserted} inserted, {self.revised} revised, {self.unchanged} unchanged, {stats}")
+    }
+}
+
+/// Unique key of candle: exchange, symbol, minutes, market and open time
+pub fn candle_key(candle: &Candle) -> (String, String, Decimal, String, DateTime<Utc>) {
+    (
+        candle.exchange.clone(),
+        candle.symbol.clone(),
+        candle.minutes,
+        candle.market.clone(),
+        candle.open_time,
+    )
+}
+
+/// Candles repeating an open time of symbol minutes, among themselves or with stored candles
+pub fn offending_candles<'a>(candles: &'a [Candle], stored: &[Candle]) -> Vec<&'a Candle> {
+    let mut keys = stored.iter().map(candle_key).collect::<HashSet<_>>();
+    candles.iter().filter(|c| !keys.insert(candle_key(c))).collect()
+}
+
+/// Insert error naming the first offending candles, a constraint violation when there are some
+pub fn insert_candles_error(offending: &[&Candle], cause: RepositoryError) -> RepositoryError {
+    let shown = offending
+        .iter()
+        .take(OFFENDING_CANDLES_SHOWN)
+        .map(|c| iformat!("{c} (id {c.id})"))
+        .collect::<Vec<_>>();
+    if shown.is_empty() {
+        return cause.context("Candles insert rolled back");
+    }
+    RepositoryError::Constraint(format!(
+        "Candles insert rolled back, {} offending candles: {}: {}",
+        offending.len(),
+        shown.join(", "),
+        cause.message()
+    ))
+}
+
+/// Storage of candles and market data, implemented by Postgres and by an in-memory store (no database server).
+///
+/// Nothing stored for a query is an empty result, errors are kept for failures (database down, constraint violated...).
+#[async_trait]
+pub trait Repository: Send + Sync {
+    async fn last_close_time(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<Option<DateTime<Utc>>>;
+
+    /// Min and max close time of candles stored
+    async fn ranges_symbol_minutes(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<(Option<DateTime<Utc>>, Option<DateTime<Utc>>)>;
+
+    async fn candle_by_id(&self, id: Decimal) -> RepositoryResult<Option<Candle>>;
+
+    /// Symbols minutes stored with candles count
+    async fn symbols_minutes(&self) -> RepositoryResult<Vec<(SymbolMinutes, i64)>>;
+
+    /// Candles opened or closed between start and end time, sorted by open time
+    async fn candles_by_time(&self, symbol_minutes: &SymbolMinutes, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> RepositoryResult<Vec<Candle>>;
+
+    /// First `limit` candles opened between start and end time, sorted by open time.
+    ///
+    /// Keyset page: next page starts after last open time returned, without reading skipped rows again (see `CandlesCursor`).
+    async fn candles_page(
+        &self, symbol_minutes: &SymbolMinutes, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>, limit: u32,
+    ) -> RepositoryResult<Vec<Candle>>;
+
+    /// Last `limit` candles, newest first
+    async fn last_candles(&self, symbol_minutes: &SymbolMinutes, limit: &i64) -> RepositoryResult<Vec<Candle>>;
+
+    /// Inserts candle, fails when open time of symbol minutes is already stored.
+    ///
+    /// Id is generated by repository, it is set on candle and returned.
+    async fn insert_candle(&self, candle: &mut Candle) -> RepositoryResult<Decimal>;
+
+    /// Inserts candles, all or none, ids generated by repository are set on candles.
+    ///
+    /// On failure the error names the candles repeating an open time of symbol minutes.
+    async fn insert_candles(&self, candles: &mut [Candle]) -> RepositoryResult<InsertStats>;
+
+    /// Inserts new candles and updates stored ones the exchange revised, all or none, ids are set on candles.
+    ///
+    /// A revised candle keeps its id, its revision count is incremented and revision time recorded.
+    /// Candles repeating an open time of symbol minutes among themselves are refused.
+    async fn upsert_candles(&self, candles: &mut [Candle]) -> RepositoryResult<UpsertStats>;
+
+    /// Times candle was revised and last revision time, none when never revised
+    async fn candle_revision(&self, id: &Decimal) -> RepositoryResult<Option<(i32, DateTime<Utc>)>>;
+
+    async fn delete_all_candles(&self) -> RepositoryResult<()>;
+
+    async fn delete_candles(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<()>;
+
+    async fn delete_candle(&self, id: &Decimal) -> RepositoryResult<()>;
+
+    async fn delete_last_candle(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<()>;
+
+    /// Moves candles out of stored candles into quarantine with the reason of each, all or none, returns count moved.
+    ///
+    /// Candles are found by id, the ones not stored (anymore) are skipped.
+    async fn quarantine_candles(&self, candles: &[(Candle, String)]) -> RepositoryResult<u64>;
+
+    /// Candles quarantined of symbol minutes with their reason, sorted by open time
+    async fn quarantined_candles(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<Vec<(Candle, String)>>;
+
+    async fn delete_quarantined_candles(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<()>;
+
+    /// Candles of interval aggregated from stored candles of symbol minutes, buckets opened between start and end time sorted by open time.
+    ///
+    /// Open is first open, high is max high, low is min low, close is last close and volume is summed.
+    /// A bucket missing stored candles isn't merged as if it were whole, it is flagged incomplete.
+    /// Interval must be a multiple of symbol minutes interval (see `check_resample`), otherwise query fails.
+    async fn resample_candles(
+        &self, symbol_minutes: &SymbolMinutes, interval: &Interval, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> RepositoryResult<Vec<ResampledCandle>>;
+
+    /// Inserts resampled candles or updates the ones stored (e.g. completed since), all or none, returns count saved
+    async fn save_resampled_candles(&self, candles: &[ResampledCandle]) -> RepositoryResult<u64>;
+
+    /// Resampled candles saved with interval of symbol minutes, buckets opened between start and end time sorted by open time
+    async fn resampled_candles(
+        &self, symbol_minutes: &SymbolMinutes, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> RepositoryResult<Vec<ResampledCandle>>;
+
+    async fn delete_resampled_candles(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<()>;
+
+    async fn symbol_info(&self, exchange: &ExchangeName, symbol: &str, market: &Market) -> RepositoryResult<Option<SymbolInfo>>;
+
+    /// Inserts or updates symbols info, all or none
+    async fn save_symbols_info(&self, symbols_info: &[SymbolInfo]) -> RepositoryResult<()>;
+
+    async fn delete_symbol_info(&self, exchange: &ExchangeName, symbol: &str, market: &Market) -> RepositoryResult<()>;
+
+    async fn last_funding_time(&self, exchange: &ExchangeName, symbol: &str) -> RepositoryResult<Option<DateTime<Utc>>>;
+
+    async fn funding_rates(
+        &self, exchange: &ExchangeName, symbol: &str, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> RepositoryResult<Vec<FundingRate>>;
+
+    /// Inserts funding rates not stored yet, all or none, returns count inserted
+    async fn insert_funding_rates(&self, funding_rates: &[FundingRate]) -> RepositoryResult<u64>;
+
+    async fn delete_funding_rates(&self, exchange: &ExchangeName, symbol: &str) -> RepositoryResult<()>;
+
+    async fn last_open_interest_time(&self, exchange: &ExchangeName, symbol: &str, interval: &Interval) -> RepositoryResult<Option<DateTime<Utc>>>;
+
+    async fn open_interests(
+        &self, exchange: &ExchangeName, symbol: &str, interval: &Interval, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> RepositoryResult<Vec<OpenInterest>>;
+
+    /// Inserts open interests not stored yet, all or none, returns count inserted
+    async fn insert_open_interests(&self, open_interests: &[OpenInterest]) -> RepositoryResult<u64>;
+
+    async fn delete_open_interests(&self, exchange: &ExchangeName, symbol: &str, interval: &Interval) -> RepositoryResult<()>;
+
+    /// Id and time of last aggregate trade stored
+    async fn last_agg_trade(&self, exchange: &ExchangeName, symbol: &str, market: &Market) -> RepositoryResult<Option<(i64, DateTime<Utc>)>>;
+
+    /// Aggregate trades between start and end time, sorted by id
+    async fn agg_trades(
+        &self, exchange: &ExchangeName, symbol: &str, market: &Market, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> RepositoryResult<Vec<AggTrade>>;
+
+    /// Inserts aggregate trades not stored yet, returns count inserted
+    async fn insert_agg_trades(&self, trades: &[AggTrade]) -> RepositoryResult<u64>;
+
+    async fn delete_agg_trades(&self, exchange: &ExchangeName, symbol: &str, market: &Market) -> RepositoryResult<()>;
+
+    /// Inserts position, id generated by repository is set on position and returned
+    async fn insert_position(&self, position: &mut Position) -> RepositoryResult<i64>;
+
+    /// Updates position balances and state, not found when position doesn't exist
+    async fn update_position(&self, position: &Position) -> RepositoryResult<()>;
+
+    /// Positions of symbol, only simulated or only live ones, sorted by id
+    async fn positions(&self, symbol: &str, simulation: bool) -> RepositoryResult<Vec<Position>>;
+
+    /// Deletes position with its orders, profits and flows
+    async fn delete_position(&self, id: i64) -> RepositoryResult<()>;
+
+    /// Inserts order, it fails when position doesn't exist or has another simulation flag
+    async fn insert_order(&self, order: &mut Order) -> RepositoryResult<i64>;
+
+    /// Updates order fill state and profit, not found when order with same simulation flag doesn't exist
+    async fn update_order(&self, order: &Order) -> RepositoryResult<()>;
+
+    /// Orders of position, sorted by date
+    async fn orders(&self, position: i64) -> RepositoryResult<Vec<Order>>;
+
+    /// Inserts profit, it fails when position doesn't exist or has another simulation flag
+    async fn insert_profit(&self, profit: &mut Profit) -> RepositoryResult<i64>;
+
+    /// Profits of position, sorted by sold date
+    async fn profits(&self, position: i64) -> RepositoryResult<Vec<Profit>>;
+
+    async fn insert_flow(&self, flow: &mut Flow) -> RepositoryResult<i64>;
+
+    /// Flows of position, sorted by date
+    async fn flows(&self, position: i64) -> RepositoryResult<Vec<Flow>>;
+
+    /// Candles of last 14 days
+    async fn candles_default(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<Vec<Candle>> {
+        let start = Instant::now();
+        let end_time = Utc::now();
+        let start_time = end_time - Duration::days(14);
+        let result = self.candles_by_time(symbol_minutes, &start_time, &end_time).await?;
+        info!("{}", iformat!("Read repository: {start.elapsed():?}"));
+        Ok(result)
+    }
+
+    async fn list_candles(&self, symbol_minutes: &SymbolMinutes, limit: &i64) -> RepositoryResult<()> {
+        let candles = self.last_candles(symbol_minutes, limit).await?;
+        info!("{}", iformat!("Listing candles limit {limit}:"));
+        for candle in candles.iter() {
+            info!("{}", iformat!("{candle}"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{memory_repository::MemoryRepository, pg_repository::PgRepository, *};
+    use crate::{
+        candles_utils::{fdec, inconsistent_candles, str_to_datetime},
+        model::symbol_info::tests::symbol_info,
+    };
+    use async_std::task;
+    use ifmt::iprintln;
+    use std::{env, sync::Arc};
+
+    pub async fn pg_repository() -> PgRepository {
+        dotenv::dotenv().ok();
+        PgRepository::new(&env::var("DATABASE_URL").unwrap()).await.unwrap()
+    }
+
+    /// Candles of same symbol on two markets are apart, symbols longer than 8 characters fit
+    async fn candles_market(repo: &dyn Repository) {
+        let start_time = str_to_datetime("2020-11-11 10:00:00");
+        let futures = SymbolMinutes::new("1000MKTUSDT", &Interval::M15, &Market::Futures);
+        let spot = SymbolMinutes::new("1000MKTUSDT", &Interval::M15, &Market::Spot);
+
+        let mut candles = vec![
+            Candle::new(
+                0,
+                "2020-11-11 10:00:00",
+                "2020-11-11 10:14:59",
+                "1000MKTUSDT",
+                15,
+                fdec(10.0),
+                fdec(10.0),
+                fdec(10.0),
+                fdec(10.0),
+                fdec(1.0),
+            ),
+            Candle {
+                market: Market::Spot.to_string(),
+                ..Candle::new(
+                    0,
+                    "2020-11-11 10:00:00",
+                    "2020-11-11 10:14:59",
+                    "1000MKTUSDT",
+                    15,
+                    fdec(9.0),
+                    fdec(9.0),
+                    fdec(9.0),
+                    fdec(9.0),
+                    fdec(1.0),
+                )
+            },
+        ];
+        repo.insert_candles(&mut candles).await.unwrap();
+
+        let candles_futures = repo.candles_by_time(&futures, &start_time, &start_time).await.unwrap();
+        let candles_spot = repo.candles_by_time(&spot, &start_time, &start_time).await.unwrap();
+        let symbols_minutes = repo.symbols_minutes().await.unwrap();
+        let last_close_time = repo.last_close_time(&spot).await.unwrap();
+        let range = repo.ranges_symbol_minutes(&futures).await.unwrap();
+        // Same open time of symbol minutes is refused
+        let duplicate = repo.insert_candles(&mut candles[..1].to_vec()).await;
+        for candle in candles.iter() {
+            repo.delete_candle(&candle.id).await.unwrap();
+        }
+
+        assert_eq!(candles_futures.len(), 1);
+        assert_eq!(candles_futures[0].close, fdec(10.0));
+        assert_eq!(candles_spot.len(), 1);
+        assert_eq!(candles_spot[0].close, fdec(9.0));
+        assert!(symbols_minutes.contains(&(spot.clone(), 1)));
+        assert_eq!(last_close_time, Some(str_to_datetime("2020-11-11 10:14:59")));
+        assert_eq!(range, (last_close_time, last_close_time));
+        assert!(matches!(duplicate, Err(RepositoryError::Constraint(_))));
+        assert!(repo.candles_by_time(&spot, &start_time, &start_time).await.unwrap().is_empty());
+        assert_eq!(repo.last_close_time(&spot).await, Ok(None));
+    }
+
+    async fn symbol_info_saved(repo: &dyn Repository) {
+        let mut symbol_info = symbol_info("MKTUSDT", &Market::Spot);
+        repo.save_symbols_info(&[symbol_info.clone()]).await.unwrap();
+        symbol_info.min_notional = fdec(5.0);
+        repo.save_symbols_info(&[symbol_info.clone()]).await.unwrap();
+
+        let saved = repo.symbol_info(&ExchangeName::Binance, "MKTUSDT", &Market::Spot).await.unwrap();
+        let futures = repo.symbol_info(&ExchangeName::Binance, "MKTUSDT", &Market::Futures).await.unwrap();
+        repo.delete_symbol_info(&ExchangeName::Binance, "MKTUSDT", &Market::Spot).await.unwrap();
+
+        let saved = saved.unwrap();
+        assert_eq!(saved.exchange, ExchangeName::Binance);
+        assert_eq!(saved.min_notional, fdec(5.0));
+        assert_eq!(saved.tick_size, symbol_info.tick_size);
+        assert_eq!(saved.updated_at.timestamp(), symbol_info.updated_at.timestamp());
+        assert!(futures.is_none());
+    }
+
+    /// Batch with a candle already stored is rolled back and names it
+    async fn insert_candles_rollback(repo: &dyn Repository) {
+        let symbol_minutes = SymbolMinutes::new("RBKUSDT", &Interval::M15, &Market::Spot);
+        let candle = |open_time: &str, close_time: &str| Candle {
+            market: Market::Spot.to_string(),
+            ..Candle::new(0, open_time, close_time, "RBKUSDT", 15, fdec(10.0), fdec(10.0), fdec(10.0), fdec(10.0), fdec(1.0))
+        };
+        repo.delete_candles(&symbol_minutes).await.unwrap();
+        let stats = repo.insert_candles(&mut [candle("2020-11-11 10:15:00", "2020-11-11 10:29:59")]).await.unwrap();
+
+        let mut batch = [
+            candle("2020-11-11 10:00:00", "2020-11-11 10:14:59"),
+            candle("2020-11-11 10:15:00", "2020-11-11 10:29:59"),
+            candle("2020-11-11 10:30:00", "2020-11-11 10:44:59"),
+        ];
+        let error = repo.insert_candles(&mut batch).await.unwrap_err().to_string();
+        let stored = repo
+            .candles_by_time(&symbol_minutes, &str_to_datetime("2020-11-11 00:00:00"), &str_to_datetime("2020-11-12 00:00:00"))
+            .await;
+        repo.delete_candles(&symbol_minutes).await.unwrap();
+
+        assert_eq!(stats.rows, 1);
+        assert!(error.contains("1 offending candles: RBKUSDT [15 2020-11-11 10:15:00 UTC"), "{}", error);
+        assert_eq!(stored.unwrap().len(), 1);
+    }
+
+    /// Overlapping batches insert new candles, update revised ones keeping ids and skip unchanged ones
+    async fn upsert_candles_revised(repo: &dyn Repository) {
+        let symbol_minutes = SymbolMinutes::new("UPSUSDT", &Interval::M15, &Market::Spot);
+        let candle = |open_time: &str, close_time: &str, close: f64| Candle {
+            market: Market::Spot.to_string(),
+            ..Candle::new(0, open_time, close_time, "UPSUSDT", 15, fdec(10.0), fdec(12.0), fdec(9.0), fdec(close), fdec(1.0))
+        };
+        repo.delete_candles(&symbol_minutes).await.unwrap();
+        let mut first = [
+            candle("2020-11-11 10:00:00", "2020-11-11 10:14:59", 10.0),
+            candle("2020-11-11 10:15:00", "2020-11-11 10:29:59", 10.0),
+        ];
+        let first_stats = repo.upsert_candles(&mut first).await.unwrap();
+
+        let mut second = [
+            candle("2020-11-11 10:00:00", "2020-11-11 10:14:59", 10.0),
+            candle("2020-11-11 10:15:00", "2020-11-11 10:29:59", 11.0),
+            candle("2020-11-11 10:30:00", "2020-11-11 10:44:59", 10.0),
+        ];
+        let second_stats = repo.upsert_candles(&mut second).await.unwrap();
+        let duplicate = repo.upsert_candles(&mut [second[2].clone(), second[2].clone()]).await;
+
+        let stored = repo
+            .candles_by_time(&symbol_minutes, &str_to_datetime("2020-11-11 00:00:00"), &str_to_datetime("2020-11-12 00:00:00"))
+            .await;
+        let revision = repo.candle_revision(&first[1].id).await.unwrap();
+        let unchanged = repo.candle_revision(&first[0].id).await.unwrap();
+        repo.delete_candles(&symbol_minutes).await.unwrap();
+
+        assert_eq!((first_stats.inserted, first_stats.revised, first_stats.unchanged), (2, 0, 0));
+        assert_eq!((second_stats.inserted, second_stats.revised, second_stats.unchanged), (1, 1, 1));
+        assert_eq!(
+            second.iter().take(2).map(|c| c.id).collect::<Vec<_>>(),
+            first.iter().map(|c| c.id).collect::<Vec<_>>()
+        );
+        assert!(duplicate.is_err());
+        let stored = stored.unwrap();
+        assert_eq!(stored.len(), 3);
+        assert_eq!(stored[1].close, fdec(11.0));
+        assert_eq!(revision.map(|r| r.0), Some(1));
+        assert_eq!(unchanged, None);
+    }
+
+    /// Hour with a missing quarter is aggregated but flagged incomplete, materialized rows are read back
+    async fn resampled_candles_flagged(repo: &dyn Repository) {
+        let symbol_minutes = SymbolMinutes::new("RSPUSDT", &Interval::M15, &Market::Spot);
+        let hours = SymbolMinutes::new("RSPUSDT", &Interval::H1, &Market::Spot);
+        let candle = |open_time: &str, close_time: &str, high: f64, low: f64| Candle {
+            market: Market::Spot.to_string(),
+            ..Candle::new(
+                0,
+                open_time,
+                close_time,
+                "RSPUSDT",
+                15,
+                fdec(low + 1.0),
+                fdec(high),
+                fdec(low),
+                fdec(high - 1.0),
+                fdec(2.0),
+            )
+        };
+        repo.delete_candles(&symbol_minutes).await.unwrap();
+        repo.delete_resampled_candles(&hours).await.unwrap();
+        let mut candles = [
+            candle("2020-11-11 10:00:00", "2020-11-11 10:14:59", 12.0, 8.0),
+            candle("2020-11-11 10:15:00", "2020-11-11 10:29:59", 14.0, 9.0),
+            candle("2020-11-11 10:45:00", "2020-11-11 10:59:59", 11.0, 7.0),
+            candle("2020-11-11 11:00:00", "2020-11-11 11:14:59", 10.0, 6.0),
+            candle("2020-11-11 11:15:00", "2020-11-11 11:29:59", 10.0, 6.0),
+            candle("2020-11-11 11:30:00", "2020-11-11 11:44:59", 10.0, 6.0),
+            candle("2020-11-11 11:45:00", "2020-11-11 11:59:59", 13.0, 5.0),
+        ];
+        repo.insert_candles(&mut candles).await.unwrap();
+
+        let start_time = str_to_datetime("2020-11-11 10:20:00");
+        let end_time = str_to_datetime("2020-11-11 11:00:00");
+        let resampled = repo.resample_candles(&symbol_minutes, &Interval::H1, &start_time, &end_time).await.unwrap();
+        let saved = repo.save_resampled_candles(&resampled).await.unwrap();
+        let stored = repo.resampled_candles(&hours, &start_time, &end_time).await.unwrap();
+        let invalid = repo.resample_candles(&hours, &Interval::M15, &start_time, &end_time).await;
+        repo.delete_candles(&symbol_minutes).await.unwrap();
+        repo.delete_resampled_candles(&hours).await.unwrap();
+
+        assert_eq!(
+            resampled.iter().map(|r| (r.sources, r.expected, r.is_complete())).collect::<Vec<_>>(),
+            vec![(3, 4, false), (4, 4, true)]
+        );
+        let first = &resampled[0].candle;
+        assert_eq!(
+            (first.open_time, first.close_time),
+            (str_to_datetime("2020-11-11 10:00:00"), str_to_datetime("2020-11-11 10:59:59"))
+        );
+        assert_eq!(
+            (first.open, first.high, first.low, first.close, first.volume),
+            (fdec(9.0), fdec(14.0), fdec(7.0), fdec(10.0), fdec(6.0))
+        );
+        assert_eq!(
+            (first.symbol.as_str(), first.minutes, first.market.as_str()),
+            ("RSPUSDT", Decimal::from(60), "spot")
+        );
+        assert_eq!(resampled[1].candle.low, fdec(5.0));
+        assert_eq!(saved, 2);
+        assert_eq!(stored, resampled);
+        assert!(matches!(invalid, Err(RepositoryError::Query(_))));
+        assert!(repo.resampled_candles(&hours, &start_time, &end_time).await.unwrap().is_empty());
+    }
+
+    /// Invalid candles leave stored candles for quarantine, moving them again does nothing
+    async fn quarantine_moved(repo: &dyn Repository) {
+        let symbol_minutes = SymbolMinutes::new("QRTUSDT", &Interval::M15, &Market::Spot);
+        let candle = |open_time: &str, close_time: &str, low: f64| Candle {
+            market: Market::Spot.to_string(),
+            ..Candle::new(0, open_time, close_time, "QRTUSDT", 15, fdec(10.0), fdec(12.0), fdec(low), fdec(11.0), fdec(1.0))
+        };
+        repo.delete_candles(&symbol_minutes).await.unwrap();
+        repo.delete_quarantined_candles(&symbol_minutes).await.unwrap();
+        let mut candles = [
+            candle("2020-11-11 10:00:00", "2020-11-11 10:14:59", 9.0),
+            candle("2020-11-11 10:15:00", "2020-11-11 10:29:59", 10.5),
+            candle("2020-11-11 10:30:00", "2020-11-11 10:44:59", 9.0),
+        ];
+        repo.insert_candles(&mut candles).await.unwrap();
+
+        let bad = vec![(candles[1].clone(), "ohlc_bounds: low 10.5".to_string())];
+        let moved = repo.quarantine_candles(&bad).await.unwrap();
+        let again = repo.quarantine_candles(&bad).await.unwrap();
+        let stored = repo
+            .candles_by_time(&symbol_minutes, &candles[0].open_time, &candles[2].open_time)
+            .await
+            .unwrap();
+        let quarantined = repo.quarantined_candles(&symbol_minutes).await.unwrap();
+        repo.delete_candles(&symbol_minutes).await.unwrap();
+        repo.delete_quarantined_candles(&symbol_minutes).await.unwrap();
+
+        assert_eq!((moved, again), (1, 0));
+        assert_eq!(stored.iter().map(|c| c.id).collect::<Vec<_>>(), vec![candles[0].id, candles[2].id]);
+        assert_eq!(quarantined, bad);
+        assert!(repo.quarantined_candles(&symbol_minutes).await.unwrap().is_empty());
+    }
+
+    /// Syncs of several symbols at the same time get distinct ids
+    async fn concurrent_inserts(repo: Arc<dyn Repository + Send + Sync>) {
+        let handles = (0..4)
+            .map(|i| {
+                let repo = repo.clone();
+                task::spawn(async move {
+                    let symbol_minutes = SymbolMinutes::new(&format!("CC{}USDT", i), &Interval::M15, &Market::Futures);
+                    repo.delete_candles(&symbol_minutes).await.unwrap();
+                    let start = str_to_datetime("2020-11-11 00:00:00");
+                    let mut candles = (0..50)
+                        .map(|n| {
+                            let open_time = start + Duration::minutes(15 * n);
+                            let close_time = open_time + Duration::seconds(899);
+                            Candle {
+                                open_time,
+                                close_time,
+                                ..Candle::new(
+                                    0,
+                                    "2020-11-11 00:00:00",
+                                    "2020-11-11 00:14:59",
+                                    &symbol_minutes.symbol,
+                                    15,
+                                    fdec(1.0),
+                                    fdec(1.0),
+                                    fdec(1.0),
+                                    fdec(1.0),
+                                    fdec(1.0),
+                                )
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    let mut one = candles.pop().unwrap();
+                    repo.insert_candles(&mut candles).await.unwrap();
+                    repo.insert_candle(&mut one).await.unwrap();
+                    candles.push(one);
+                    repo.delete_candles(&symbol_minutes).await.unwrap();
+                    candles.into_iter().map(|c| c.id).collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut ids = HashSet::new();
+        for handle in handles {
+            ids.extend(handle.await);
+        }
+        assert_eq!(ids.len(), 200);
+        assert!(!ids.contains(&Decimal::ZERO));
+    }
+
+    #[async_std::test]
+    async fn concurrent_inserts_test() {
+        concurrent_inserts(Arc::new(MemoryRepository::new())).await;
+    }
+
+    #[async_std::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn pg_concurrent_inserts_test() {
+        concurrent_inserts(Arc::new(pg_repository().await)).await;
+    }
+
+    #[test]
+    fn insert_stats_test() {
+        let stats = InsertStats {
+            rows: 3000,
+            elapsed: std::time::Duration::from_millis(1500),
+        };
+        assert_eq!(stats.rows_per_second(), 2000.0);
+        assert_eq!(stats.to_string(), "3000 rows in 1.5s (2000 rows/s)");
+    }
+
+    #[async_std::test]
+    async fn upsert_candles_test() {
+        upsert_candles_revised(&MemoryRepository::new()).await;
+    }
+
+    #[async_std::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn pg_upsert_candles_test() {
+        upsert_candles_revised(&pg_repository().await).await;
+    }
+
+    #[async_std::test]
+    async fn resampled_candles_test() {
+        resampled_candles_flagged(&MemoryRepository::new()).await;
+    }
+
+    #[async_std::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn pg_resampled_candles_test() {
+        resampled_candles_flagged(&pg_repository().await).await;
+    }
+
+    #[async_std::test]
+    async fn quarantine_test() {
+        quarantine_moved(&MemoryRepository::new()).await;
+    }
+
+    #[async_std::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn pg_quarantine_test() {
+        quarantine_moved(&pg_repository().await).await;
+    }
+
+    #[async_std::test]
+    async fn insert_candles_rollback_test() {
+        insert_candles_rollback(&MemoryRepository::new()).await;
+    }
+
+    #[async_std::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn pg_insert_candles_rollback_test() {
+        insert_candles_rollback(&pg_repository().await).await;
+    }
+
+    #[async_std::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn candles_test() {
+        let end_time = Utc::now();
+        let start_time = end_time - Duration::days(30);
+        let repo = pg_repository().await;
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Futures);
+        let candles = repo.candles_by_time(&symbol_minutes, &start_time, &end_time).await.unwrap_or_default();
+
+        println!("Found candles:");
+        for candle in candles.iter() {
+            iprintln!("{candle}");
+        }
+
+        let candles_ref: Vec<_> = candles.iter().collect();
+
+        println!("Inconsist candles:");
+        let inconsist = inconsistent_candles(candles_ref.as_slice(), &Interval::M15);
+        for candle in inconsist.iter() {
+            iprintln!("{candle}");
+        }
+    }
+
+    #[async_std::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn symbols_minutes_test() {
+        let repo = pg_repository().await;
+        let symbols_minutes = repo.symbols_minutes().await.unwrap();
+
+        iprintln!("symbols_minutes.len: {symbols_minutes.len()}");
+        for (symbol_minutes, count) in symbols_minutes {
+            let last_close_time = repo.last_close_time(&symbol_minutes).await.unwrap();
+            iprintln!("{symbol_minutes:?} {count}  {last_close_time:?}");
+            let range = repo.ranges_symbol_minutes(&symbol_minutes).await.unwrap();
+            iprintln!("{symbol_minutes:?} {count}  {range.0:?} - {range.1:?}");
+        }
+    }
+
+    #[test]
+    fn add_candles_test() {}
+
+    #[async_std::test]
+    async fn candles_market_test() {
+        candles_market(&MemoryRepository::new()).await;
+    }
+
+    #[async_std::test]
+    async fn symbol_info_test() {
+        symbol_info_saved(&MemoryRepository::new()).await;
+    }
+
+    #[async_std::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn pg_candles_market_test() {
+        candles_market(&pg_repository().await).await;
+    }
+
+    #[async_std::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn pg_symbol_info_test() {
+        symbol_info_saved(&pg_repository().await).await;
+    }
+}
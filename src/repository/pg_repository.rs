@@ -0,0 +1,1286 @@
+use super::{
+    candle_key, insert_candles_error, offending_candles,
+    repository_error::{RepositoryError, RepositoryResult},
+    InsertStats, Repository, UpsertStats,
+};
+use crate::{
+    config::symbol_minutes::SymbolMinutes,
+    model::{
+        agg_trade::AggTrade,
+        candle::Candle,
+        exchange_name::ExchangeName,
+        funding_rate::FundingRate,
+        interval::Interval,
+        market::Market,
+        open_interest::OpenInterest,
+        resampled_candle::{check_resample, resample_range, ResampledCandle},
+        symbol_info::SymbolInfo,
+    },
+    strategy::model::{flow::Flow, order::Order, position::Position, profit::Profit},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ifmt::iformat;
+use log::error;
+use rust_decimal::{
+    prelude::{FromPrimitive, ToPrimitive},
+    Decimal,
+};
+use sqlx::{
+    postgres::{PgPoolOptions, PgRow},
+    PgPool, Row,
+};
+use std::{collections::HashMap, time::Instant};
+
+/// Candles inserted by one statement
+const INSERT_CANDLES_CHUNK: usize = 5000;
+
+/// Repository on Postgres, queries are checked at runtime so building doesn't need a database
+pub struct PgRepository {
+    pool: PgPool,
+}
+
+impl PgRepository {
+    pub async fn new(database_url: &str) -> RepositoryResult<PgRepository> {
+        let pool = PgPoolOptions::new().max_connections(5).connect(database_url).await?;
+        Ok(PgRepository { pool })
+    }
+
+    /// Stored candles with an open time of symbol minutes of candles
+    async fn conflicting_candles(&self, candles: &[Candle]) -> RepositoryResult<Vec<Candle>> {
+        let symbols = candles.iter().map(|c| c.symbol.clone()).collect::<Vec<_>>();
+        let minutes = candles.iter().map(|c| c.minutes).collect::<Vec<_>>();
+        let markets = candles.iter().map(|c| c.market.clone()).collect::<Vec<_>>();
+        let open_times = candles.iter().map(|c| c.open_time).collect::<Vec<_>>();
+        let exchanges = candles.iter().map(|c| c.exchange.clone()).collect::<Vec<_>>();
+        let future = sqlx::query(
+            r#"
+                SELECT DISTINCT c.* FROM candle c
+                JOIN UNNEST ( $1::varchar[], $2::numeric[], $3::varchar[], $4::timestamptz[], $5::varchar[] ) AS n ( symbol, minutes, market, open_time, exchange )
+                ON c.symbol = n.symbol AND c.minutes = n.minutes AND c.market = n.market AND c.open_time = n.open_time AND c.exchange = n.exchange
+            "#,
+        )
+        .bind(&symbols)
+        .bind(&minutes)
+        .bind(&markets)
+        .bind(&open_times)
+        .bind(&exchanges)
+        .try_map(row_to_candle)
+        .fetch_all(&self.pool);
+        Ok(future.await?)
+    }
+}
+
+/// Sets id stored of each candle from ids by candle key
+fn set_candle_ids(candles: &mut [Candle], ids: &HashMap<(String, String, Decimal, String, DateTime<Utc>), Decimal>) -> RepositoryResult<()> {
+    for candle in candles.iter_mut() {
+        candle.id = *ids
+            .get(&candle_key(candle))
+            .ok_or_else(|| RepositoryError::NotFound(iformat!("Candle {candle} id not returned by database")))?;
+    }
+    Ok(())
+}
+
+fn row_to_candle(row: PgRow) -> Result<Candle, sqlx::Error> {
+    Ok(Candle {
+        open_time: row.try_get("open_time")?,
+        close_time: row.try_get("close_time")?,
+        id: row.try_get("id")?,
+        symbol: row.try_get("symbol")?,
+        minutes: row.try_get("minutes")?,
+        open: row.try_get("open")?,
+        high: row.try_get("high")?,
+        low: row.try_get("low")?,
+        close: row.try_get("close")?,
+        volume: row.try_get("volume")?,
+        market: row.try_get("market")?,
+        exchange: row.try_get("exchange")?,
+    })
+}
+
+fn row_to_resampled(row: PgRow) -> Result<ResampledCandle, sqlx::Error> {
+    let source_minutes: Decimal = row.try_get("source_minutes")?;
+    let source = source_minutes
+        .to_u32()
+        .ok_or_else(|| format!("Minutes {} is not a valid interval!", source_minutes))
+        .and_then(Interval::from_minutes)
+        .map_err(|e| sqlx::Error::Decode(e.into()))?;
+    let sources: i32 = row.try_get("sources")?;
+    let expected: i32 = row.try_get("expected")?;
+    Ok(ResampledCandle {
+        candle: Candle {
+            open_time: row.try_get("open_time")?,
+            close_time: row.try_get("close_time")?,
+            id: Decimal::ZERO,
+            symbol: row.try_get("symbol")?,
+            minutes: row.try_get("minutes")?,
+            open: row.try_get("open")?,
+            high: row.try_get("high")?,
+            low: row.try_get("low")?,
+            close: row.try_get("close")?,
+            volume: row.try_get("volume")?,
+            market: row.try_get("market")?,
+            exchange: row.try_get("exchange")?,
+        },
+        source,
+        sources: sources as u32,
+        expected: expected as u32,
+    })
+}
+
+fn row_to_position(row: PgRow) -> Result<Position, sqlx::Error> {
+    Ok(Position {
+        id: row.try_get("id")?,
+        state: row.try_get("state")?,
+        balance: row.try_get("balance")?,
+        balance_usd: row.try_get("balance_usd")?,
+        balance_amount: row.try_get("balance_amount")?,
+        balance_minimum: row.try_get("balance_minimum")?,
+        symbol: row.try_get("symbol")?,
+        simulation: row.try_get("simulation")?,
+        active: row.try_get("active")?,
+        current_profit: row.try_get("current_profit")?,
+    })
+}
+
+fn row_to_order(row: PgRow) -> Result<Order, sqlx::Error> {
+    Ok(Order {
+        id: row.try_get("id")?,
+        symbol: row.try_get("symbol")?,
+        exchange: row.try_get("exchange")?,
+        date: row.try_get("date")?,
+        price: row.try_get("price")?,
+        avg_execution_price: row.try_get("avg_execution_price")?,
+        side: row.try_get("side")?,
+        order_type: row.try_get("order_type")?,
+        is_live: row.try_get("is_live")?,
+        is_cancelled: row.try_get("is_cancelled")?,
+        is_hidden: row.try_get("is_hidden")?,
+        was_forced: row.try_get("was_forced")?,
+        original_amount: row.try_get("original_amount")?,
+        remaining_amount: row.try_get("remaining_amount")?,
+        executed_amount: row.try_get("executed_amount")?,
+        source: row.try_get("source")?,
+        order_id: row.try_get("order_id")?,
+        position: row.try_get("position")?,
+        profit: row.try_get("profit")?,
+        simulation: row.try_get("simulation")?,
+    })
+}
+
+fn row_to_profit(row: PgRow) -> Result<Profit, sqlx::Error> {
+    Ok(Profit {
+        id: row.try_get("id")?,
+        symbol: row.try_get("symbol")?,
+        position: row.try_get("position")?,
+        sold_date: row.try_get("sold_date")?,
+        sold_price: row.try_get("sold_price")?,
+        sold_amount: row.try_get("sold_amount")?,
+        bought_date: row.try_get("bought_date")?,
+        bought_price: row.try_get("bought_price")?,
+        bought_amount: row.try_get("bought_amount")?,
+        bought_usd: row.try_get("bought_usd")?,
+        sold_usd: row.try_get("sold_usd")?,
+        bought_order: row.try_get("bought_order")?,
+        sold_order: row.try_get("sold_order")?,
+        profit: row.try_get("profit")?,
+        simulation: row.try_get("simulation")?,
+        percent: row.try_get("percent")?,
+    })
+}
+
+fn row_to_flow(row: PgRow) -> Result<Flow, sqlx::Error> {
+    Ok(Flow {
+        id: row.try_get("id")?,
+        profit: row.try_get("profit")?,
+        position: row.try_get("position")?,
+        date: row.try_get("date")?,
+        price: row.try_get("price")?,
+        amount: row.try_get("amount")?,
+        usd: row.try_get("usd")?,
+        original_usd: row.try_get("original_usd")?,
+        balance_amount: row.try_get("balance_amount")?,
+        balance_usd: row.try_get("balance_usd")?,
+        state: row.try_get("state")?,
+    })
+}
+
+#[async_trait]
+impl Repository for PgRepository {
+    async fn last_close_time(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<Option<DateTime<Utc>>> {
+        let future = sqlx::query_as("SELECT MAX(close_time) FROM candle WHERE symbol = $1 AND minutes = $2 AND market = $3 AND exchange = $4")
+            .bind(&symbol_minutes.symbol)
+            .bind(Decimal::from_u32(symbol_minutes.interval.minutes()))
+            .bind(symbol_minutes.market.as_str())
+            .bind(symbol_minutes.exchange.as_str())
+            .fetch_one(&self.pool);
+        let result: (Option<DateTime<Utc>>,) = future.await?;
+        Ok(result.0)
+    }
+
+    async fn ranges_symbol_minutes(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<(Option<DateTime<Utc>>, Option<DateTime<Utc>>)> {
+        let future = sqlx::query_as("SELECT MIN(close_time), MAX(close_time) FROM candle WHERE symbol = $1 AND minutes = $2 AND market = $3 AND exchange = $4")
+            .bind(&symbol_minutes.symbol)
+            .bind(Decimal::from_u32(symbol_minutes.interval.minutes()))
+            .bind(symbol_minutes.market.as_str())
+            .bind(symbol_minutes.exchange.as_str())
+            .fetch_one(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn candle_by_id(&self, id: Decimal) -> RepositoryResult<Option<Candle>> {
+        let future = sqlx::query("SELECT * FROM candle WHERE id = $1")
+            .bind(id)
+            .try_map(row_to_candle)
+            .fetch_optional(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn symbols_minutes(&self) -> RepositoryResult<Vec<(SymbolMinutes, i64)>> {
+        let mut result = Vec::new();
+
+        let future = sqlx::query_as(
+            r#"
+                SELECT exchange, symbol, minutes, market, count(*) as qtd FROM candle
+                GROUP BY exchange, symbol, minutes, market
+                "#,
+        )
+        .fetch_all(&self.pool);
+
+        let rows: Vec<(String, String, Decimal, String, i64)> = future.await?;
+        for (exchange, symbol, minutes, market, count) in rows {
+            let exchange = exchange
+                .parse::<ExchangeName>()
+                .map_err(|e| RepositoryError::Decode(iformat!("Candles {symbol} exchange: {e}")))?;
+            let market = market
+                .parse::<Market>()
+                .map_err(|e| RepositoryError::Decode(iformat!("Candles {symbol} market: {e}")))?;
+            let minutes = minutes
+                .to_u32()
+                .ok_or_else(|| RepositoryError::Decode(iformat!("Candles {symbol} {market} minutes {minutes}")))?;
+            match Interval::from_minutes(minutes) {
+                Ok(interval) => result.push((
+                    SymbolMinutes {
+                        exchange,
+                        ..SymbolMinutes::new(&symbol, &interval, &market)
+                    },
+                    count,
+                )),
+                Err(e) => error!("{}", iformat!("Candles {exchange} {symbol} {market}: {e}")),
+            }
+        }
+        Ok(result)
+    }
+
+    async fn candles_by_time(&self, symbol_minutes: &SymbolMinutes, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>) -> RepositoryResult<Vec<Candle>> {
+        let minutes = Decimal::from(symbol_minutes.interval.minutes());
+
+        let future = sqlx::query(
+            r#"
+                SELECT * FROM candle
+                WHERE symbol = $1 AND minutes = $2 AND market = $3 AND exchange = $6 AND (open_time BETWEEN $4 AND $5 OR close_time BETWEEN $4 AND $5)
+                ORDER BY open_time
+            "#,
+        )
+        .bind(&symbol_minutes.symbol)
+        .bind(minutes)
+        .bind(symbol_minutes.market.as_str())
+        .bind(start_time)
+        .bind(end_time)
+        .bind(symbol_minutes.exchange.as_str())
+        .try_map(row_to_candle)
+        .fetch_all(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn candles_page(
+        &self, symbol_minutes: &SymbolMinutes, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>, limit: u32,
+    ) -> RepositoryResult<Vec<Candle>> {
+        let future = sqlx::query(
+            r#"
+                SELECT * FROM candle
+                WHERE symbol = $1 AND minutes = $2 AND market = $3 AND exchange = $4 AND open_time BETWEEN $5 AND $6
+                ORDER BY open_time
+                FETCH FIRST $7 ROWS ONLY
+            "#,
+        )
+        .bind(&symbol_minutes.symbol)
+        .bind(Decimal::from(symbol_minutes.interval.minutes()))
+        .bind(symbol_minutes.market.as_str())
+        .bind(symbol_minutes.exchange.as_str())
+        .bind(start_time)
+        .bind(end_time)
+        .bind(limit as i64)
+        .try_map(row_to_candle)
+        .fetch_all(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn last_candles(&self, symbol_minutes: &SymbolMinutes, limit: &i64) -> RepositoryResult<Vec<Candle>> {
+        let minutes = Decimal::from(symbol_minutes.interval.minutes());
+
+        let future = sqlx::query(
+            r#"
+                SELECT * FROM candle
+                WHERE symbol = $1 AND minutes = $2 AND market = $3 AND exchange = $5
+                ORDER BY open_time DESC
+                FETCH FIRST $4 ROWS ONLY
+            "#,
+        )
+        .bind(&symbol_minutes.symbol)
+        .bind(minutes)
+        .bind(symbol_minutes.market.as_str())
+        .bind(limit)
+        .bind(symbol_minutes.exchange.as_str())
+        .try_map(row_to_candle)
+        .fetch_all(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn insert_candle(&self, candle: &mut Candle) -> RepositoryResult<Decimal> {
+        let future = sqlx::query_as(
+            r#"
+                INSERT INTO candle (
+                    symbol,
+                    minutes,
+                    open_time,
+                    close_time,
+                    open,
+                    high,
+                    low,
+                    close,
+                    volume,
+                    market,
+                    exchange )
+                VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11 )
+                RETURNING id
+            "#,
+        )
+        .bind(&candle.symbol)
+        .bind(candle.minutes)
+        .bind(candle.open_time)
+        .bind(candle.close_time)
+        .bind(candle.open)
+        .bind(candle.high)
+        .bind(candle.low)
+        .bind(candle.close)
+        .bind(candle.volume)
+        .bind(&candle.market)
+        .bind(&candle.exchange)
+        .fetch_one(&self.pool);
+        let rec: (Decimal,) = future.await?;
+
+        candle.id = rec.0;
+        Ok(rec.0)
+    }
+
+    /// Multi-row inserts of `INSERT_CANDLES_CHUNK` candles in one transaction
+    async fn insert_candles(&self, candles: &mut [Candle]) -> RepositoryResult<InsertStats> {
+        let start = Instant::now();
+        let future = async {
+            let mut ids = HashMap::new();
+            let mut transaction = self.pool.begin().await?;
+            for chunk in candles.chunks(INSERT_CANDLES_CHUNK) {
+                let rows: Vec<(Decimal, String, String, Decimal, String, DateTime<Utc>)> = sqlx::query_as(
+                    r#"
+                        INSERT INTO candle ( symbol, minutes, open_time, close_time, open, high, low, close, volume, market, exchange )
+                        SELECT * FROM UNNEST (
+                            $1::varchar[], $2::numeric[], $3::timestamptz[], $4::timestamptz[],
+                            $5::numeric[], $6::numeric[], $7::numeric[], $8::numeric[], $9::numeric[], $10::varchar[], $11::varchar[] )
+                        RETURNING id, exchange, symbol, minutes, market, open_time
+                    "#,
+                )
+                .bind(chunk.iter().map(|c| c.symbol.clone()).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|c| c.minutes).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|c| c.open_time).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|c| c.close_time).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|c| c.open).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|c| c.high).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|c| c.low).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|c| c.close).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|c| c.volume).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|c| c.market.clone()).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|c| c.exchange.clone()).collect::<Vec<_>>())
+                .fetch_all(&mut transaction)
+                .await?;
+                // Returning order isn't granted, ids are matched by unique key
+                ids.extend(
+                    rows.into_iter()
+                        .map(|(id, exchange, symbol, minutes, market, open_time)| ((exchange, symbol, minutes, market, open_time), id)),
+                );
+            }
+            transaction.commit().await.map(|_| ids)
+        };
+        // Transaction not committed is rolled back when dropped
+        match future.await {
+            Ok(ids) => set_candle_ids(candles, &ids)?,
+            Err(e) => {
+                // Without stored candles the error doesn't name offending ones
+                let stored = self.conflicting_candles(candles).await.unwrap_or_else(|e| {
+                    error!("{}", iformat!("Conflicting candles not read: {e}"));
+                    Vec::new()
+                });
+                return Err(insert_candles_error(&offending_candles(candles, &stored), e.into()));
+            }
+        }
+        Ok(InsertStats::new(candles.len() as u64, &start))
+    }
+
+    /// Upserts chunks of `INSERT_CANDLES_CHUNK` candles in one transaction, rows with same values aren't rewritten
+    async fn upsert_candles(&self, candles: &mut [Candle]) -> RepositoryResult<UpsertStats> {
+        let start = Instant::now();
+        let offending = offending_candles(candles, &[]);
+        if !offending.is_empty() {
+            return Err(insert_candles_error(&offending, RepositoryError::Constraint("duplicate open time".to_string())));
+        }
+        let future = async {
+            let (mut ids, mut inserted, mut revised) = (HashMap::new(), 0, 0);
+            let mut transaction = self.pool.begin().await?;
+            for chunk in candles.chunks(INSERT_CANDLES_CHUNK) {
+                let symbols = chunk.iter().map(|c| c.symbol.clone()).collect::<Vec<_>>();
+                let minutes = chunk.iter().map(|c| c.minutes).collect::<Vec<_>>();
+                let markets = chunk.iter().map(|c| c.market.clone()).collect::<Vec<_>>();
+                let open_times = chunk.iter().map(|c| c.open_time).collect::<Vec<_>>();
+                let exchanges = chunk.iter().map(|c| c.exchange.clone()).collect::<Vec<_>>();
+                // Row inserted has no previous version (xmax 0), rows not distinct aren't returned
+                let rows: Vec<(bool,)> = sqlx::query_as(
+                    r#"
+                        INSERT INTO candle ( symbol, minutes, open_time, close_time, open, high, low, close, volume, market, exchange )
+                        SELECT * FROM UNNEST (
+                            $1::varchar[], $2::numeric[], $3::timestamptz[], $4::timestamptz[],
+                            $5::numeric[], $6::numeric[], $7::numeric[], $8::numeric[], $9::numeric[], $10::varchar[], $11::varchar[] )
+                        ON CONFLICT ( exchange, symbol, minutes, market, open_time ) DO UPDATE SET
+                            close_time = EXCLUDED.close_time,
+                            open = EXCLUDED.open,
+                            high = EXCLUDED.high,
+                            low = EXCLUDED.low,
+                            close = EXCLUDED.close,
+                            volume = EXCLUDED.volume,
+                            revision = candle.revision + 1,
+                            revised_at = now()
+                        WHERE ( candle.close_time, candle.open, candle.high, candle.low, candle.close, candle.volume )
+                            IS DISTINCT FROM ( EXCLUDED.close_time, EXCLUDED.open, EXCLUDED.high, EXCLUDED.low, EXCLUDED.close, EXCLUDED.volume )
+                        RETURNING xmax = 0
+                    "#,
+                )
+                .bind(&symbols)
+                .bind(&minutes)
+                .bind(&open_times)
+                .bind(chunk.iter().map(|c| c.close_time).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|c| c.open).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|c| c.high).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|c| c.low).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|c| c.close).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|c| c.volume).collect::<Vec<_>>())
+                .bind(&markets)
+                .bind(&exchanges)
+                .fetch_all(&mut transaction)
+                .await?;
+                let chunk_inserted = rows.iter().filter(|r| r.0).count() as u64;
+                inserted += chunk_inserted;
+                revised += rows.len() as u64 - chunk_inserted;
+
+                let rows: Vec<(Decimal, String, String, Decimal, String, DateTime<Utc>)> = sqlx::query_as(
+                    r#"
+                        SELECT c.id, c.exchange, c.symbol, c.minutes, c.market, c.open_time FROM candle c
+                        JOIN UNNEST ( $1::varchar[], $2::numeric[], $3::varchar[], $4::timestamptz[], $5::varchar[] ) AS n ( symbol, minutes, market, open_time, exchange )
+                        ON c.symbol = n.symbol AND c.minutes = n.minutes AND c.market = n.market AND c.open_time = n.open_time AND c.exchange = n.exchange
+                    "#,
+                )
+                .bind(&symbols)
+                .bind(&minutes)
+                .bind(&markets)
+                .bind(&open_times)
+                .bind(&exchanges)
+                .fetch_all(&mut transaction)
+                .await?;
+                ids.extend(
+                    rows.into_iter()
+                        .map(|(id, exchange, symbol, minutes, market, open_time)| ((exchange, symbol, minutes, market, open_time), id)),
+                );
+            }
+            transaction.commit().await.map(|_| (ids, inserted, revised))
+        };
+        let (ids, inserted, revised) = future.await.map_err(|e| insert_candles_error(&[], e.into()))?;
+        set_candle_ids(candles, &ids)?;
+        Ok(UpsertStats::new(inserted, revised, candles.len() as u64 - inserted - revised, &start))
+    }
+
+    async fn candle_revision(&self, id: &Decimal) -> RepositoryResult<Option<(i32, DateTime<Utc>)>> {
+        let future = sqlx::query_as("SELECT revision, revised_at FROM candle WHERE id = $1 AND revised_at IS NOT NULL")
+            .bind(id)
+            .fetch_optional(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn delete_all_candles(&self) -> RepositoryResult<()> {
+        let future = sqlx::query("DELETE FROM candle").execute(&self.pool);
+        future.await?;
+        Ok(())
+    }
+
+    async fn delete_candles(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<()> {
+        let future = sqlx::query("DELETE FROM candle WHERE symbol = $1 AND minutes = $2 AND market = $3 AND exchange = $4")
+            .bind(&symbol_minutes.symbol)
+            .bind(Decimal::from(symbol_minutes.interval.minutes()))
+            .bind(symbol_minutes.market.as_str())
+            .bind(symbol_minutes.exchange.as_str())
+            .execute(&self.pool);
+        future.await?;
+        Ok(())
+    }
+
+    async fn delete_candle(&self, id: &Decimal) -> RepositoryResult<()> {
+        let future = sqlx::query("DELETE FROM candle WHERE id = $1").bind(id).execute(&self.pool);
+        future.await?;
+        Ok(())
+    }
+
+    async fn delete_last_candle(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<()> {
+        let future = sqlx::query(
+            r#"DELETE FROM candle WHERE id =
+            (SELECT id FROM candle WHERE symbol = $1 AND minutes = $2 AND market = $3 AND exchange = $4
+                ORDER BY close_time DESC FETCH FIRST 1 ROWS ONLY
+            )"#,
+        )
+        .bind(&symbol_minutes.symbol)
+        .bind(Decimal::from(symbol_minutes.interval.minutes()))
+        .bind(symbol_minutes.market.as_str())
+        .bind(symbol_minutes.exchange.as_str())
+        .execute(&self.pool);
+        future.await?;
+        Ok(())
+    }
+
+    async fn quarantine_candles(&self, candles: &[(Candle, String)]) -> RepositoryResult<u64> {
+        let ids = candles.iter().map(|(c, _)| c.id).collect::<Vec<_>>();
+        let reasons = candles.iter().map(|(_, reason)| reason.clone()).collect::<Vec<_>>();
+        let future = async {
+            let mut transaction = self.pool.begin().await?;
+            let moved = sqlx::query(
+                r#"
+                    INSERT INTO candle_quarantine ( id, exchange, symbol, minutes, market, open_time, close_time, open, high, low, close, volume, reason )
+                    SELECT c.id, c.exchange, c.symbol, c.minutes, c.market, c.open_time, c.close_time, c.open, c.high, c.low, c.close, c.volume, q.reason
+                    FROM candle c JOIN UNNEST ( $1::numeric[], $2::text[] ) AS q ( id, reason ) ON c.id = q.id
+                "#,
+            )
+            .bind(&ids)
+            .bind(&reasons)
+            .execute(&mut transaction)
+            .await?
+            .rows_affected();
+            sqlx::query("DELETE FROM candle WHERE id = ANY($1)")
+                .bind(&ids)
+                .execute(&mut transaction)
+                .await?;
+            transaction.commit().await?;
+            Ok::<_, sqlx::Error>(moved)
+        };
+        Ok(future.await?)
+    }
+
+    async fn quarantined_candles(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<Vec<(Candle, String)>> {
+        let future = sqlx::query("SELECT * FROM candle_quarantine WHERE symbol = $1 AND minutes = $2 AND market = $3 AND exchange = $4 ORDER BY open_time")
+            .bind(&symbol_minutes.symbol)
+            .bind(Decimal::from(symbol_minutes.interval.minutes()))
+            .bind(symbol_minutes.market.as_str())
+            .bind(symbol_minutes.exchange.as_str())
+            .try_map(|row: PgRow| {
+                let reason = row.try_get("reason")?;
+                Ok((row_to_candle(row)?, reason))
+            })
+            .fetch_all(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn delete_quarantined_candles(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<()> {
+        let future = sqlx::query("DELETE FROM candle_quarantine WHERE symbol = $1 AND minutes = $2 AND market = $3 AND exchange = $4")
+            .bind(&symbol_minutes.symbol)
+            .bind(Decimal::from(symbol_minutes.interval.minutes()))
+            .bind(symbol_minutes.market.as_str())
+            .bind(symbol_minutes.exchange.as_str())
+            .execute(&self.pool);
+        future.await?;
+        Ok(())
+    }
+
+    async fn resample_candles(
+        &self, symbol_minutes: &SymbolMinutes, interval: &Interval, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> RepositoryResult<Vec<ResampledCandle>> {
+        check_resample(interval, &symbol_minutes.interval).map_err(RepositoryError::Query)?;
+        let (start_time, end_time) = resample_range(interval, start_time, end_time);
+        // Buckets as `Interval::open_trunc`: months and weeks (monday) truncated on UTC, others aligned to epoch
+        let bucket = match interval {
+            Interval::Month1 => "month",
+            Interval::W1 => "week",
+            _ => "epoch",
+        };
+
+        let future = sqlx::query(
+            r#"
+                SELECT bucket AS open_time,
+                    (ARRAY_AGG(open ORDER BY open_time))[1] AS open,
+                    MAX(high) AS high,
+                    MIN(low) AS low,
+                    (ARRAY_AGG(close ORDER BY open_time DESC))[1] AS close,
+                    SUM(volume) AS volume,
+                    COUNT(*) AS sources
+                FROM (
+                    SELECT c.*, CASE $7
+                        WHEN 'month' THEN DATE_TRUNC('month', open_time AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+                        WHEN 'week' THEN DATE_TRUNC('week', open_time AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+                        ELSE TO_TIMESTAMP(FLOOR(EXTRACT(EPOCH FROM open_time) / $8) * $8)
+                    END AS bucket
+                    FROM candle c
+                    WHERE symbol = $1 AND minutes = $2 AND market = $3 AND exchange = $4 AND open_time >= $5 AND open_time < $6
+                ) c
+                GROUP BY bucket
+                ORDER BY bucket
+            "#,
+        )
+        .bind(&symbol_minutes.symbol)
+        .bind(Decimal::from(symbol_minutes.interval.minutes()))
+        .bind(symbol_minutes.market.as_str())
+        .bind(symbol_minutes.exchange.as_str())
+        .bind(start_time)
+        .bind(end_time)
+        .bind(bucket)
+        .bind(interval.duration().num_seconds() as f64)
+        .try_map(|row: PgRow| {
+            let open_time: DateTime<Utc> = row.try_get("open_time")?;
+            let sources: i64 = row.try_get("sources")?;
+            let candle = Candle {
+                open_time,
+                close_time: interval.close(&open_time),
+                id: Decimal::ZERO,
+                symbol: symbol_minutes.symbol.clone(),
+                minutes: interval.minutes().into(),
+                open: row.try_get("open")?,
+                high: row.try_get("high")?,
+                low: row.try_get("low")?,
+                close: row.try_get("close")?,
+                volume: row.try_get("volume")?,
+                market: symbol_minutes.market.to_string(),
+                exchange: symbol_minutes.exchange.to_string(),
+            };
+            Ok(ResampledCandle::new(candle, interval, &symbol_minutes.interval, sources as u32))
+        })
+        .fetch_all(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn save_resampled_candles(&self, candles: &[ResampledCandle]) -> RepositoryResult<u64> {
+        let exchanges = candles.iter().map(|r| r.candle.exchange.clone()).collect::<Vec<_>>();
+        let symbols = candles.iter().map(|r| r.candle.symbol.clone()).collect::<Vec<_>>();
+        let minutes = candles.iter().map(|r| r.candle.minutes).collect::<Vec<_>>();
+        let markets = candles.iter().map(|r| r.candle.market.clone()).collect::<Vec<_>>();
+        let open_times = candles.iter().map(|r| r.candle.open_time).collect::<Vec<_>>();
+        let close_times = candles.iter().map(|r| r.candle.close_time).collect::<Vec<_>>();
+        let opens = candles.iter().map(|r| r.candle.open).collect::<Vec<_>>();
+        let highs = candles.iter().map(|r| r.candle.high).collect::<Vec<_>>();
+        let lows = candles.iter().map(|r| r.candle.low).collect::<Vec<_>>();
+        let closes = candles.iter().map(|r| r.candle.close).collect::<Vec<_>>();
+        let volumes = candles.iter().map(|r| r.candle.volume).collect::<Vec<_>>();
+        let source_minutes = candles.iter().map(|r| Decimal::from(r.source.minutes())).collect::<Vec<_>>();
+        let sources = candles.iter().map(|r| r.sources as i32).collect::<Vec<_>>();
+        let expected = candles.iter().map(|r| r.expected as i32).collect::<Vec<_>>();
+
+        let future = sqlx::query(
+            r#"
+                INSERT INTO resampled_candle (
+                    exchange, symbol, minutes, market, open_time, close_time, open, high, low, close, volume, source_minutes, sources, expected )
+                SELECT * FROM UNNEST (
+                    $1::varchar[], $2::varchar[], $3::numeric[], $4::varchar[], $5::timestamptz[], $6::timestamptz[], $7::numeric[], $8::numeric[],
+                    $9::numeric[], $10::numeric[], $11::numeric[], $12::numeric[], $13::integer[], $14::integer[] )
+                ON CONFLICT (exchange, symbol, minutes, market, open_time) DO UPDATE SET
+                    close_time = EXCLUDED.close_time,
+                    open = EXCLUDED.open,
+                    high = EXCLUDED.high,
+                    low = EXCLUDED.low,
+                    close = EXCLUDED.close,
+                    volume = EXCLUDED.volume,
+                    source_minutes = EXCLUDED.source_minutes,
+                    sources = EXCLUDED.sources,
+                    expected = EXCLUDED.expected
+            "#,
+        )
+        .bind(&exchanges)
+        .bind(&symbols)
+        .bind(&minutes)
+        .bind(&markets)
+        .bind(&open_times)
+        .bind(&close_times)
+        .bind(&opens)
+        .bind(&highs)
+        .bind(&lows)
+        .bind(&closes)
+        .bind(&volumes)
+        .bind(&source_minutes)
+        .bind(&sources)
+        .bind(&expected)
+        .execute(&self.pool);
+        Ok(future.await?.rows_affected())
+    }
+
+    async fn resampled_candles(
+        &self, symbol_minutes: &SymbolMinutes, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> RepositoryResult<Vec<ResampledCandle>> {
+        let (start_time, end_time) = resample_range(&symbol_minutes.interval, start_time, end_time);
+        let future = sqlx::query(
+            r#"
+                SELECT * FROM resampled_candle
+                WHERE symbol = $1 AND minutes = $2 AND market = $3 AND exchange = $4 AND open_time >= $5 AND open_time < $6
+                ORDER BY open_time
+            "#,
+        )
+        .bind(&symbol_minutes.symbol)
+        .bind(Decimal::from(symbol_minutes.interval.minutes()))
+        .bind(symbol_minutes.market.as_str())
+        .bind(symbol_minutes.exchange.as_str())
+        .bind(start_time)
+        .bind(end_time)
+        .try_map(row_to_resampled)
+        .fetch_all(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn delete_resampled_candles(&self, symbol_minutes: &SymbolMinutes) -> RepositoryResult<()> {
+        let future = sqlx::query("DELETE FROM resampled_candle WHERE symbol = $1 AND minutes = $2 AND market = $3 AND exchange = $4")
+            .bind(&symbol_minutes.symbol)
+            .bind(Decimal::from(symbol_minutes.interval.minutes()))
+            .bind(symbol_minutes.market.as_str())
+            .bind(symbol_minutes.exchange.as_str())
+            .execute(&self.pool);
+        future.await?;
+        Ok(())
+    }
+
+    async fn symbol_info(&self, exchange: &ExchangeName, symbol: &str, market: &Market) -> RepositoryResult<Option<SymbolInfo>> {
+        let future = sqlx::query("SELECT * FROM symbol_info WHERE exchange = $1 AND symbol = $2 AND market = $3")
+            .bind(exchange.as_str())
+            .bind(symbol)
+            .bind(market.as_str())
+            .try_map(|row: PgRow| {
+                Ok(SymbolInfo {
+                    exchange: *exchange,
+                    symbol: row.try_get("symbol")?,
+                    market: *market,
+                    base_asset: row.try_get("base_asset")?,
+                    quote_asset: row.try_get("quote_asset")?,
+                    tick_size: row.try_get("tick_size")?,
+                    step_size: row.try_get("step_size")?,
+                    min_qty: row.try_get("min_qty")?,
+                    min_notional: row.try_get("min_notional")?,
+                    updated_at: row.try_get("updated_at")?,
+                })
+            })
+            .fetch_optional(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn save_symbols_info(&self, symbols_info: &[SymbolInfo]) -> RepositoryResult<()> {
+        let future = async {
+            let mut transaction = self.pool.begin().await?;
+            for symbol_info in symbols_info.iter() {
+                sqlx::query(
+                    r#"
+                        INSERT INTO symbol_info (
+                            exchange,
+                            symbol,
+                            market,
+                            base_asset,
+                            quote_asset,
+                            tick_size,
+                            step_size,
+                            min_qty,
+                            min_notional,
+                            updated_at )
+                        VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10 )
+                        ON CONFLICT (exchange, symbol, market) DO UPDATE SET
+                            base_asset = EXCLUDED.base_asset,
+                            quote_asset = EXCLUDED.quote_asset,
+                            tick_size = EXCLUDED.tick_size,
+                            step_size = EXCLUDED.step_size,
+                            min_qty = EXCLUDED.min_qty,
+                            min_notional = EXCLUDED.min_notional,
+                            updated_at = EXCLUDED.updated_at
+                    "#,
+                )
+                .bind(symbol_info.exchange.as_str())
+                .bind(&symbol_info.symbol)
+                .bind(symbol_info.market.as_str())
+                .bind(&symbol_info.base_asset)
+                .bind(&symbol_info.quote_asset)
+                .bind(symbol_info.tick_size)
+                .bind(symbol_info.step_size)
+                .bind(symbol_info.min_qty)
+                .bind(symbol_info.min_notional)
+                .bind(symbol_info.updated_at)
+                .execute(&mut transaction)
+                .await?;
+            }
+            transaction.commit().await
+        };
+        future.await?;
+        Ok(())
+    }
+
+    async fn delete_symbol_info(&self, exchange: &ExchangeName, symbol: &str, market: &Market) -> RepositoryResult<()> {
+        let future = sqlx::query("DELETE FROM symbol_info WHERE exchange = $1 AND symbol = $2 AND market = $3")
+            .bind(exchange.as_str())
+            .bind(symbol)
+            .bind(market.as_str())
+            .execute(&self.pool);
+        future.await?;
+        Ok(())
+    }
+
+    async fn last_funding_time(&self, exchange: &ExchangeName, symbol: &str) -> RepositoryResult<Option<DateTime<Utc>>> {
+        let future = sqlx::query_as("SELECT MAX(funding_time) FROM funding_rate WHERE exchange = $1 AND symbol = $2")
+            .bind(exchange.as_str())
+            .bind(symbol)
+            .fetch_one(&self.pool);
+        let result: (Option<DateTime<Utc>>,) = future.await?;
+        Ok(result.0)
+    }
+
+    async fn funding_rates(
+        &self, exchange: &ExchangeName, symbol: &str, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> RepositoryResult<Vec<FundingRate>> {
+        let future = sqlx::query("SELECT * FROM funding_rate WHERE exchange = $1 AND symbol = $2 AND funding_time BETWEEN $3 AND $4 ORDER BY funding_time")
+            .bind(exchange.as_str())
+            .bind(symbol)
+            .bind(start_time)
+            .bind(end_time)
+            .try_map(|row: PgRow| {
+                Ok(FundingRate {
+                    exchange: *exchange,
+                    symbol: row.try_get("symbol")?,
+                    funding_time: row.try_get("funding_time")?,
+                    funding_rate: row.try_get("funding_rate")?,
+                    mark_price: row.try_get("mark_price")?,
+                })
+            })
+            .fetch_all(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn insert_funding_rates(&self, funding_rates: &[FundingRate]) -> RepositoryResult<u64> {
+        let future = async {
+            let mut inserted = 0;
+            let mut transaction = self.pool.begin().await?;
+            for funding_rate in funding_rates.iter() {
+                inserted += sqlx::query(
+                    r#"
+                        INSERT INTO funding_rate ( exchange, symbol, funding_time, funding_rate, mark_price )
+                        VALUES ( $1, $2, $3, $4, $5 )
+                        ON CONFLICT DO NOTHING
+                    "#,
+                )
+                .bind(funding_rate.exchange.as_str())
+                .bind(&funding_rate.symbol)
+                .bind(funding_rate.funding_time)
+                .bind(funding_rate.funding_rate)
+                .bind(funding_rate.mark_price)
+                .execute(&mut transaction)
+                .await?
+                .rows_affected();
+            }
+            transaction.commit().await.map(|_| inserted)
+        };
+        Ok(future.await?)
+    }
+
+    async fn delete_funding_rates(&self, exchange: &ExchangeName, symbol: &str) -> RepositoryResult<()> {
+        let future = sqlx::query("DELETE FROM funding_rate WHERE exchange = $1 AND symbol = $2")
+            .bind(exchange.as_str())
+            .bind(symbol)
+            .execute(&self.pool);
+        future.await?;
+        Ok(())
+    }
+
+    async fn last_open_interest_time(&self, exchange: &ExchangeName, symbol: &str, interval: &Interval) -> RepositoryResult<Option<DateTime<Utc>>> {
+        let future = sqlx::query_as("SELECT MAX(time) FROM open_interest WHERE exchange = $1 AND symbol = $2 AND minutes = $3")
+            .bind(exchange.as_str())
+            .bind(symbol)
+            .bind(Decimal::from(interval.minutes()))
+            .fetch_one(&self.pool);
+        let result: (Option<DateTime<Utc>>,) = future.await?;
+        Ok(result.0)
+    }
+
+    async fn open_interests(
+        &self, exchange: &ExchangeName, symbol: &str, interval: &Interval, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> RepositoryResult<Vec<OpenInterest>> {
+        let future = sqlx::query("SELECT * FROM open_interest WHERE exchange = $1 AND symbol = $2 AND minutes = $3 AND time BETWEEN $4 AND $5 ORDER BY time")
+            .bind(exchange.as_str())
+            .bind(symbol)
+            .bind(Decimal::from(interval.minutes()))
+            .bind(start_time)
+            .bind(end_time)
+            .try_map(|row: PgRow| {
+                Ok(OpenInterest {
+                    exchange: *exchange,
+                    symbol: row.try_get("symbol")?,
+                    interval: *interval,
+                    time: row.try_get("time")?,
+                    sum_open_interest: row.try_get("sum_open_interest")?,
+                    sum_open_interest_value: row.try_get("sum_open_interest_value")?,
+                })
+            })
+            .fetch_all(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn insert_open_interests(&self, open_interests: &[OpenInterest]) -> RepositoryResult<u64> {
+        let future = async {
+            let mut inserted = 0;
+            let mut transaction = self.pool.begin().await?;
+            for open_interest in open_interests.iter() {
+                inserted += sqlx::query(
+                    r#"
+                        INSERT INTO open_interest ( exchange, symbol, minutes, time, sum_open_interest, sum_open_interest_value )
+                        VALUES ( $1, $2, $3, $4, $5, $6 )
+                        ON CONFLICT DO NOTHING
+                    "#,
+                )
+                .bind(open_interest.exchange.as_str())
+                .bind(&open_interest.symbol)
+                .bind(Decimal::from(open_interest.interval.minutes()))
+                .bind(open_interest.time)
+                .bind(open_interest.sum_open_interest)
+                .bind(open_interest.sum_open_interest_value)
+                .execute(&mut transaction)
+                .await?
+                .rows_affected();
+            }
+            transaction.commit().await.map(|_| inserted)
+        };
+        Ok(future.await?)
+    }
+
+    async fn delete_open_interests(&self, exchange: &ExchangeName, symbol: &str, interval: &Interval) -> RepositoryResult<()> {
+        let future = sqlx::query("DELETE FROM open_interest WHERE exchange = $1 AND symbol = $2 AND minutes = $3")
+            .bind(exchange.as_str())
+            .bind(symbol)
+            .bind(Decimal::from(interval.minutes()))
+            .execute(&self.pool);
+        future.await?;
+        Ok(())
+    }
+
+    async fn last_agg_trade(&self, exchange: &ExchangeName, symbol: &str, market: &Market) -> RepositoryResult<Option<(i64, DateTime<Utc>)>> {
+        let future = sqlx::query_as("SELECT id, time FROM agg_trade WHERE exchange = $1 AND symbol = $2 AND market = $3 ORDER BY id DESC LIMIT 1")
+            .bind(exchange.as_str())
+            .bind(symbol)
+            .bind(market.as_str())
+            .fetch_optional(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn agg_trades(
+        &self, exchange: &ExchangeName, symbol: &str, market: &Market, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+    ) -> RepositoryResult<Vec<AggTrade>> {
+        let future = sqlx::query("SELECT * FROM agg_trade WHERE exchange = $1 AND symbol = $2 AND market = $3 AND time BETWEEN $4 AND $5 ORDER BY id")
+            .bind(exchange.as_str())
+            .bind(symbol)
+            .bind(market.as_str())
+            .bind(start_time)
+            .bind(end_time)
+            .try_map(|row: PgRow| {
+                Ok(AggTrade {
+                    exchange: *exchange,
+                    symbol: row.try_get("symbol")?,
+                    market: *market,
+                    id: row.try_get("id")?,
+                    price: row.try_get("price")?,
+                    quantity: row.try_get("quantity")?,
+                    time: row.try_get("time")?,
+                    is_buyer_maker: row.try_get("is_buyer_maker")?,
+                })
+            })
+            .fetch_all(&self.pool);
+        Ok(future.await?)
+    }
+
+    /// A single statement for all trades
+    async fn insert_agg_trades(&self, trades: &[AggTrade]) -> RepositoryResult<u64> {
+        let exchanges = trades.iter().map(|t| t.exchange.as_str().to_string()).collect::<Vec<_>>();
+        let symbols = trades.iter().map(|t| t.symbol.clone()).collect::<Vec<_>>();
+        let markets = trades.iter().map(|t| t.market.as_str().to_string()).collect::<Vec<_>>();
+        let ids = trades.iter().map(|t| t.id).collect::<Vec<_>>();
+        let prices = trades.iter().map(|t| t.price).collect::<Vec<_>>();
+        let quantities = trades.iter().map(|t| t.quantity).collect::<Vec<_>>();
+        let times = trades.iter().map(|t| t.time).collect::<Vec<_>>();
+        let buyer_makers = trades.iter().map(|t| t.is_buyer_maker).collect::<Vec<_>>();
+        let future = sqlx::query(
+            r#"
+                INSERT INTO agg_trade ( exchange, symbol, market, id, price, quantity, time, is_buyer_maker )
+                SELECT * FROM UNNEST ( $1::varchar[], $2::varchar[], $3::varchar[], $4::bigint[], $5::numeric[], $6::numeric[], $7::timestamptz[], $8::bool[] )
+                ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(&exchanges)
+        .bind(&symbols)
+        .bind(&markets)
+        .bind(&ids)
+        .bind(&prices)
+        .bind(&quantities)
+        .bind(&times)
+        .bind(&buyer_makers)
+        .execute(&self.pool);
+        Ok(future.await?.rows_affected())
+    }
+
+    async fn delete_agg_trades(&self, exchange: &ExchangeName, symbol: &str, market: &Market) -> RepositoryResult<()> {
+        let future = sqlx::query("DELETE FROM agg_trade WHERE exchange = $1 AND symbol = $2 AND market = $3")
+            .bind(exchange.as_str())
+            .bind(symbol)
+            .bind(market.as_str())
+            .execute(&self.pool);
+        future.await?;
+        Ok(())
+    }
+
+    async fn insert_position(&self, position: &mut Position) -> RepositoryResult<i64> {
+        let future = sqlx::query_as(
+            r#"
+                INSERT INTO position ( symbol, state, balance, balance_usd, balance_amount, balance_minimum, simulation, active, current_profit )
+                VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9 )
+                RETURNING id
+            "#,
+        )
+        .bind(&position.symbol)
+        .bind(&position.state)
+        .bind(position.balance)
+        .bind(position.balance_usd)
+        .bind(position.balance_amount)
+        .bind(position.balance_minimum)
+        .bind(position.simulation)
+        .bind(position.active)
+        .bind(position.current_profit)
+        .fetch_one(&self.pool);
+        let rec: (i64,) = future.await?;
+        position.id = rec.0;
+        Ok(rec.0)
+    }
+
+    async fn update_position(&self, position: &Position) -> RepositoryResult<()> {
+        let future = sqlx::query(
+            r#"
+                UPDATE position SET
+                    state = $2,
+                    balance = $3,
+                    balance_usd = $4,
+                    balance_amount = $5,
+                    balance_minimum = $6,
+                    active = $7,
+                    current_profit = $8
+                WHERE id = $1
+            "#,
+        )
+        .bind(position.id)
+        .bind(&position.state)
+        .bind(position.balance)
+        .bind(position.balance_usd)
+        .bind(position.balance_amount)
+        .bind(position.balance_minimum)
+        .bind(position.active)
+        .bind(position.current_profit)
+        .execute(&self.pool);
+        if future.await?.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound(format!("Position {} not found", position.id)));
+        }
+        Ok(())
+    }
+
+    async fn positions(&self, symbol: &str, simulation: bool) -> RepositoryResult<Vec<Position>> {
+        let future = sqlx::query("SELECT * FROM position WHERE symbol = $1 AND simulation = $2 ORDER BY id")
+            .bind(symbol)
+            .bind(simulation)
+            .try_map(row_to_position)
+            .fetch_all(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn delete_position(&self, id: i64) -> RepositoryResult<()> {
+        let future = sqlx::query("DELETE FROM position WHERE id = $1").bind(id).execute(&self.pool);
+        future.await?;
+        Ok(())
+    }
+
+    async fn insert_order(&self, order: &mut Order) -> RepositoryResult<i64> {
+        let future = sqlx::query_as(
+            r#"
+                INSERT INTO trade_order (
+                    symbol,
+                    exchange,
+                    date,
+                    price,
+                    avg_execution_price,
+                    side,
+                    order_type,
+                    is_live,
+                    is_cancelled,
+                    is_hidden,
+                    was_forced,
+                    original_amount,
+                    remaining_amount,
+                    executed_amount,
+                    source,
+                    order_id,
+                    position,
+                    profit,
+                    simulation )
+                VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19 )
+                RETURNING id
+            "#,
+        )
+        .bind(&order.symbol)
+        .bind(&order.exchange)
+        .bind(order.date)
+        .bind(order.price)
+        .bind(order.avg_execution_price)
+        .bind(&order.side)
+        .bind(&order.order_type)
+        .bind(order.is_live)
+        .bind(order.is_cancelled)
+        .bind(order.is_hidden)
+        .bind(order.was_forced)
+        .bind(order.original_amount)
+        .bind(order.remaining_amount)
+        .bind(order.executed_amount)
+        .bind(&order.source)
+        .bind(order.order_id)
+        .bind(order.position)
+        .bind(order.profit)
+        .bind(order.simulation)
+        .fetch_one(&self.pool);
+        let rec: (i64,) = future.await?;
+        order.id = rec.0;
+        Ok(rec.0)
+    }
+
+    async fn update_order(&self, order: &Order) -> RepositoryResult<()> {
+        let future = sqlx::query(
+            r#"
+                UPDATE trade_order SET
+                    avg_execution_price = $2,
+                    is_live = $3,
+                    is_cancelled = $4,
+                    remaining_amount = $5,
+                    executed_amount = $6,
+                    order_id = $7,
+                    profit = $8
+                WHERE id = $1 AND simulation = $9
+            "#,
+        )
+        .bind(order.id)
+        .bind(order.avg_execution_price)
+        .bind(order.is_live)
+        .bind(order.is_cancelled)
+        .bind(order.remaining_amount)
+        .bind(order.executed_amount)
+        .bind(order.order_id)
+        .bind(order.profit)
+        .bind(order.simulation)
+        .execute(&self.pool);
+        if future.await?.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound(format!("Order {} not found", order.id)));
+        }
+        Ok(())
+    }
+
+    async fn orders(&self, position: i64) -> RepositoryResult<Vec<Order>> {
+        let future = sqlx::query("SELECT * FROM trade_order WHERE position = $1 ORDER BY date, id")
+            .bind(position)
+            .try_map(row_to_order)
+            .fetch_all(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn insert_profit(&self, profit: &mut Profit) -> RepositoryResult<i64> {
+        let future = sqlx::query_as(
+            r#"
+                INSERT INTO profit (
+                    symbol,
+                    position,
+                    sold_date,
+                    sold_price,
+                    sold_amount,
+                    bought_date,
+                    bought_price,
+                    bought_amount,
+                    bought_usd,
+                    sold_usd,
+                    bought_order,
+                    sold_order,
+                    profit,
+                    simulation,
+                    percent )
+                VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15 )
+                RETURNING id
+            "#,
+        )
+        .bind(&profit.symbol)
+        .bind(profit.position)
+        .bind(profit.sold_date)
+        .bind(profit.sold_price)
+        .bind(profit.sold_amount)
+        .bind(profit.bought_date)
+        .bind(profit.bought_price)
+        .bind(profit.bought_amount)
+        .bind(profit.bought_usd)
+        .bind(profit.sold_usd)
+        .bind(profit.bought_order)
+        .bind(profit.sold_order)
+        .bind(profit.profit)
+        .bind(profit.simulation)
+        .bind(profit.percent)
+        .fetch_one(&self.pool);
+        let rec: (i64,) = future.await?;
+        profit.id = rec.0;
+        Ok(rec.0)
+    }
+
+    async fn profits(&self, position: i64) -> RepositoryResult<Vec<Profit>> {
+        let future = sqlx::query("SELECT * FROM profit WHERE position = $1 ORDER BY sold_date, id")
+            .bind(position)
+            .try_map(row_to_profit)
+            .fetch_all(&self.pool);
+        Ok(future.await?)
+    }
+
+    async fn insert_flow(&self, flow: &mut Flow) -> RepositoryResult<i64> {
+        let future = sqlx::query_as(
+            r#"
+                INSERT INTO flow ( profit, position, date, price, amount, usd, original_usd, balance_amount, balance_usd, state )
+                VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10 )
+                RETURNING id
+            "#,
+        )
+        .bind(flow.profit)
+        .bind(flow.position)
+        .bind(flow.date)
+        .bind(flow.price)
+        .bind(flow.amount)
+        .bind(flow.usd)
+        .bind(flow.original_usd)
+        .bind(flow.balance_amount)
+        .bind(flow.balance_usd)
+        .bind(&flow.state)
+        .fetch_one(&self.pool);
+        let rec: (i64,) = future.await?;
+        flow.id = rec.0;
+        Ok(rec.0)
+    }
+
+    async fn flows(&self, position: i64) -> RepositoryResult<Vec<Flow>> {
+        let future = sqlx::query("SELECT * FROM flow WHERE position = $1 ORDER BY date, id")
+            .bind(position)
+            .try_map(row_to_flow)
+            .fetch_all(&self.pool);
+        Ok(future.await?)
+    }
+}
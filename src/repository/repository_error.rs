@@ -0,0 +1,109 @@
+use std::fmt;
+
+/// Error of a repository operation, tagged with what went wrong so callers can tell a failure from missing data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepositoryError {
+    /// Database unreachable: network, TLS, pool timed out or closed
+    Connection(String),
+    /// Unique key, foreign key or check violated (e.g. open time already stored, position not found on insert)
+    Constraint(String),
+    /// Row to update doesn't exist
+    NotFound(String),
+    /// Row stored can't be read as model (column missing, type or value not expected)
+    Decode(String),
+    /// Statement refused by database for another reason (syntax, missing table...)
+    Query(String),
+}
+
+pub type RepositoryResult<T> = Result<T, RepositoryError>;
+
+impl RepositoryError {
+    pub fn message(&self) -> &str {
+        match self {
+            RepositoryError::Connection(message)
+            | RepositoryError::Constraint(message)
+            | RepositoryError::NotFound(message)
+            | RepositoryError::Decode(message)
+            | RepositoryError::Query(message) => message,
+        }
+    }
+
+    /// Same kind of error, message prefixed by context
+    pub fn context(self, context: &str) -> Self {
+        let message = format!("{}: {}", context, self.message());
+        match self {
+            RepositoryError::Connection(_) => RepositoryError::Connection(message),
+            RepositoryError::Constraint(_) => RepositoryError::Constraint(message),
+            RepositoryError::NotFound(_) => RepositoryError::NotFound(message),
+            RepositoryError::Decode(_) => RepositoryError::Decode(message),
+            RepositoryError::Query(_) => RepositoryError::Query(message),
+        }
+    }
+}
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            RepositoryError::Connection(_) => "Connection failed",
+            RepositoryError::Constraint(_) => "Constraint violated",
+            RepositoryError::NotFound(_) => "Not found",
+            RepositoryError::Decode(_) => "Decode failed",
+            RepositoryError::Query(_) => "Query failed",
+        };
+        write!(f, "{}: {}", kind, self.message())
+    }
+}
+
+impl std::error::Error for RepositoryError {}
+
+impl From<sqlx::Error> for RepositoryError {
+    fn from(error: sqlx::Error) -> Self {
+        let message = error.to_string();
+        match &error {
+            // SQLSTATE class 23 is integrity constraint violation, class 08 connection exception
+            sqlx::Error::Database(database) => match database.code() {
+                Some(code) if code.starts_with("23") => RepositoryError::Constraint(message),
+                Some(code) if code.starts_with("08") => RepositoryError::Connection(message),
+                _ => RepositoryError::Query(message),
+            },
+            sqlx::Error::Configuration(_)
+            | sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::Protocol(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed => RepositoryError::Connection(message),
+            sqlx::Error::RowNotFound => RepositoryError::NotFound(message),
+            sqlx::Error::TypeNotFound { .. }
+            | sqlx::Error::ColumnIndexOutOfBounds { .. }
+            | sqlx::Error::ColumnNotFound(_)
+            | sqlx::Error::ColumnDecode { .. }
+            | sqlx::Error::Decode(_) => RepositoryError::Decode(message),
+            _ => RepositoryError::Query(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqlx_error_test() {
+        let io = std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "refused");
+        assert!(matches!(RepositoryError::from(sqlx::Error::Io(io)), RepositoryError::Connection(_)));
+        assert!(matches!(RepositoryError::from(sqlx::Error::PoolTimedOut), RepositoryError::Connection(_)));
+        assert!(matches!(RepositoryError::from(sqlx::Error::RowNotFound), RepositoryError::NotFound(_)));
+        assert!(matches!(
+            RepositoryError::from(sqlx::Error::ColumnNotFound("close".to_string())),
+            RepositoryError::Decode(_)
+        ));
+    }
+
+    #[test]
+    fn context_test() {
+        let error = RepositoryError::Constraint("duplicate open time".to_string()).context("Candles insert rolled back");
+        assert_eq!(error, RepositoryError::Constraint("Candles insert rolled back: duplicate open time".to_string()));
+        assert_eq!(error.to_string(), "Constraint violated: Candles insert rolled back: duplicate open time");
+    }
+}
@@ -1,4 +1,10 @@
-use super::{macd_trend::MacdTrend, trade_context_provider::TradeContextProvider, trader::Trader};
+use super::{
+    macd_trend::MacdTrend,
+    trade_context_provider::TradeContextProvider,
+    trade_ledger::TradeLedger,
+    trader::Trader,
+    trader_register::{Position, Trade, TraderRegister},
+};
 use crate::{
     application::{
         app::Application,
@@ -13,8 +19,12 @@ use ifmt::iformat;
 use lockfree_object_pool::LinearObjectPool;
 use log::info;
 use rayon::prelude::*;
+use rust_decimal_macros::dec;
 use std::time::Instant;
 
+/// Source of orders written by back tests
+pub static SOURCE_BACK_TEST: &str = "back-test";
+
 #[derive(Clone)]
 pub struct TraderFactory {
     candles_selection: CandlesSelection,
@@ -34,7 +44,8 @@ impl TraderFactory {
         let mut candles_provider = self.candles_provider.clone();
         candles_provider.set_candles_selection(self.candles_selection.clone());
         let indicator_provider = IndicatorProvider::new();
-        let trend_context_provider = TradeContextProvider::new(&self.candles_selection.symbol_minutes.symbol, indicator_provider, candles_provider);
+        let symbol_minutes = &self.candles_selection.symbol_minutes;
+        let trend_context_provider = TradeContextProvider::new(&symbol_minutes.symbol, &symbol_minutes.market, indicator_provider, candles_provider);
         let mcad_trend = MacdTrend::new();
         Trader::new(trend_context_provider, Box::new(mcad_trend))
     }
@@ -44,10 +55,27 @@ pub fn run_trader_back_test(app: &mut Application) -> anyhow::Result<()> {
     let start = Instant::now();
     info!("Initializing backtest...");
 
-    let trader_factory = TraderFactory::new(app.selection.candles_selection.clone(), app.candles_provider.clone());
+    let (trades, ledger) = back_test_ledger(app)?;
+    let position = ledger.position();
+    info!(
+        "{}",
+        iformat!("Back test position {position.id}: {position.state} balance USD {position.balance_usd} coin {position.balance_amount}")
+    );
+
+    let trading_plotter = TradingPlotter::new(&trades);
+
+    let plotters = vec![Box::new(trading_plotter) as Box<dyn PlotterIndicatorContext>];
 
-    //let position = Position::new_from_usd(dec!(1000));
-    //let trader_register = TraderRegister::new(position);
+    plot_selection(app.selection.clone(), app.candles_provider.clone_provider(), plotters, Vec::new())?;
+
+    info!("{}", iformat!("Finished backtest, elapsed: {start.elapsed():?}"));
+
+    Ok(())
+}
+
+/// Checks trader on each candle of selection, its trades are written by a simulated position ledger following symbol filters
+pub fn back_test_ledger(app: &mut Application) -> anyhow::Result<(Vec<Trade>, TradeLedger)> {
+    let trader_factory = TraderFactory::new(app.selection.candles_selection.clone(), app.candles_provider.clone());
 
     app.candles_provider.set_candles_selection(app.selection.candles_selection.clone());
     let candles = app.candles_provider.candles()?;
@@ -65,7 +93,7 @@ pub fn run_trader_back_test(app: &mut Application) -> anyhow::Result<()> {
 
     //let pool = Pool::<Trader>::new(32, || trader_factory.create_trader());
     let pool_rayon = rayon::ThreadPoolBuilder::new().num_threads(16).build().unwrap();
-    let trades = pool_rayon.install(|| {
+    let mut trades = pool_rayon.install(|| {
         candles
             .par_iter()
             .map(|c| {
@@ -84,18 +112,59 @@ pub fn run_trader_back_test(app: &mut Application) -> anyhow::Result<()> {
             .collect::<Vec<_>>()
     });
 
-    // TODO generating position from trades
-
-    //let trades = trader.trades();
-    //
-
-    let trading_plotter = TradingPlotter::new(&trades);
-
-    let plotters = vec![Box::new(trading_plotter) as Box<dyn PlotterIndicatorContext>];
-
-    plot_selection(app.selection.clone(), app.candles_provider.clone_provider(), plotters)?;
-
-    info!("{}", iformat!("Finished backtest, elapsed: {start.elapsed():?}"));
+    // Simulated position from trades, written to repository
+    trades.sort_by_key(|t| t.now);
+    let symbol_minutes = &app.selection.candles_selection.symbol_minutes;
+    let register = TraderRegister::new(Position::new_from_usd(dec!(1000))).with_symbol_info(app.symbol_info()?);
+    let mut ledger = TradeLedger::open(app.repository.clone(), register, symbol_minutes, true, SOURCE_BACK_TEST)?;
+    for trade in trades.iter() {
+        ledger.record(trade)?;
+    }
+    Ok((trades, ledger))
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        candles_utils::str_to_datetime,
+        checker::Checker,
+        config::selection::Selection,
+        exchange::replay_exchange::tests::replay_exchange,
+        model::{interval::Interval, market::Market},
+        repository::{memory_repository::MemoryRepository, Repository},
+    };
+    use std::{collections::HashMap, sync::Arc};
+
+    #[test]
+    fn back_test_ledger_test() -> anyhow::Result<()> {
+        let repository: Arc<dyn Repository + Send + Sync> = Arc::new(MemoryRepository::new());
+        let exchange = Arc::new(replay_exchange());
+        let candles_selection = CandlesSelection::new(
+            "BTCUSDT",
+            &Interval::M15,
+            &Market::Futures,
+            str_to_datetime("2020-11-10 00:00:00"),
+            str_to_datetime("2020-11-12 23:45:00"),
+        );
+        let checker = Checker::new(&candles_selection.symbol_minutes, repository.as_ref(), exchange.as_ref());
+        let selection = Selection {
+            tacs: HashMap::new(),
+            candles_selection: candles_selection.clone(),
+            image_name: String::new(),
+        };
+        let mut app = Application::new(repository.clone(), exchange.clone(), &checker, selection);
+
+        let (trades, ledger) = back_test_ledger(&mut app)?;
+        let orders = async_std::task::block_on(repository.orders(ledger.position().id))?;
+
+        // Replay exchange info of BTCUSDT futures has tick 0.10 and lot step 0.001
+        assert!(!trades.is_empty());
+        assert!(!orders.is_empty());
+        for order in orders.iter() {
+            assert!((order.executed_amount % dec!(0.001)).is_zero(), "{} isn't on lot step", order.executed_amount);
+            assert!((order.price % dec!(0.1)).is_zero(), "{} isn't on tick", order.price);
+        }
+        Ok(())
+    }
 }
@@ -1,5 +1,5 @@
 use super::{trade_context_provider::TradeContextProvider, trend::Trend, trend_provider::TrendProvider};
-use crate::technicals::ind_type::IndicatorType;
+use crate::{model::interval::Interval, technicals::ind_type::IndicatorType};
 use log::debug;
 
 /// setup
@@ -16,9 +16,11 @@ impl MacdTrend {
 
 impl<'a> TrendProvider for MacdTrend {
     fn trend(&self, trend_context_provider: &mut TradeContextProvider) -> anyhow::Result<Trend> {
-        let mcad = trend_context_provider.indicator(15, &IndicatorType::Macd(34, 72, 17))?.value()?;
-        let mcad_signal = trend_context_provider.indicator(15, &IndicatorType::MacdSignal(34, 72, 17))?.value()?;
-        //let _mcad_divergence = trend_context_provider.indicator(15, &IndicatorType::MacdDivergence(34, 72, 17))?.value()?;
+        let mcad = trend_context_provider.indicator(Interval::M15, &IndicatorType::Macd(34, 72, 17))?.value()?;
+        let mcad_signal = trend_context_provider
+            .indicator(Interval::M15, &IndicatorType::MacdSignal(34, 72, 17))?
+            .value()?;
+        //let _mcad_divergence = trend_context_provider.indicator(Interval::M15, &IndicatorType::MacdDivergence(34, 72, 17))?.value()?;
         let trend = if mcad > mcad_signal { Trend::Bought } else { Trend::Sold };
 
         debug!("trend: {:?} {} > {}", trend_context_provider.now(), mcad, mcad_signal);
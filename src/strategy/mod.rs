@@ -4,6 +4,7 @@ pub mod model;
 pub mod order_executor;
 pub mod topbottom_triangle;
 pub mod trade_context_provider;
+pub mod trade_ledger;
 pub mod trader;
 pub mod trader_register;
 pub mod trend;
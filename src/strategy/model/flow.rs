@@ -1,26 +1,24 @@
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 
+/// Position balances after an order was filled
+#[derive(Clone, Debug, PartialEq)]
 pub struct Flow {
-    // @Column(name = "PROFIT")
-    profit: u64,
-    // @Column(name = "POSITION")
-    position: u64,
-    // @Column(name = "DATE")
-    // @Temporal(TemporalType.TIMESTAMP)
-    date: DateTime<Utc>,
-    // @Column(name = "PRICE", precision = 14, scale = 8)
-    price: Decimal,
-    // @Column(name = "AMOUNT", precision = 14, scale = 8)
-    amount: Decimal,
-    // @Column(name = "USD", precision = 14, scale = 8)
-    usd: Decimal,
-    // @Column(name = "ORIGINAL_USD", precision = 14, scale = 8)
-    original_usd: Decimal,
-    // @Column(name = "BALANCE_AMOUNT", precision = 14, scale = 8)
-    balance_amount: Decimal,
-    // @Column(name = "BALANCE_USD", precision = 14, scale = 8)
-    balance_usd: Decimal,
-    // @Column(name = "STATE")
-    state: String,
+    /// Generated by repository
+    pub id: i64,
+    /// Profit realized by the order, if it closed one
+    pub profit: Option<i64>,
+    pub position: i64,
+    pub date: DateTime<Utc>,
+    pub price: Decimal,
+    /// Coin amount of order
+    pub amount: Decimal,
+    /// USD amount of order
+    pub usd: Decimal,
+    /// USD balance the position started with
+    pub original_usd: Decimal,
+    pub balance_amount: Decimal,
+    pub balance_usd: Decimal,
+    /// Position state after order, `STATE_BOUGHT` or `STATE_SOLD`
+    pub state: String,
 }
@@ -1,4 +1,4 @@
-// pub mod flow;
-// pub mod order;
-// pub mod position;
-// pub mod profit;
+pub mod flow;
+pub mod order;
+pub mod position;
+pub mod profit;
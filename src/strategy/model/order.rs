@@ -1,44 +1,38 @@
+use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 
+pub static SIDE_BUY: &str = "buy";
+pub static SIDE_SELL: &str = "sell";
+
+pub static ORDER_TYPE_MARKET: &str = "market";
+
+/// Order sent to exchange (or simulated), with its fill state
+#[derive(Clone, Debug, PartialEq)]
 pub struct Order {
-    //@Column(name = "ID")
-    id: u64,
-    //@Column(name = "SYMBOL")
-    symbol: String,
-    //@Column(name = "EXCHANGE")
-    exchange: String,
-    //@Column(name = "PRICE", precision = 14, scale = 8)
-    price: Decimal,
-    //@Column(name = "AVG_EXECUTION_PRICE", precision = 14, scale = 8)
-    avg_execution_price: Decimal,
-    // buy / sell
-    //@Column(name = "SIDE")
-    side: String,
-    // exchange limit
-    //@Column(name = "TYPE")
-    type_o: String,
-    //@Column(name = "IS_LIVE")
-    is_live: bool,
-    //@Column(name = "IS_CANCELLED")
-    is_cancelled: bool,
-    //@Column(name = "IS_HIDDEN")
-    is_hidden: bool,
-    //@Column(name = "WAS_FORCED")
-    was_forced: bool,
-    //@Column(name = "ORIGINAL_AMOUNT", precision = 18, scale = 8)
-    original_amount: Decimal,
-    //@Column(name = "REMAINING_AMOUNT", precision = 18, scale = 8)
-    remaining_amount: Decimal,
-    //@Column(name = "EXECUTED_AMOUNT", precision = 18, scale = 8)
-    executed_amount: Decimal,
-    //@Column(name = "SOURCE")
-    source: String,
-    //@Column(name = "ORDER_ID")
-    order_id: u64,
-    //@Column(name = "POSITION")
-    position: u64,
-    //@Column(name = "PROFIT")
-    profit: u64,
-    //@Column(name = "SIMULATED")
-    simulation: bool,
+    /// Generated by repository
+    pub id: i64,
+    pub symbol: String,
+    pub exchange: String,
+    pub date: DateTime<Utc>,
+    pub price: Decimal,
+    pub avg_execution_price: Decimal,
+    /// `SIDE_BUY` or `SIDE_SELL`
+    pub side: String,
+    /// Exchange order type, e.g. `ORDER_TYPE_MARKET`
+    pub order_type: String,
+    pub is_live: bool,
+    pub is_cancelled: bool,
+    pub is_hidden: bool,
+    pub was_forced: bool,
+    pub original_amount: Decimal,
+    pub remaining_amount: Decimal,
+    pub executed_amount: Decimal,
+    /// Run that created order (e.g. back-test, live)
+    pub source: String,
+    /// Exchange order id, none when simulated or not sent yet
+    pub order_id: Option<i64>,
+    pub position: i64,
+    /// Profit closed by this order
+    pub profit: Option<i64>,
+    pub simulation: bool,
 }
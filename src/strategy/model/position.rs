@@ -1,20 +1,22 @@
 use rust_decimal::Decimal;
 
+/// Balances of a symbol traded by a run, simulated runs have positions apart from live ones
+#[derive(Clone, Debug, PartialEq)]
 pub struct Position {
-    id: u64,
-    // position SOLD / BOUGHT
-    state: String,
-    // @Column(name = "BALANCE", precision = 5, scale = 2)
-    // percent 
-    balance: Decimal,
-    // @Column(name = "BALANCE_USD", precision = 18, scale = 8)
-    balance_usd: Decimal,
-    // @Column(name = "BALANCE_CURRENCY", precision = 18, scale = 8)
-    balance_amount: Decimal,
-    // @Column(name = "BALANCE_MINIMUN", precision = 18, scale = 8)
-    balance_minimun: Decimal,
-    symbol: String,
-    simulation: bool,
-    active: bool,
-    current_profit: u64,
+    /// Generated by repository
+    pub id: i64,
+    /// `STATE_BOUGHT` or `STATE_SOLD`
+    pub state: String,
+    /// Percent of balance traded
+    pub balance: Decimal,
+    pub balance_usd: Decimal,
+    /// Balance of symbol coin
+    pub balance_amount: Decimal,
+    /// Balance kept out of trades
+    pub balance_minimum: Decimal,
+    pub symbol: String,
+    pub simulation: bool,
+    pub active: bool,
+    /// Last profit realized
+    pub current_profit: Option<i64>,
 }
@@ -1,32 +1,26 @@
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 
+/// Profit realized by a sell order closing a buy order
+#[derive(Clone, Debug, PartialEq)]
 pub struct Profit {
-    id: u64,
-    symbol: String,
-    position: u64,
-    sold_date: DateTime<Utc>,
-    // @Column(name = "SOLD_PRICE", precision = 14, scale = 8)
-    sold_price: Decimal,
-    // @Column(name = "SOLD_AMOUNT", precision = 14, scale = 8)
-    sold_amount: Decimal,
-    bought_date: DateTime<Utc>,
-    // @Column(name = "BOUGHT_PRICE", precision = 14, scale = 8)
-    bought_price: Decimal,
-    // @Column(name = "BOUGHT_AMOUNT", precision = 14, scale = 8)
-    bought_amount: Decimal,
-    // @Column(name = "BOUGHT_USD", precision = 14, scale = 8)
-    bought_usd: Decimal,
-    // @Column(name = "SOLD_USD", precision = 14, scale = 8)
-    sold_usd: Decimal,
-    // @Column(name = "BOUGHT_ORDER")
-    bought_order: u64,
-    // @Column(name = "SOLD_ORDER")
-    sold_order: u64,
-    // @Column(name = "PROFIT", precision = 14, scale = 8)
-    profit: Decimal,
-    // @Column(name = "SIMULATED")
-    simulation: bool,
-    // @Column(name = "PERCENT", precision = 5, scale = 2)
-    percent: Decimal,
+    /// Generated by repository
+    pub id: i64,
+    pub symbol: String,
+    pub position: i64,
+    pub sold_date: DateTime<Utc>,
+    pub sold_price: Decimal,
+    pub sold_amount: Decimal,
+    pub bought_date: DateTime<Utc>,
+    pub bought_price: Decimal,
+    pub bought_amount: Decimal,
+    pub bought_usd: Decimal,
+    pub sold_usd: Decimal,
+    pub bought_order: i64,
+    pub sold_order: i64,
+    /// Sold USD minus bought USD
+    pub profit: Decimal,
+    pub simulation: bool,
+    /// Profit percent of bought USD
+    pub percent: Decimal,
 }
@@ -1,24 +1,26 @@
 use crate::{
     application::candles_provider::{CandlesProvider, CandlesProviderBuffer, CandlesProviderSelection, CandlesProviderVec},
-    config::candles_selection::CandlesSelection,
-    model::candle::Candle,
-    technicals::{ind_provider::IndicatorProvider, ind_type::IndicatorType, indicator::Indicator},
+    config::{candles_selection::CandlesSelection, symbol_minutes::SymbolMinutes},
+    model::{candle::Candle, interval::Interval, market::Market},
+    technicals::{futures_tac::FuturesTac, ind_provider::IndicatorProvider, ind_type::IndicatorType, indicator::Indicator},
 };
 use chrono::{DateTime, Utc};
 
 #[derive(Clone)]
 pub struct TradeContextProvider {
     symbol: String,
+    market: Market,
     indicator_provider: IndicatorProvider,
     candles_provider: CandlesProviderBuffer,
     now: DateTime<Utc>,
-    candles_opt: Option<(DateTime<Utc>, u32, Vec<Candle>)>,
+    candles_opt: Option<(DateTime<Utc>, Interval, Vec<Candle>)>,
 }
 
 impl TradeContextProvider {
-    pub fn new(symbol: &str, indicator_provider: IndicatorProvider, candles_provider: CandlesProviderBuffer) -> Self {
+    pub fn new(symbol: &str, market: &Market, indicator_provider: IndicatorProvider, candles_provider: CandlesProviderBuffer) -> Self {
         Self {
             symbol: symbol.to_string(),
+            market: *market,
             indicator_provider,
             candles_provider,
             now: Utc::now(),
@@ -34,21 +36,33 @@ impl TradeContextProvider {
         self.now
     }
 
-    pub fn indicator(&mut self, minutes: u32, i_type: &IndicatorType) -> anyhow::Result<&Indicator> {
+    /// Last candles of interval until now, kept while now doesn't change
+    fn candles(&mut self, interval: Interval) -> &Vec<Candle> {
         let now = self.now;
-        self.candles_opt = self.candles_opt.take().filter(|e| e.0 == now && e.1 == minutes);
+        self.candles_opt = self.candles_opt.take().filter(|e| e.0 == now && e.1 == interval);
 
-        let candles_selection = CandlesSelection::last_n(&self.symbol, &minutes, 200, now);
+        let candles_selection = CandlesSelection::last_n(&self.symbol, &interval, &self.market, 200, now);
         let mut candles_provider_selection = CandlesProviderSelection::new(self.candles_provider.clone(), candles_selection);
 
         let now_candles = self.candles_opt.get_or_insert_with(|| {
             let candles = candles_provider_selection.candles().unwrap();
-            (now, minutes, candles)
+            (now, interval, candles)
         });
+        &now_candles.2
+    }
 
-        let candles_provider_vec = CandlesProviderVec::new(now_candles.2.as_slice(), 200);
+    pub fn indicator(&mut self, interval: Interval, i_type: &IndicatorType) -> anyhow::Result<&Indicator> {
+        let candles_provider_vec = CandlesProviderVec::new(self.candles(interval).as_slice(), 200);
         let candles_provider = Box::new(candles_provider_vec) as Box<dyn CandlesProvider>;
 
         self.indicator_provider.indicator(self.now, candles_provider, i_type)
     }
+
+    /// Funding rate and open interest aligned to last candles of interval until now, futures market only
+    pub fn futures_tac(&mut self, interval: Interval) -> anyhow::Result<FuturesTac> {
+        let symbol_minutes = SymbolMinutes::new(&self.symbol, &interval, &self.market);
+        let singleton = self.candles_provider.candles_provider_singleton();
+        let candles = self.candles(interval).iter().collect::<Vec<_>>();
+        async_std::task::block_on(singleton.futures_tac(&symbol_minutes, &candles))
+    }
 }
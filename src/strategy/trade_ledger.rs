@@ -0,0 +1,254 @@
+use super::{
+    model::{
+        flow::Flow,
+        order::{Order, ORDER_TYPE_MARKET, SIDE_BUY, SIDE_SELL},
+        position::Position,
+        profit::Profit,
+    },
+    trader_register::{Trade, TraderRegister, STATE_BOUGHT, STATE_SOLD},
+    trend::{Operation, Trend},
+};
+use crate::{
+    config::symbol_minutes::SymbolMinutes,
+    model::exchange_name::ExchangeName,
+    repository::{blocking_repository::BlockingRepository, Repository},
+};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::sync::Arc;
+
+fn state_name(trend: &Trend) -> &'static str {
+    match trend {
+        Trend::Bought => STATE_BOUGHT,
+        Trend::Sold => STATE_SOLD,
+    }
+}
+
+/// Writes trades of a run to repository, so they can be audited later.
+///
+/// Each trade is an order filled at once at trade price, followed by position balances, a balance flow
+/// and, for a sell after a buy, the profit realized. Simulated runs are kept apart from live ones.
+pub struct TradeLedger {
+    repository: BlockingRepository,
+    register: TraderRegister,
+    exchange: ExchangeName,
+    source: String,
+    position: Position,
+    original_usd: Decimal,
+    last_buy: Option<Order>,
+}
+
+impl TradeLedger {
+    /// Opens a position of symbol minutes with balances of register, its orders are on symbol minutes exchange
+    pub fn open(
+        repository: Arc<dyn Repository + Send + Sync>, register: TraderRegister, symbol_minutes: &SymbolMinutes, simulation: bool, source: &str,
+    ) -> anyhow::Result<Self> {
+        let balances = register.position();
+        let mut position = Position {
+            id: 0,
+            state: state_name(balances.state()).to_string(),
+            balance: dec!(100),
+            balance_usd: balances.balance_usd(),
+            balance_amount: balances.balance_coin(),
+            balance_minimum: Decimal::ZERO,
+            symbol: symbol_minutes.symbol.clone(),
+            simulation,
+            active: true,
+            current_profit: None,
+        };
+        let repository = BlockingRepository::new(repository);
+        repository.insert_position(&mut position)?;
+        Ok(Self {
+            repository,
+            original_usd: position.balance_usd,
+            register,
+            exchange: symbol_minutes.exchange,
+            source: source.to_string(),
+            position,
+            last_buy: None,
+        })
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// Registers and writes trade, returns false when it doesn't change position state (e.g. a sell when already sold)
+    pub fn record(&mut self, trade: &Trade) -> anyhow::Result<bool> {
+        if &trade.operation.to_trend() == self.register.position().state() {
+            return Ok(false);
+        }
+        // Filled at symbol tick, as exchange would do
+        let trade = Trade {
+            price: self.register.round_price(&trade.price),
+            ..trade.clone()
+        };
+        let coin_before = self.register.position().balance_coin();
+        let usd_before = self.register.position().balance_usd();
+        self.register.register(trade.clone())?;
+        let balances = self.register.position();
+        let amount = (balances.balance_coin() - coin_before).abs();
+        let usd = (balances.balance_usd() - usd_before).abs();
+
+        let mut order = Order {
+            id: 0,
+            symbol: self.position.symbol.clone(),
+            exchange: self.exchange.to_string(),
+            date: trade.now,
+            price: trade.price,
+            avg_execution_price: trade.price,
+            side: match trade.operation {
+                Operation::Buy => SIDE_BUY.to_string(),
+                Operation::Sell => SIDE_SELL.to_string(),
+            },
+            order_type: ORDER_TYPE_MARKET.to_string(),
+            is_live: false,
+            is_cancelled: false,
+            is_hidden: false,
+            was_forced: false,
+            original_amount: amount,
+            remaining_amount: Decimal::ZERO,
+            executed_amount: amount,
+            source: self.source.clone(),
+            order_id: None,
+            position: self.position.id,
+            profit: None,
+            simulation: self.position.simulation,
+        };
+        self.repository.insert_order(&mut order)?;
+
+        let profit = match (&trade.operation, self.last_buy.take()) {
+            (Operation::Sell, Some(buy)) => {
+                let profit = self.record_profit(&buy, &order, usd)?;
+                order.profit = Some(profit);
+                self.repository.update_order(&order)?;
+                Some(profit)
+            }
+            (Operation::Buy, _) => {
+                self.last_buy = Some(order.clone());
+                None
+            }
+            (Operation::Sell, None) => None,
+        };
+
+        self.position.state = state_name(balances.state()).to_string();
+        self.position.balance_usd = balances.balance_usd();
+        self.position.balance_amount = balances.balance_coin();
+        self.position.current_profit = profit.or(self.position.current_profit);
+        self.repository.update_position(&self.position)?;
+
+        self.repository.insert_flow(&mut Flow {
+            id: 0,
+            profit,
+            position: self.position.id,
+            date: trade.now,
+            price: trade.price,
+            amount,
+            usd,
+            original_usd: self.original_usd,
+            balance_amount: self.position.balance_amount,
+            balance_usd: self.position.balance_usd,
+            state: self.position.state.clone(),
+        })?;
+        Ok(true)
+    }
+
+    fn record_profit(&self, buy: &Order, sell: &Order, sold_usd: Decimal) -> anyhow::Result<i64> {
+        let bought_usd = buy.executed_amount * buy.avg_execution_price;
+        let profit = sold_usd - bought_usd;
+        let percent = if bought_usd.is_zero() {
+            Decimal::ZERO
+        } else {
+            (profit / bought_usd * dec!(100)).round_dp(2)
+        };
+        Ok(self.repository.insert_profit(&mut Profit {
+            id: 0,
+            symbol: self.position.symbol.clone(),
+            position: self.position.id,
+            sold_date: sell.date,
+            sold_price: sell.avg_execution_price,
+            sold_amount: sell.executed_amount,
+            bought_date: buy.date,
+            bought_price: buy.avg_execution_price,
+            bought_amount: buy.executed_amount,
+            bought_usd,
+            sold_usd,
+            bought_order: buy.id,
+            sold_order: sell.id,
+            profit,
+            simulation: self.position.simulation,
+            percent,
+        })?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        candles_utils::str_to_datetime,
+        model::{interval::Interval, market::Market},
+        repository::{memory_repository::MemoryRepository, repository_error::RepositoryError, tests::pg_repository},
+        strategy::trader_register::Position as Balances,
+    };
+
+    /// Buy then sell is recorded as two orders, a profit and two flows; the repeated sell is ignored
+    async fn ledger_recorded(repository: Arc<dyn Repository + Send + Sync>) {
+        let mut ledger = TradeLedger::open(
+            repository.clone(),
+            TraderRegister::new(Balances::new_from_usd(dec!(1000))),
+            &SymbolMinutes::new("LDGUSDT", &Interval::M15, &Market::Futures),
+            true,
+            "back-test",
+        )
+        .unwrap();
+        let buy = Trade::new(Operation::Buy, str_to_datetime("2020-11-11 10:00:00"), dec!(100));
+        let sell = Trade::new(Operation::Sell, str_to_datetime("2020-11-11 11:00:00"), dec!(110));
+        let recorded = [ledger.record(&buy).unwrap(), ledger.record(&sell).unwrap(), ledger.record(&sell).unwrap()];
+
+        let id = ledger.position().id;
+        let positions = repository.positions("LDGUSDT", true).await.unwrap();
+        let live_positions = repository.positions("LDGUSDT", false).await.unwrap();
+        let orders = repository.orders(id).await.unwrap();
+        let profits = repository.profits(id).await.unwrap();
+        let flows = repository.flows(id).await.unwrap();
+        // Live order on simulated position is refused
+        let mut live_order = Order {
+            simulation: false,
+            ..orders[0].clone()
+        };
+        let live_insert = repository.insert_order(&mut live_order).await;
+        repository.delete_position(id).await.unwrap();
+        let deleted_update = repository.update_position(ledger.position()).await;
+
+        assert_eq!(recorded, [true, true, false]);
+        assert_eq!(positions, vec![ledger.position().clone()]);
+        assert_eq!(positions[0].balance_usd, dec!(1100));
+        assert_eq!(positions[0].state, STATE_SOLD);
+        assert!(live_positions.iter().all(|p| p.id != id));
+        assert_eq!(orders.iter().map(|o| o.side.as_str()).collect::<Vec<_>>(), vec![SIDE_BUY, SIDE_SELL]);
+        assert_eq!(orders[0].executed_amount, dec!(10));
+        assert!(orders.iter().all(|o| o.exchange == ExchangeName::Binance.as_str()));
+        assert_eq!(profits.len(), 1);
+        assert_eq!((profits[0].profit, profits[0].percent), (dec!(100), dec!(10)));
+        assert_eq!((profits[0].bought_order, profits[0].sold_order), (orders[0].id, orders[1].id));
+        assert_eq!(orders[1].profit, Some(profits[0].id));
+        assert_eq!(positions[0].current_profit, Some(profits[0].id));
+        assert_eq!(flows.iter().map(|f| f.profit).collect::<Vec<_>>(), vec![None, Some(profits[0].id)]);
+        assert_eq!(flows[1].original_usd, dec!(1000));
+        assert!(matches!(live_insert, Err(RepositoryError::Constraint(_))));
+        assert!(matches!(deleted_update, Err(RepositoryError::NotFound(_))));
+        assert!(repository.orders(id).await.unwrap().is_empty());
+    }
+
+    #[async_std::test]
+    async fn ledger_test() {
+        ledger_recorded(Arc::new(MemoryRepository::new())).await;
+    }
+
+    #[async_std::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn pg_ledger_test() {
+        ledger_recorded(Arc::new(pg_repository().await)).await;
+    }
+}
@@ -1,4 +1,5 @@
 use super::trend::{Operation, Trend};
+use crate::model::symbol_info::SymbolInfo;
 use chrono::{DateTime, Utc};
 use colored::Colorize;
 use log::debug;
@@ -33,6 +34,14 @@ impl Position {
     pub fn state(&self) -> &Trend {
         &self.state
     }
+
+    pub fn balance_coin(&self) -> Decimal {
+        self.balance_coin
+    }
+
+    pub fn balance_usd(&self) -> Decimal {
+        self.balance_usd
+    }
 }
 
 #[derive(Clone)]
@@ -51,6 +60,7 @@ impl Trade {
 pub struct TraderRegister {
     position: Position,
     trades: Vec<Trade>,
+    symbol_info: Option<SymbolInfo>,
 }
 
 impl TraderRegister {
@@ -58,23 +68,36 @@ impl TraderRegister {
         Self {
             position: postion,
             trades: Vec::new(),
+            symbol_info: None,
         }
     }
 
-    pub fn register(&mut self, trade: Trade) {
+    /// Quantities follow symbol lot step and orders are checked against its filters
+    pub fn with_symbol_info(mut self, symbol_info: SymbolInfo) -> Self {
+        self.symbol_info = Some(symbol_info);
+        self
+    }
+
+    /// Registers trade, it fails when the order would be refused by symbol filters
+    pub fn register(&mut self, trade: Trade) -> anyhow::Result<()> {
         match trade.operation {
             // I have USB and must buy coin
             Operation::Buy => {
-                let quantity_usd = self.position.balance_usd;
-                let quantity_coin = quantity_usd / trade.price;
+                let quantity_coin = self.quantity(&(self.position.balance_usd / trade.price));
+                let quantity_usd = match self.symbol_info {
+                    Some(_) => quantity_coin * trade.price,
+                    None => self.position.balance_usd,
+                };
+                self.check_order(&trade.price, &quantity_coin)?;
 
                 self.position.balance_coin += quantity_coin;
                 self.position.balance_usd -= quantity_usd;
             }
             // I have USB and must buy coin
             Operation::Sell => {
-                let quantity_coin = self.position.balance_coin;
+                let quantity_coin = self.quantity(&self.position.balance_coin);
                 let quantity_usd = quantity_coin * trade.price;
+                self.check_order(&trade.price, &quantity_coin)?;
 
                 self.position.balance_coin -= quantity_coin;
                 self.position.balance_usd += quantity_usd;
@@ -93,6 +116,29 @@ impl TraderRegister {
         debug!("{}", message);
 
         self.trades.push(trade);
+        Ok(())
+    }
+
+    /// Price rounded to symbol tick, unchanged without symbol info
+    pub fn round_price(&self, price: &Decimal) -> Decimal {
+        match &self.symbol_info {
+            Some(symbol_info) => symbol_info.round_price(price),
+            None => *price,
+        }
+    }
+
+    fn quantity(&self, quantity: &Decimal) -> Decimal {
+        match &self.symbol_info {
+            Some(symbol_info) => symbol_info.round_quantity(quantity),
+            None => *quantity,
+        }
+    }
+
+    fn check_order(&self, price: &Decimal, quantity: &Decimal) -> anyhow::Result<()> {
+        match &self.symbol_info {
+            Some(symbol_info) => symbol_info.check_order(price, quantity),
+            None => Ok(()),
+        }
     }
 
     pub fn position(&self) -> &Position {
@@ -103,3 +149,46 @@ impl TraderRegister {
         self.trades.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        candles_utils::str_to_datetime,
+        model::{market::Market, symbol_info::tests::symbol_info},
+    };
+
+    #[test]
+    fn register_test() {
+        let now = str_to_datetime("2020-11-11 10:00:00");
+        let mut register = TraderRegister::new(Position::new_from_usd(dec!(1000)));
+        register.register(Trade::new(Operation::Buy, now, dec!(15321.47))).unwrap();
+        assert_eq!(register.position().balance_coin, dec!(0.06526788));
+        assert_eq!(register.position().balance_usd, dec!(0));
+
+        let mut register = TraderRegister::new(Position::new_from_coin(dec!(0.065)));
+        register.register(Trade::new(Operation::Sell, now, dec!(15400))).unwrap();
+        assert_eq!(register.position().balance_usd, dec!(1001));
+    }
+
+    #[test]
+    fn register_symbol_info_test() {
+        let now = str_to_datetime("2020-11-11 10:00:00");
+        let mut register = TraderRegister::new(Position::new_from_usd(dec!(1000))).with_symbol_info(symbol_info("BTCUSDT", &Market::Futures));
+        assert_eq!(register.round_price(&dec!(15321.4761)), dec!(15321.48));
+
+        // Coin quantity follows lot step 0.001, the remaining USD stays on balance
+        register.register(Trade::new(Operation::Buy, now, dec!(15321.47))).unwrap();
+        assert_eq!(register.position().balance_coin, dec!(0.065));
+        assert_eq!(register.position().balance_usd, dec!(4.10445));
+        assert_eq!(register.position().state(), &Trend::Bought);
+
+        register.register(Trade::new(Operation::Sell, now, dec!(15400))).unwrap();
+        assert_eq!(register.position().balance_coin, dec!(0));
+        assert_eq!(register.position().balance_usd, dec!(1005.10445));
+
+        // 1005 USD don't buy the minimum quantity
+        assert!(register.register(Trade::new(Operation::Buy, now, dec!(2000000))).is_err());
+        assert_eq!(register.trades().len(), 2);
+    }
+}
@@ -0,0 +1,71 @@
+use super::{indicator_plotter::IndicatorPlotter, theme_plotter::ThemePlotter};
+use crate::{
+    config::selection::Selection,
+    technicals::{futures_tac::FuturesTac, indicator::Indicator},
+};
+use plotters::{coord::Shift, prelude::*};
+use plotters_bitmap::bitmap_pixel::RGBPixel;
+
+/// Plots funding rate (percent) and open interest, one over the other
+pub struct FuturesPlotter {
+    futures_tac: FuturesTac,
+}
+
+impl FuturesPlotter {
+    pub fn new(futures_tac: FuturesTac) -> Self {
+        FuturesPlotter { futures_tac }
+    }
+}
+
+impl IndicatorPlotter for FuturesPlotter {
+    fn plot(
+        &self, selection: &Selection, _upper: &DrawingArea<BitMapBackend<RGBPixel>, Shift>, lower: &DrawingArea<BitMapBackend<RGBPixel>, Shift>,
+    ) -> anyhow::Result<()> {
+        let (funding_area, open_interest_area) = lower.split_vertically((50).percent());
+        plot_line(
+            selection,
+            &funding_area,
+            self.futures_tac.funding_rate(),
+            100.0,
+            "funding rate %",
+            RGBColor(0, 128, 128),
+        )?;
+        plot_line(
+            selection,
+            &open_interest_area,
+            self.futures_tac.open_interest(),
+            1.0,
+            "open interest",
+            RGBColor(128, 64, 0),
+        )?;
+        Ok(())
+    }
+}
+
+fn plot_line(
+    selection: &Selection, area: &DrawingArea<BitMapBackend<RGBPixel>, Shift>, indicator: &Indicator, scale: f64, caption: &str, color: RGBColor,
+) -> anyhow::Result<()> {
+    if indicator.series.is_empty() {
+        return Ok(());
+    }
+    let from_date = selection.candles_selection.start_time;
+    let to_date = selection.candles_selection.end_time;
+
+    let min = indicator.series.iter().fold(f64::MAX, |acc, s| acc.min(s.value)) * scale;
+    let max = indicator.series.iter().fold(f64::MIN, |acc, s| acc.max(s.value)) * scale;
+    // Constant values still need a range
+    let margin = ((max - min) * 0.05).max(max.abs() * 0.01).max(f64::EPSILON);
+
+    let font = FontDesc::new(FontFamily::Name("sans-serif"), 16.0, FontStyle::Normal).color(&ThemePlotter::fore_ground());
+    let mut chart_context = ChartBuilder::on(area)
+        .set_label_area_size(LabelAreaPosition::Left, 30)
+        .set_label_area_size(LabelAreaPosition::Right, 80)
+        .y_label_area_size(80)
+        .x_label_area_size(30)
+        .caption(caption, font)
+        .build_cartesian_2d(from_date..to_date, (min - margin)..(max + margin))?;
+
+    chart_context.configure_mesh().light_line_style(ThemePlotter::back_ground()).draw()?;
+    chart_context.draw_series(LineSeries::new(indicator.series.iter().map(|s| (s.date_time, s.value * scale)), &color))?;
+    Ok(())
+}
@@ -1,4 +1,5 @@
 pub mod candles_plotter;
+pub mod futures_plotter;
 pub mod indicator_plotter;
 pub mod line_ind_plotter;
 pub mod macd_plotter;
@@ -79,8 +79,12 @@ impl<'a> Plotter<'a> {
 
         lower.fill(&bg_color)?;
 
-        for plotter_ind in self.plotters_ind.iter() {
-            plotter_ind.plot(&self.selection, &upper, &lower)?;
+        // Lower indicators share the lower area, one below the other
+        if !self.plotters_ind.is_empty() {
+            let lowers = lower.split_evenly((self.plotters_ind.len(), 1));
+            for (plotter_ind, lower) in self.plotters_ind.iter().zip(lowers.iter()) {
+                plotter_ind.plot(&self.selection, &upper, lower)?;
+            }
         }
 
         // for plotters_ind_upper_ind in self.plotters_ind_lower.iter() {
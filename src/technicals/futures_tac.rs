@@ -0,0 +1,144 @@
+use super::technical::{TechnicalDefinition, TechnicalIndicators};
+use crate::{
+    config::definition::TacDefinition,
+    model::{candle::Candle, funding_rate::FundingRate, open_interest::OpenInterest},
+    technicals::indicator::Indicator,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::HashMap;
+
+pub const FUTURES_TAC: &str = "futures";
+pub const FUNDING_RATE_IND: &str = "funding_rate";
+pub const OPEN_INTEREST_IND: &str = "open_interest";
+
+/// Funding rate and open interest aligned to candles close time.
+///
+/// Each candle gets the last value known until its close: the funding rate in effect and the last open interest snapshot.
+/// Candles before the first value have no serie.
+#[derive(Clone)]
+pub struct FuturesTac {
+    pub indicators: HashMap<String, Indicator>,
+}
+
+impl TechnicalDefinition for FuturesTac {
+    fn definition() -> TacDefinition {
+        TacDefinition::new(FUTURES_TAC, &[FUNDING_RATE_IND, OPEN_INTEREST_IND])
+    }
+}
+
+impl TechnicalIndicators for FuturesTac {
+    fn indicators(&self) -> &HashMap<String, Indicator> {
+        &self.indicators
+    }
+
+    fn main_indicator(&self) -> &Indicator {
+        self.indicators.get(FUNDING_RATE_IND).unwrap()
+    }
+}
+
+impl FuturesTac {
+    /// Candles, funding rates and open interests must be sorted by time
+    pub fn new(candles: &[&Candle], funding_rates: &[FundingRate], open_interests: &[OpenInterest]) -> Self {
+        let mut funding_rate = Indicator::new(FUNDING_RATE_IND, candles.len());
+        for (candle, f) in candles.iter().zip(align_to_candles(candles, funding_rates, |f| f.funding_time)) {
+            if let Some(f) = f {
+                funding_rate.push_serie(candle.close_time, f.funding_rate.to_f64().unwrap());
+            }
+        }
+
+        let mut open_interest = Indicator::new(OPEN_INTEREST_IND, candles.len());
+        for (candle, o) in candles.iter().zip(align_to_candles(candles, open_interests, |o| o.time)) {
+            if let Some(o) = o {
+                open_interest.push_serie(candle.close_time, o.sum_open_interest.to_f64().unwrap());
+            }
+        }
+
+        let mut indicators = HashMap::new();
+        indicators.insert(funding_rate.name.clone(), funding_rate);
+        indicators.insert(open_interest.name.clone(), open_interest);
+        FuturesTac { indicators }
+    }
+
+    pub fn funding_rate(&self) -> &Indicator {
+        self.indicators.get(FUNDING_RATE_IND).unwrap()
+    }
+
+    pub fn open_interest(&self) -> &Indicator {
+        self.indicators.get(OPEN_INTEREST_IND).unwrap()
+    }
+}
+
+/// For each candle the last item with time until candle close time, candles and items sorted by time
+pub fn align_to_candles<'a, T>(candles: &[&Candle], items: &'a [T], time: impl Fn(&T) -> DateTime<Utc>) -> Vec<Option<&'a T>> {
+    let mut next = 0;
+    candles
+        .iter()
+        .map(|candle| {
+            while next < items.len() && time(&items[next]) <= candle.close_time {
+                next += 1;
+            }
+            next.checked_sub(1).map(|i| &items[i])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        candles_utils::{fdec, str_to_datetime},
+        model::{exchange_name::ExchangeName, interval::Interval},
+    };
+    use rust_decimal_macros::dec;
+
+    fn candle(open_time: &str, close_time: &str) -> Candle {
+        Candle::new(0, open_time, close_time, "BTCUSDT", 15, fdec(1.0), fdec(1.0), fdec(1.0), fdec(1.0), fdec(1.0))
+    }
+
+    fn funding_rate(funding_time: &str, rate: f64) -> FundingRate {
+        FundingRate {
+            exchange: ExchangeName::Binance,
+            symbol: "BTCUSDT".into(),
+            funding_time: str_to_datetime(funding_time),
+            funding_rate: fdec(rate),
+            mark_price: None,
+        }
+    }
+
+    #[test]
+    fn futures_tac_test() {
+        let candles = [
+            candle("2020-11-10 23:30:00", "2020-11-10 23:44:59"),
+            candle("2020-11-10 23:45:00", "2020-11-10 23:59:59"),
+            candle("2020-11-11 00:00:00", "2020-11-11 00:14:59"),
+            candle("2020-11-11 08:00:00", "2020-11-11 08:14:59"),
+        ];
+        let candles = candles.iter().collect::<Vec<_>>();
+        let funding_rates = [
+            funding_rate("2020-11-10 16:00:00", 0.0001),
+            funding_rate("2020-11-11 00:00:00", 0.0003),
+            funding_rate("2020-11-11 08:00:00", -0.0002),
+        ];
+        let open_interests = [OpenInterest {
+            exchange: ExchangeName::Binance,
+            symbol: "BTCUSDT".into(),
+            interval: Interval::M15,
+            time: str_to_datetime("2020-11-10 23:45:00"),
+            sum_open_interest: dec!(31000.5),
+            sum_open_interest_value: dec!(480000000),
+        }];
+
+        let futures_tac = FuturesTac::new(candles.as_slice(), &funding_rates, &open_interests);
+
+        let rates = futures_tac.funding_rate().series.iter().map(|s| s.value).collect::<Vec<_>>();
+        assert_eq!(rates, vec![0.0001, 0.0001, 0.0003, -0.0002]);
+        assert_eq!(futures_tac.funding_rate().series[2].date_time, str_to_datetime("2020-11-11 00:14:59"));
+
+        // First candle closed before the first snapshot
+        let open_interest = &futures_tac.open_interest().series;
+        assert_eq!(open_interest.len(), 3);
+        assert_eq!(open_interest[0].date_time, str_to_datetime("2020-11-10 23:59:59"));
+        assert_eq!(open_interest[0].value, 31000.5);
+    }
+}
@@ -7,24 +7,25 @@ const DEC_4: Decimal = dec!(4);
 
 /// Heikin-Ashi Candle Calculations
 pub fn heikin_ashi(candles: &[&Candle]) -> Vec<Candle> {
-    if candles.is_empty() {
-        return Vec::new();
-    }
-    let mut ha_candles = Vec::with_capacity(candles.len());
-    fn add_ha(has: &mut Vec<Candle>, hac: Candle) -> (Decimal, Decimal) {
-        let prev_oc = (hac.open, hac.close);
-        has.push(hac);
-        prev_oc
-    }
+    let mut heikin_ashi = HeikinAshi::default();
+    candles.iter().map(|c| heikin_ashi.next(c)).collect()
+}
 
-    let first_ha = heikin_ashi_first(&candles[0]);
-    let mut prev_oc = add_ha(&mut ha_candles, first_ha);
+/// Heikin-Ashi candles one at a time, open and close of previous one kept, so candles can be read page by page
+#[derive(Default)]
+pub struct HeikinAshi {
+    prev_oc: Option<(Decimal, Decimal)>,
+}
 
-    for candle in &candles[1..] {
-        let ha = heikin_ashi_candles(prev_oc.0, prev_oc.1, &candle);
-        prev_oc = add_ha(&mut ha_candles, ha);
+impl HeikinAshi {
+    pub fn next(&mut self, candle: &Candle) -> Candle {
+        let ha = match self.prev_oc {
+            None => heikin_ashi_first(candle),
+            Some((prev_open, prev_close)) => heikin_ashi_candles(prev_open, prev_close, candle),
+        };
+        self.prev_oc = Some((ha.open, ha.close));
+        ha
     }
-    ha_candles
 }
 
 /// First Heikin-Ashi Candle Calculations
@@ -1,4 +1,5 @@
 pub mod ema_tac;
+pub mod futures_tac;
 pub mod heikin_ashi;
 pub mod ind_group;
 pub mod ind_provider;
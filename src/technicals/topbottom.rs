@@ -172,6 +172,8 @@ pub mod tests {
             low: dec!(100.0),
             close: dec!(100.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c2 = Candle {
@@ -185,6 +187,8 @@ pub mod tests {
             low: dec!(102.0),
             close: dec!(102.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c3 = Candle {
@@ -198,6 +202,8 @@ pub mod tests {
             low: dec!(103.0),
             close: dec!(103.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c4 = Candle {
@@ -211,6 +217,8 @@ pub mod tests {
             low: dec!(104.0),
             close: dec!(104.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c5 = Candle {
@@ -224,6 +232,8 @@ pub mod tests {
             low: dec!(105.0),
             close: dec!(105.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c6 = Candle {
@@ -237,6 +247,8 @@ pub mod tests {
             low: dec!(106.0),
             close: dec!(106.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c7 = Candle {
@@ -250,6 +262,8 @@ pub mod tests {
             low: dec!(107.0),
             close: dec!(107.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c8 = Candle {
@@ -263,6 +277,8 @@ pub mod tests {
             low: dec!(108.0),
             close: dec!(108.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c9 = Candle {
@@ -276,6 +292,8 @@ pub mod tests {
             low: dec!(107.0),
             close: dec!(107.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c10 = Candle {
@@ -289,6 +307,8 @@ pub mod tests {
             low: dec!(106.0),
             close: dec!(106.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c11 = Candle {
@@ -302,6 +322,8 @@ pub mod tests {
             low: dec!(105.0),
             close: dec!(105.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c12 = Candle {
@@ -315,6 +337,8 @@ pub mod tests {
             low: dec!(104.0),
             close: dec!(104.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c13 = Candle {
@@ -328,6 +352,8 @@ pub mod tests {
             low: dec!(103.0),
             close: dec!(103.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c14 = Candle {
@@ -341,6 +367,8 @@ pub mod tests {
             low: dec!(102.0),
             close: dec!(102.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c15 = Candle {
@@ -354,6 +382,8 @@ pub mod tests {
             low: dec!(101.0),
             close: dec!(101.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c16 = Candle {
@@ -367,6 +397,8 @@ pub mod tests {
             low: dec!(100.0),
             close: dec!(100.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let c17 = Candle {
@@ -380,6 +412,8 @@ pub mod tests {
             low: dec!(99.0),
             close: dec!(99.0),
             volume: dec!(100.0),
+            market: "futures".into(),
+            exchange: "binance".into(),
         };
 
         let candles = [&c1, &c2, &c3, &c4, &c5, &c6, &c7, &c8, &c9, &c10, &c11, &c12, &c13, &c14, &c15, &c16, &c17];
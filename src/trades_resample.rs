@@ -0,0 +1,201 @@
+use crate::model::{agg_trade::AggTrade, candle::Candle, interval::Interval, market::Market};
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use ifmt::iformat;
+use rust_decimal::Decimal;
+use std::{collections::HashMap, fmt, str::FromStr};
+
+/// Candle period built from trades: an exchange interval (calendar aware) or any count of seconds, minutes, hours or days
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Bucket {
+    Interval(Interval),
+    Seconds(i64),
+}
+
+impl Bucket {
+    pub fn interval(&self) -> Option<Interval> {
+        match self {
+            Bucket::Interval(interval) => Some(*interval),
+            Bucket::Seconds(_) => None,
+        }
+    }
+
+    /// Open time of bucket containing date time, fixed buckets are aligned to epoch
+    pub fn open_trunc(&self, date_time: &DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Bucket::Interval(interval) => interval.open_trunc(date_time),
+            Bucket::Seconds(seconds) => Utc.timestamp_opt(date_time.timestamp().div_euclid(*seconds) * seconds, 0).unwrap(),
+        }
+    }
+
+    pub fn next_open(&self, open: &DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Bucket::Interval(interval) => interval.next_open(open),
+            Bucket::Seconds(seconds) => self.open_trunc(open) + Duration::seconds(*seconds),
+        }
+    }
+
+    /// Candle minutes, fractional below one minute
+    pub fn minutes(&self) -> Decimal {
+        match self {
+            Bucket::Interval(interval) => Decimal::from(interval.minutes()),
+            Bucket::Seconds(seconds) => Decimal::from(*seconds) / Decimal::from(60),
+        }
+    }
+}
+
+impl fmt::Display for Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Bucket::Interval(interval) => write!(f, "{}", interval),
+            Bucket::Seconds(seconds) => write!(f, "{}s", seconds),
+        }
+    }
+}
+
+impl FromStr for Bucket {
+    type Err = anyhow::Error;
+
+    /// Parses exchange interval code (e.g. "15m", "1M") or count with unit s, m, h or d (e.g. "45s", "2m")
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Ok(interval) = Interval::from_str(value) {
+            return Ok(Bucket::Interval(interval));
+        }
+        let unit = value.chars().last().ok_or_else(|| anyhow!("Empty bucket"))?;
+        let count = value[..value.len() - unit.len_utf8()]
+            .parse::<i64>()
+            .map_err(|_| anyhow!("Bucket {} is not valid (e.g. 45s, 2m, 1h, 15m)", value))?;
+        let seconds = match unit {
+            's' => count,
+            'm' => count * 60,
+            'h' => count * 3600,
+            'd' => count * 86400,
+            _ => bail!("Bucket {} unit must be s, m, h or d", value),
+        };
+        if seconds <= 0 {
+            bail!("Bucket {} must be positive", value);
+        }
+        Ok(Bucket::Seconds(seconds))
+    }
+}
+
+/// Builds candles from Binance aggregate trades sorted by time, buckets without trades have no candle
+pub fn trades_to_candles(trades: &[AggTrade], symbol: &str, market: &Market, bucket: &Bucket) -> Vec<Candle> {
+    let mut candles: Vec<Candle> = Vec::new();
+    for trade in trades.iter() {
+        let open_time = bucket.open_trunc(&trade.time);
+        match candles.last_mut() {
+            Some(candle) if candle.open_time == open_time => {
+                candle.high = candle.high.max(trade.price);
+                candle.low = candle.low.min(trade.price);
+                candle.close = trade.price;
+                candle.volume += trade.quantity;
+            }
+            _ => candles.push(Candle {
+                open_time,
+                close_time: bucket.next_open(&open_time) - Duration::seconds(1),
+                id: Decimal::ZERO,
+                symbol: symbol.to_string(),
+                minutes: bucket.minutes(),
+                open: trade.price,
+                high: trade.price,
+                low: trade.price,
+                close: trade.price,
+                volume: trade.quantity,
+                market: market.to_string(),
+                exchange: trade.exchange.to_string(),
+            }),
+        }
+    }
+    candles
+}
+
+/// Differences between candles resampled from trades and klines of the same interval, by open time.
+///
+/// Klines without resampled candle are ignored, trades may cover only part of klines range.
+pub fn candles_mismatches(resampled: &[Candle], klines: &[Candle]) -> Vec<String> {
+    let klines = klines.iter().map(|k| (k.open_time, k)).collect::<HashMap<_, _>>();
+    resampled
+        .iter()
+        .filter_map(|candle| match klines.get(&candle.open_time) {
+            None => Some(iformat!("{candle.open_time} no kline")),
+            Some(kline) => {
+                let values = [
+                    ("open", candle.open, kline.open),
+                    ("high", candle.high, kline.high),
+                    ("low", candle.low, kline.low),
+                    ("close", candle.close, kline.close),
+                    ("volume", candle.volume, kline.volume),
+                ];
+                let differences = values
+                    .iter()
+                    .filter(|(_, resampled, kline)| resampled != kline)
+                    .map(|(name, resampled, kline)| iformat!("{name} {resampled} != {kline}"))
+                    .collect::<Vec<_>>();
+                if differences.is_empty() {
+                    None
+                } else {
+                    Some(iformat!("{candle.open_time} {differences.join(\", \")}"))
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        candles_utils::str_to_datetime,
+        config::symbol_minutes::SymbolMinutes,
+        exchange::{replay_exchange::tests::replay_exchange, Exchange},
+    };
+    use rust_decimal_macros::dec;
+
+    fn fixture_trades() -> Vec<AggTrade> {
+        let start = str_to_datetime("2020-11-11 10:00:00");
+        let end = str_to_datetime("2020-11-11 11:00:00");
+        replay_exchange().agg_trades("BTCUSDT", &Market::Futures, &start, &end).unwrap()
+    }
+
+    #[test]
+    fn bucket_parse_test() {
+        assert_eq!("15m".parse::<Bucket>().unwrap(), Bucket::Interval(Interval::M15));
+        assert_eq!("45s".parse::<Bucket>().unwrap(), Bucket::Seconds(45));
+        assert_eq!("2m".parse::<Bucket>().unwrap(), Bucket::Seconds(120));
+        assert_eq!("45s".parse::<Bucket>().unwrap().minutes(), dec!(0.75));
+        assert!("0s".parse::<Bucket>().is_err());
+        assert!("2x".parse::<Bucket>().is_err());
+        assert!("m".parse::<Bucket>().is_err());
+    }
+
+    #[test]
+    fn trades_to_candles_test() {
+        let trades = fixture_trades();
+        let candles = trades_to_candles(&trades, "BTCUSDT", &Market::Futures, &Bucket::Interval(Interval::M15));
+        assert_eq!(candles.len(), 4);
+        assert_eq!(candles[0].close_time, str_to_datetime("2020-11-11 10:14:59"));
+
+        let symbol_minutes = SymbolMinutes::new("BTCUSDT", &Interval::M15, &Market::Futures);
+        let klines = replay_exchange()
+            .candles(
+                &symbol_minutes,
+                &Some(str_to_datetime("2020-11-11 10:00:00")),
+                &Some(str_to_datetime("2020-11-11 10:45:00")),
+            )
+            .unwrap();
+        assert_eq!(candles_mismatches(&candles, &klines), Vec::<String>::new());
+
+        let mut wrong = candles.clone();
+        wrong[1].high += dec!(1);
+        assert_eq!(candles_mismatches(&wrong, &klines).len(), 1);
+
+        // Interval not offered by exchange, same volume overall
+        let candles = trades_to_candles(&trades, "BTCUSDT", &Market::Futures, &"45s".parse().unwrap());
+        assert!(candles.iter().all(|c| c.close_time - c.open_time == Duration::seconds(44)));
+        assert_eq!(
+            candles.iter().map(|c| c.volume).sum::<Decimal>(),
+            trades.iter().map(|t| t.quantity).sum::<Decimal>()
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use rust_decimal::Decimal;
+
+/// Rounding of decimals to exchange steps (tick size, lot step).
+///
+/// A zero step means there is no filter, value is returned unchanged.
+pub trait DecimalRound {
+    /// Greatest multiple of step less than or equal to value
+    fn floor_step(&self, step: &Decimal) -> Decimal;
+    /// Least multiple of step greater than or equal to value
+    fn ceil_step(&self, step: &Decimal) -> Decimal;
+    /// Nearest multiple of step, halfway rounds away from zero
+    fn round_step(&self, step: &Decimal) -> Decimal;
+}
+
+impl DecimalRound for Decimal {
+    fn floor_step(&self, step: &Decimal) -> Decimal {
+        to_step(self, step, |steps| steps.floor())
+    }
+
+    fn ceil_step(&self, step: &Decimal) -> Decimal {
+        to_step(self, step, |steps| steps.ceil())
+    }
+
+    fn round_step(&self, step: &Decimal) -> Decimal {
+        to_step(self, step, |steps| steps.round())
+    }
+}
+
+fn to_step(value: &Decimal, step: &Decimal, round: fn(Decimal) -> Decimal) -> Decimal {
+    if step.is_zero() {
+        return *value;
+    }
+    let step = step.normalize();
+    (round(*value / step) * step).round_dp(step.scale())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn decimal_round_test() {
+        assert_eq!(dec!(15321.478).floor_step(&dec!(0.01)), dec!(15321.47));
+        assert_eq!(dec!(15321.478).ceil_step(&dec!(0.01)), dec!(15321.48));
+        assert_eq!(dec!(15321.475).round_step(&dec!(0.01)), dec!(15321.48));
+        assert_eq!(dec!(15321.47).ceil_step(&dec!(0.01000000)), dec!(15321.47));
+        assert_eq!(dec!(0.123456789).floor_step(&dec!(0.001)), dec!(0.123));
+        assert_eq!(dec!(17).floor_step(&dec!(5)), dec!(15));
+        assert_eq!(dec!(0.0009).floor_step(&dec!(0.001)), dec!(0));
+        assert_eq!(dec!(1.23456).floor_step(&dec!(0)), dec!(1.23456));
+    }
+}
@@ -1 +1,2 @@
-pub mod log_utils;
\ No newline at end of file
+pub mod decimal_utils;
+pub mod log_utils;